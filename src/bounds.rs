@@ -0,0 +1,71 @@
+use num_bigint::{BigInt};
+use num_traits::{Signed, Zero};
+
+use crate::define::{BigNum, MODULUS};
+use crate::math::matrix::{BigIntMatrix};
+
+
+/// Plaintext bound of a scheme constructed with `new`, until a bound is declared with `set_bound`.
+pub const DEFAULT_PLAIN_BOUND: u64 = 1000;
+
+/// Reasons for a decryption to fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecryptError {
+    /// The ciphertext and the functional evaluation key have different dimensions.
+    DimensionMismatch { expected: usize, actual: usize },
+    /// The result range derived from the bounds does not fit into (-MODULUS/2, MODULUS/2).
+    RangeTooLarge { range: BigInt },
+    /// The result does not lie in [-range, range], i.e. the plaintext or the function exceeds its declared bound.
+    OutOfRange { range: BigInt },
+}
+
+/// Largest absolute value of the entries of `v`, used as the bound of a plaintext or a function.
+pub fn max_abs(v: &[BigInt]) -> BigInt {
+    v.iter().map(|vi| vi.abs()).max().unwrap_or_else(BigInt::zero)
+}
+
+/// Largest absolute value of the entries of a matrix.
+pub fn max_abs_matrix(f: &BigIntMatrix) -> BigInt {
+    let mut m = BigInt::zero();
+    for i in 0..f.n_rows {
+        for j in 0..f.n_cols {
+            let fij = f.get_element(i, j).abs();
+            if fij > m {
+                m = fij;
+            }
+        }
+    }
+    m
+}
+
+/// Largest absolute column sum of a matrix. If the entries of `x` are bounded by `b`, the entries of `x^T P` are
+/// bounded by `b` times this value.
+pub fn max_abs_column_sum(p: &BigIntMatrix) -> BigInt {
+    (0..p.n_cols)
+        .map(|j| (0..p.n_rows).map(|i| p.get_element(i, j).abs()).sum::<BigInt>())
+        .max()
+        .unwrap_or_else(BigInt::zero)
+}
+
+/// Range of an inner product of `len` entries bounded by `x_bound` with `len` entries bounded by `y_bound`.
+pub fn inner_product_range(x_bound: &BigInt, y_bound: &BigInt, len: usize) -> BigInt {
+    x_bound * y_bound * len
+}
+
+/// Range of `x^T F y` for entries of `x`, `y` and the `n_rows x n_cols` matrix `F` bounded by `x_bound`, `y_bound`
+/// and `f_bound` respectively.
+pub fn quadratic_range(x_bound: &BigInt, y_bound: &BigInt, f_bound: &BigInt, n_rows: usize, n_cols: usize) -> BigInt {
+    x_bound * y_bound * f_bound * n_rows * n_cols
+}
+
+/// Solve the discrete logarithm of a decryption result with `dlog`, searching [-range, range].
+pub(crate) fn solve_in_range<F>(range: &BigInt, dlog: F) -> Result<BigInt, DecryptError>
+where
+    F: FnOnce(&BigNum) -> Option<BigInt>,
+{
+    if range * 2 >= *MODULUS {
+        return Err(DecryptError::RangeTooLarge { range: range.clone() });
+    }
+    let bound = BigNum::fromstring(range.to_str_radix(16));
+    dlog(&bound).ok_or_else(|| DecryptError::OutOfRange { range: range.clone() })
+}
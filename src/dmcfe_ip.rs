@@ -1,12 +1,14 @@
 use miracl_core::bls12381::pair;
 use miracl_core::hash256::HASH256;
 use num_bigint::{BigInt};
-use num_traits::{Num};
+use num_traits::{Num, Signed};
 
-use crate::define::{BigNum, G1, G2, Gt, G1Vector, G2Vector, MB, CURVE_ORDER, MODULUS};
+use crate::define::{BigNum, G1, G2, Gt, G2Vector, MB, CURVE_ORDER, MODULUS};
 use crate::math::matrix::BigIntMatrix2x2;
 use crate::utils::{baby_step_giant_step, hash_to_g1, hash_to_g2, reduce};
 use crate::utils::rand_utils::{RandUtilsRand, Sample};
+use crate::math::fixed_point::{FixedPoint, FixedPointError, Fixed, Scale, decode, encode_bound};
+use crate::bounds::{DecryptError, DEFAULT_PLAIN_BOUND, max_abs, inner_product_range, solve_in_range};
 use crate::traits::FunctionalEncryption;
 
 
@@ -27,26 +29,39 @@ use crate::traits::FunctionalEncryption;
 #[derive(Clone)]
 pub struct Dmcfe<const L: usize> {
     /// Index of a client
-    index: usize,
+    pub(crate) index: usize,
     /// Public key  
     pub client_pub_key: G1,
     /// Secret key
-    client_sec_key: BigNum,
+    pub(crate) client_sec_key: BigNum,
     /// Secret share matrix
     share: BigIntMatrix2x2,
     /// Functional secret key
     s: [BigNum; 2],
+    /// Public bound on the absolute value of the plaintexts encrypted by `encrypt`
+    bound: BigInt,
+}
+
+/// Ciphertext of a single number
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DmcfeCipher {
+    pub(crate) c: G1,
+    /// Bound on the absolute value of the plaintext
+    pub bound: BigInt,
 }
 
 /// Functional evaluation key
 #[derive(Debug)]
 pub struct DmcfeDecKey<const L: usize> {
-    key: G2Vector,
-    y: [BigNum; L],
+    pub(crate) key: G2Vector,
+    pub(crate) y: [BigNum; L],
+    /// Bound on the absolute value of the function entries
+    pub bound: BigInt,
 }
 
 impl<const L: usize> FunctionalEncryption for Dmcfe<L> {
-    type CipherText = G1Vector;
+    type CipherText = Vec<DmcfeCipher>;
     type PlainData = [BigInt; L];
     type FEKeyData = [BigInt; L];
     type EvaluationKey = DmcfeDecKey<L>;
@@ -65,19 +80,23 @@ impl<const L: usize> FunctionalEncryption for Dmcfe<L> {
         Dmcfe::<L>::new_single(0) 
     }
 
-    /// Encrypt a number, together with a label. Label should be the same for all clients. 
+    /// Encrypt a vector of numbers with the default label. Only used when there is a single client. The entries must
+    /// be bounded by the plaintext bound of the client, `DEFAULT_PLAIN_BOUND` unless it was changed with `set_bound`.
     ///
     /// # Examples
     ///
     /// ```ignore
     /// use ruby::dmcfe_ip::Dmcfe; 
-    /// let client = Dmcfe::new(0);
-    /// let x = BigInt::from(10);
-    /// let label = "dmcfe-label";
-    /// let cipher = client.encrypt(&x, label);
+    /// let client = Dmcfe::<L>::new();
+    /// let ciphers = client.encrypt(&x);
     /// ``` 
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entry of `x` exceeds the bound declared with `set_bound`, `DEFAULT_PLAIN_BOUND` if none was
+    /// declared. Use `try_encrypt_with_label` to get an error instead.
     fn encrypt(&self, x: &Self::PlainData) -> Self::CipherText {
-        self.encrypt_with_label(x, "dmcfe-label") 
+        self.encrypt_with_label(x, &self.bound, "dmcfe-label")
     }
 
     /// Derive the functional evaluation key for a vector of numbers. Only used when there is a single client.
@@ -118,26 +137,25 @@ impl<const L: usize> FunctionalEncryption for Dmcfe<L> {
         }
         DmcfeDecKey {
             key: fe_key,
-            y: new_y
+            y: new_y,
+            bound: max_abs(y)
         }
     }
 
-    /// Decrypt a ciphertext with the functional evaluation key `dk`, associated with a specified label. The parameter `bound` is the absolute value bound for numbers used in the quadratic polynomial.
+    /// Decrypt a ciphertext with the functional evaluation key `dk`, associated with the default label.
     ///
     /// # Examples
     ///
     /// ```ignore
-    /// // Following the example of `key_comb`
-    /// let bound = BigInt::from(100);
-    /// let xfy = Dmcfe::decrypt(&ciphers, &y[..], &dk, label, &bound); 
+    /// // Following the example of `derive_fe_key`
+    /// let xy = client.decrypt(&ciphers, &dk); 
     /// ```
     fn decrypt(
         &self,
         ciphers: &Self::CipherText,
         dk: &Self::EvaluationKey, 
-        bound: &BigInt,
-    ) -> Option<BigInt> {
-        self.decrypt_with_label(ciphers, dk, bound, "dmcfe-label")
+    ) -> Result<BigInt, DecryptError> {
+        self.decrypt_with_label(ciphers, dk, "dmcfe-label")
     }
 }
 
@@ -156,8 +174,7 @@ impl<const L: usize> Dmcfe<L> {
         let mut rng = RandUtilsRand::new();
         let client_sec_key = rng.sample(&(CURVE_ORDER));
 
-        let client_pub_key = G1::generator();
-        client_pub_key.mul(&client_sec_key);
+        let client_pub_key = G1::generator().mul(&client_sec_key);
 
         let share = BigIntMatrix2x2::new();
         let s = [
@@ -171,9 +188,20 @@ impl<const L: usize> Dmcfe<L> {
             client_sec_key,
             share,
             s,
+            bound: BigInt::from(DEFAULT_PLAIN_BOUND),
         }
     }
 
+    /// The public plaintext bound used by `encrypt`.
+    pub fn bound(&self) -> &BigInt {
+        &self.bound
+    }
+
+    /// Declare the public bound on the absolute value of the plaintexts encrypted by `encrypt`.
+    pub fn set_bound(&mut self, bound: &BigInt) {
+        self.bound = bound.clone();
+    }
+
     /// Set the secret share matrix with all clients' public keys.
     ///
     /// # Examples
@@ -221,7 +249,8 @@ impl<const L: usize> Dmcfe<L> {
         }
     }
 
-    /// Encrypt a number, together with a label. Label should be the same for all clients. 
+    /// Encrypt a number, together with a label. Label should be the same for all clients. `bound` is the bound on
+    /// the absolute value of `x` agreed by the clients, which determines the range searched during decryption.
     ///
     /// # Examples
     ///
@@ -230,9 +259,24 @@ impl<const L: usize> Dmcfe<L> {
     /// let client = Dmcfe::new(0);
     /// let x = BigInt::from(10);
     /// let label = "dmcfe-label";
-    /// let cipher = client.encrypt(&x, label);
+    /// let cipher = client.encrypt_single(&x, &BigInt::from(100), label);
     /// ``` 
-    pub fn encrypt_single(&self, x: &BigInt, label: &str) -> G1 {
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` exceeds `bound`.
+    pub fn encrypt_single(&self, x: &BigInt, bound: &BigInt, label: &str) -> DmcfeCipher {
+        match self.try_encrypt_single(x, bound, label) {
+            Ok(cipher) => cipher,
+            Err(_) => panic!("Malformed input: x exceeds the bound ({})", bound),
+        }
+    }
+
+    /// Encrypt like `encrypt_single`, and return `DecryptError::OutOfRange` if `x` exceeds `bound`.
+    pub fn try_encrypt_single(&self, x: &BigInt, bound: &BigInt, label: &str) -> Result<DmcfeCipher, DecryptError> {
+        if x.abs() > *bound {
+            return Err(DecryptError::OutOfRange { range: bound.clone() });
+        }
         let x = reduce(&x, &MODULUS);
         let x = BigNum::fromstring(x.to_str_radix(16));
         let mut cipher: G1 = G1::new();
@@ -248,18 +292,30 @@ impl<const L: usize> Dmcfe<L> {
         g = g.mul(&x);
         cipher.add(&g);
 
-        cipher
+        Ok(DmcfeCipher {
+            c: cipher,
+            bound: bound.clone()
+        })
     }
 
-    /// Encrypt a vector fo numbers. Only used when there is a single client.
-    pub fn encrypt_with_label(&self, x: &[BigInt; L], label: &str) -> G1Vector {
-        let mut ciphers: G1Vector = Vec::with_capacity(x.len());
+    /// Encrypt a vector fo numbers bounded by `bound`. Only used when there is a single client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entry of `x` exceeds `bound`.
+    pub fn encrypt_with_label(&self, x: &[BigInt; L], bound: &BigInt, label: &str) -> Vec<DmcfeCipher> {
+        let mut ciphers: Vec<DmcfeCipher> = Vec::with_capacity(x.len());
         for i in 0..x.len() {
-            ciphers.push(self.encrypt_single(&(x[i]), label));
+            ciphers.push(self.encrypt_single(&(x[i]), bound, label));
         }
         ciphers
     }
 
+    /// Encrypt like `encrypt_with_label`, and return `DecryptError::OutOfRange` if an entry of `x` exceeds `bound`.
+    pub fn try_encrypt_with_label(&self, x: &[BigInt; L], bound: &BigInt, label: &str) -> Result<Vec<DmcfeCipher>, DecryptError> {
+        x.iter().map(|xi| self.try_encrypt_single(xi, bound, label)).collect()
+    }
+
     /// Derive a share of the functional evaluation key for a vector of numbers.
     ///
     /// # Examples
@@ -329,51 +385,40 @@ impl<const L: usize> Dmcfe<L> {
         }
         DmcfeDecKey {
             key: keys_sum,
-            y: new_y
+            y: new_y,
+            bound: max_abs(y)
         }
     }
 
-    /// Decrypt a ciphertext with the functional evaluation key `dk`, associated with a specified label. The parameter `bound` is the absolute value bound for numbers used in the quadratic polynomial.
+    /// Decrypt a ciphertext with the functional evaluation key `dk`, associated with a specified label. The result
+    /// is searched in [-range, range] with `range = max(ciphers[i].bound) * dk.bound * L`.
     ///
     /// # Examples
     ///
     /// ```ignore
     /// // Following the example of `key_comb`
-    /// let bound = BigInt::from(100);
-    /// let xfy = Dmcfe::decrypt(&ciphers, &y[..], &dk, label, &bound); 
+    /// let xy = clients[0].decrypt_with_label(&ciphers, &dk, label); 
     /// ```
     pub fn decrypt_with_label(
         &self,
-        ciphers: &G1Vector,
+        ciphers: &[DmcfeCipher],
         dk: &DmcfeDecKey<L>, 
-        bound: &BigInt,
         label: &str,
-    ) -> Option<BigInt> {
-        let ylen: isize = L as isize;
-
-        let (g1, mut ciphers_sum, mut cipher_i) = (G1::generator(), G1::new(), G1::new());
-        let g2 = G2::generator();
-
-        ciphers_sum.inf();
-
-        for i in 0..L {
-            let mut temp = dk.y[i]; 
-            cipher_i.copy(&ciphers[i]);
-            temp.rmod(&CURVE_ORDER);
-            cipher_i = cipher_i.mul(&temp);
-            ciphers_sum.add(&cipher_i);
+    ) -> Result<BigInt, DecryptError> {
+        if ciphers.len() != L {
+            return Err(DecryptError::DimensionMismatch { expected: L, actual: ciphers.len() });
         }
 
-        let mut s = pair::ate(&g2, &ciphers_sum);
+        let (g1, g2) = (G1::generator(), G2::generator());
+
+        let mut s = pair::ate(&g2, &weighted_sum(ciphers, &dk.y));
         s = pair::fexp(&s);
 
         let mut t = Gt::new();
         let mut pair: Gt;
         t.one();
-        for i in 0..2 {
-            let ex_label = format!("{} {}", i.to_string(), label);
-            let h = hash_to_g1(&ex_label);
-            pair = pair::ate(&dk.key[i], &h);
+        for (i, h) in label_points(label).iter().enumerate() {
+            pair = pair::ate(&dk.key[i], h);
             pair = pair::fexp(&pair);
             t.mul(&pair);
         }
@@ -384,12 +429,118 @@ impl<const L: usize> Dmcfe<L> {
         pair = pair::fexp(&pair);
 
         //dlog
-        let mut result_bound = BigNum::fromstring(bound.to_str_radix(16));
-        result_bound = result_bound.powmod(&BigNum::new_int(2), &CURVE_ORDER);
-        result_bound = BigNum::modmul(&result_bound, &BigNum::new_int(ylen), &CURVE_ORDER);
+        let x_bound = ciphers.iter().map(|c| &c.bound).max().unwrap();
+        let range = inner_product_range(x_bound, &dk.bound, L);
+        solve_in_range(&range, |bound| baby_step_giant_step(&s, &pair, bound))
+    }
+
+    /// Encrypt a floating point number encoded with `codec`, together with a label. Label should be the same for all
+    /// clients. `bound` is the agreed bound on the absolute value of the (real) number.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let codec = FixedPoint::new(100);
+    /// let cipher = client.encrypt_single_fixed(0.25, 2.0, "dmcfe-label", &codec).unwrap();
+    /// ```
+    pub fn encrypt_single_fixed<T: Copy + Into<f64>>(&self, x: T, bound: f64, label: &str, codec: &FixedPoint) -> Result<Fixed<DmcfeCipher>, FixedPointError> {
+        let int_x = codec.encode(x)?;
+        let int_bound = encode_bound(bound, &codec.scale())?;
+        if int_x.abs() > int_bound {
+            return Err(FixedPointError::OutOfBound { bound: int_bound });
+        }
+        Ok(Fixed {
+            inner: self.encrypt_single(&int_x, &int_bound, label),
+            scale: codec.scale()
+        })
+    }
+
+    /// Encrypt a vector of floating point numbers encoded with `codec`. Only used when there is a single client.
+    /// `bound` is the public bound on the absolute value of the (real) entries.
+    pub fn encrypt_fixed<T: Copy + Into<f64>>(&self, x: &[T], bound: f64, label: &str, codec: &FixedPoint) -> Result<Fixed<Vec<DmcfeCipher>>, FixedPointError> {
+        let int_x: [BigInt; L] = codec.encode_array(x)?;
+        let int_bound = encode_bound(bound, &codec.scale())?;
+        if max_abs(&int_x) > int_bound {
+            return Err(FixedPointError::OutOfBound { bound: int_bound });
+        }
+        Ok(Fixed {
+            inner: self.encrypt_with_label(&int_x, &int_bound, label),
+            scale: codec.scale()
+        })
+    }
+
+    /// Derive the functional evaluation key for a vector of floating point numbers encoded with `codec`. Only used
+    /// when there is a single client.
+    pub fn derive_fe_key_fixed<T: Copy + Into<f64>>(&self, y: &[T], codec: &FixedPoint) -> Result<Fixed<DmcfeDecKey<L>>, FixedPointError> {
+        let int_y: [BigInt; L] = codec.encode_array(y)?;
+        Ok(Fixed {
+            inner: self.derive_fe_key(&int_y),
+            scale: codec.scale()
+        })
+    }
+
+    /// Derive a share of the functional evaluation key for a vector of floating point numbers encoded with `codec`.
+    pub fn derive_fe_key_share_fixed<T: Copy + Into<f64>>(&self, y: &[T], codec: &FixedPoint) -> Result<G2Vector, FixedPointError> {
+        let int_y: [BigInt; L] = codec.encode_array(y)?;
+        Ok(self.derive_fe_key_share(&int_y))
+    }
 
-        baby_step_giant_step(&s, &pair, &result_bound)
+    /// Combining shares derived by `derive_fe_key_share_fixed` into the functional evaluation key.
+    pub fn key_comb_fixed<T: Copy + Into<f64>>(&self, key_shares: &[G2Vector], y: &[T], codec: &FixedPoint) -> Result<Fixed<DmcfeDecKey<L>>, FixedPointError> {
+        let int_y: [BigInt; L] = codec.encode_array(y)?;
+        Ok(Fixed {
+            inner: self.key_comb(key_shares, &int_y),
+            scale: codec.scale()
+        })
+    }
+
+    /// Decrypt ciphertexts of floating point numbers, associated with a specified label. All ciphertexts must have
+    /// the same scale.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // ciphers: Vec<Fixed<DmcfeCipher>>, one from each client
+    /// let dk = clients[0].key_comb_fixed(&fe_key, &y, &codec).unwrap();
+    /// let xy = clients[0].decrypt_with_label_fixed(&ciphers, &dk, label).unwrap();
+    /// ```
+    pub fn decrypt_with_label_fixed(&self, ciphers: &[Fixed<DmcfeCipher>], dk: &Fixed<DmcfeDecKey<L>>, label: &str) -> Result<f64, FixedPointError> {
+        let x_scale = match ciphers.first() {
+            Some(c) => c.scale.clone(),
+            None => return Err(FixedPointError::LengthMismatch { expected: L, actual: 0 }),
+        };
+        if ciphers.iter().any(|c| c.scale != x_scale) {
+            return Err(FixedPointError::ScaleMismatch);
+        }
+        let inner: Vec<DmcfeCipher> = ciphers.iter().map(|c| c.inner.clone()).collect();
+        self.decrypt_fixed_inner(&inner, &x_scale, dk, label)
+    }
+
+    /// Decrypt a ciphertext of floating point numbers. Only used when there is a single client.
+    pub fn decrypt_fixed(&self, ct: &Fixed<Vec<DmcfeCipher>>, dk: &Fixed<DmcfeDecKey<L>>, label: &str) -> Result<f64, FixedPointError> {
+        self.decrypt_fixed_inner(&ct.inner, &ct.scale, dk, label)
+    }
+
+    fn decrypt_fixed_inner(&self, ciphers: &[DmcfeCipher], x_scale: &Scale, dk: &Fixed<DmcfeDecKey<L>>, label: &str) -> Result<f64, FixedPointError> {
+        let scale = x_scale.compose(&dk.scale);
+        let result = self.decrypt_with_label(ciphers, &dk.inner, label)?;
+        Ok(decode(&result, &scale))
     }
 }
 
+/// The inner product `sum_i y_i * c_i` of the ciphertexts with the function vector `y`.
+pub(crate) fn weighted_sum(ciphers: &[DmcfeCipher], y: &[BigNum]) -> G1 {
+    let mut sum = G1::new();
+    sum.inf();
+    for (cipher, yi) in ciphers.iter().zip(y.iter()) {
+        let mut yi = *yi;
+        yi.rmod(&CURVE_ORDER);
+        sum.add(&cipher.c.mul(&yi));
+    }
+    sum
+}
 
+/// The points `H(0 || label)` and `H(1 || label)` the ciphertexts with `label` are masked with.
+pub(crate) fn label_points(label: &str) -> [G1; 2] {
+    [0, 1].map(|i| hash_to_g1(&format!("{} {}", i, label)))
+}
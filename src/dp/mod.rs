@@ -0,0 +1,242 @@
+pub mod sampler;
+
+use num_bigint::{BigInt};
+use num_traits::{ToPrimitive};
+use std::collections::HashMap;
+
+use crate::bounds::{DecryptError};
+use crate::dmcfe_ip::{Dmcfe, DmcfeCipher, DmcfeDecKey};
+use crate::simple_ip::{Sip, SipCipher, SipDk};
+use crate::utils::rand_utils::{RandUtilsRng};
+use crate::traits::FunctionalEncryption;
+use self::sampler::{DiscreteNoise, Ratio};
+
+
+/// Noise distribution added by the encryptors to each plaintext coordinate.
+#[derive(Debug, Clone)]
+pub enum NoiseMechanism {
+    /// Discrete Laplace noise with the given scale.
+    DiscreteLaplace { scale: Ratio },
+    /// Discrete Gaussian noise with the given variance parameter.
+    DiscreteGaussian { sigma2: Ratio },
+}
+
+/// Differential privacy configuration shared by the encryptors and the aggregator.
+///
+/// Every encryptor adds independent noise to each coordinate it encrypts. Two neighbouring datasets differ in a single
+/// coordinate by at most `sensitivity`, in the (integer) unit of the plaintext.
+#[derive(Debug, Clone)]
+pub struct DpConfig {
+    pub mechanism: NoiseMechanism,
+    pub sensitivity: BigInt,
+}
+
+/// An (epsilon, delta) differential privacy guarantee.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DpGuarantee {
+    pub epsilon: f64,
+    pub delta: f64,
+}
+
+/// A decryption result together with the privacy guarantee of everything released for its label so far.
+#[derive(Debug, Clone)]
+pub struct DpResult {
+    pub value: BigInt,
+    pub guarantee: DpGuarantee,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DpError {
+    /// Releasing the result would exceed the privacy budget of the label.
+    BudgetExceeded { label: String, epsilon: f64, budget: f64 },
+    /// The noisy result could not be decrypted.
+    Decrypt(DecryptError),
+}
+
+impl DpConfig {
+    /// Constructs a configuration where each of `n` encryptors adds a share of discrete Gaussian noise, such that the
+    /// noise of the sum has variance `sigma2`.
+    pub fn distributed_gaussian(sigma2: &Ratio, n: usize, sensitivity: &BigInt) -> Self {
+        Self {
+            mechanism: NoiseMechanism::DiscreteGaussian {
+                sigma2: Ratio::new(&sigma2.num, &(&sigma2.den * BigInt::from(n))),
+            },
+            sensitivity: sensitivity.clone(),
+        }
+    }
+
+    /// Sample the noise for a single coordinate.
+    pub fn sample_noise(&self, rng: &mut RandUtilsRng) -> BigInt {
+        match &self.mechanism {
+            NoiseMechanism::DiscreteLaplace { scale } => rng.sample_discrete_laplace(scale),
+            NoiseMechanism::DiscreteGaussian { sigma2 } => rng.sample_discrete_gaussian(sigma2),
+        }
+    }
+
+    /// Add independent noise to every coordinate of `x`. Used by encryptors before `encrypt`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let noisy_x = config.perturb(&x);
+    /// let cipher = sip.encrypt(&noisy_x);
+    /// ```
+    pub fn perturb<const L: usize>(&self, x: &[BigInt; L]) -> [BigInt; L] {
+        let mut rng = RandUtilsRng::new();
+        array_init::array_init(|i| &x[i] + self.sample_noise(&mut rng))
+    }
+
+    /// Bound on the absolute value of a single noise sample, exceeded with probability below 2^-40.
+    /// Add it to the plaintext bound when encrypting noisy values with a declared bound.
+    pub fn noise_bound(&self) -> BigInt {
+        let b = match &self.mechanism {
+            NoiseMechanism::DiscreteLaplace { scale } => scale.to_f64() * 28.0,
+            NoiseMechanism::DiscreteGaussian { sigma2 } => sigma2.to_f64().sqrt() * 7.5,
+        };
+        BigInt::from(b.ceil() as i64)
+    }
+
+    /// Guarantee of releasing any number of linear functions `ys` of the noisy plaintext.
+    ///
+    /// For discrete Laplace noise, the noisy plaintext itself is `sensitivity / scale`-DP and the functions are
+    /// post-processing. For discrete Gaussian noise, the released vector `Y (x + e)` is Gaussian with covariance
+    /// `sigma2 * Y Y^T`, and changing coordinate `j` shifts it by `sensitivity * Y e_j`. This is
+    /// `rho = sensitivity^2 * max_j (P_jj) / (2 * sigma2)`-zCDP, with `P` the projection onto the row space of `Y`,
+    /// which is converted to (epsilon, delta)-DP. The bound follows the analysis of the continuous Gaussian.
+    pub fn guarantee(&self, ys: &[Vec<BigInt>], delta: f64) -> DpGuarantee {
+        let sensitivity = self.sensitivity.to_f64().unwrap();
+        match &self.mechanism {
+            NoiseMechanism::DiscreteLaplace { scale } => DpGuarantee {
+                epsilon: sensitivity / scale.to_f64(),
+                delta: 0.0,
+            },
+            NoiseMechanism::DiscreteGaussian { sigma2 } => {
+                let rho = sensitivity * sensitivity * max_leverage(ys) / (2.0 * sigma2.to_f64());
+                DpGuarantee {
+                    epsilon: rho + 2.0 * (rho * (1.0 / delta).ln()).sqrt(),
+                    delta,
+                }
+            }
+        }
+    }
+}
+
+/// Largest diagonal entry of the projection onto the row space of `ys`.
+fn max_leverage(ys: &[Vec<BigInt>]) -> f64 {
+    // Modified Gram-Schmidt on the rows
+    let mut basis: Vec<Vec<f64>> = Vec::with_capacity(ys.len());
+    for y in ys.iter() {
+        let mut v: Vec<f64> = y.iter().map(|yi| yi.to_f64().unwrap()).collect();
+        let norm0 = v.iter().map(|vi| vi * vi).sum::<f64>().sqrt();
+        for q in basis.iter() {
+            let dot: f64 = v.iter().zip(q.iter()).map(|(vi, qi)| vi * qi).sum();
+            v.iter_mut().zip(q.iter()).for_each(|(vi, qi)| *vi -= dot * qi);
+        }
+        let norm = v.iter().map(|vi| vi * vi).sum::<f64>().sqrt();
+        if norm > 1e-9 * norm0 {
+            basis.push(v.iter().map(|vi| vi / norm).collect());
+        }
+    }
+    let dim = ys.iter().map(|y| y.len()).max().unwrap_or(0);
+    (0..dim)
+        .map(|j| basis.iter().map(|q| q[j] * q[j]).sum::<f64>())
+        .fold(0.0, f64::max)
+        .min(1.0)
+}
+
+/// Per-label privacy accounting for the aggregator.
+///
+/// A label identifies one batch of noisy ciphertexts, e.g. a round of DMCFE encryptions. All functions decrypted
+/// under a label are recorded, and a decryption is refused if the guarantee of releasing all of them would exceed
+/// the budget.
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut accountant = PrivacyAccountant::new(config, 1.0, 1e-6);
+/// let res = accountant.decrypt_dmcfe(&client, &ciphers, &dk, &y, label).unwrap();
+/// println!("{} with epsilon {}", res.value, res.guarantee.epsilon);
+/// ```
+#[derive(Debug)]
+pub struct PrivacyAccountant {
+    pub config: DpConfig,
+    pub epsilon_budget: f64,
+    pub delta: f64,
+    released: HashMap<String, Vec<Vec<BigInt>>>,
+}
+
+impl PrivacyAccountant {
+    pub fn new(config: DpConfig, epsilon_budget: f64, delta: f64) -> Self {
+        Self {
+            config,
+            epsilon_budget,
+            delta,
+            released: HashMap::new(),
+        }
+    }
+
+    /// Guarantee of everything released under `label` so far.
+    pub fn spent(&self, label: &str) -> DpGuarantee {
+        match self.released.get(label) {
+            Some(ys) => self.config.guarantee(ys, self.delta),
+            None => DpGuarantee { epsilon: 0.0, delta: 0.0 },
+        }
+    }
+
+    /// Charge the release of the function `y` under `label` to the budget.
+    pub fn charge(&mut self, label: &str, y: &[BigInt]) -> Result<DpGuarantee, DpError> {
+        let (ys, guarantee) = self.tentative(label, y)?;
+        self.released.insert(label.to_string(), ys);
+        Ok(guarantee)
+    }
+
+    /// Decrypt a `Sip` ciphertext of a noisy vector.
+    pub fn decrypt_sip<const L: usize>(
+        &mut self,
+        sip: &Sip<L>,
+        cipher: &SipCipher<L>,
+        dk: &SipDk<L>,
+        y: &[BigInt; L],
+        label: &str,
+    ) -> Result<DpResult, DpError> {
+        self.release(label, y, || sip.decrypt(cipher, dk))
+    }
+
+    /// Decrypt `Dmcfe` ciphertexts of noisy values encrypted under `label`. The bounds of the ciphertexts should
+    /// include `DpConfig::noise_bound`.
+    pub fn decrypt_dmcfe<const L: usize>(
+        &mut self,
+        client: &Dmcfe<L>,
+        ciphers: &[DmcfeCipher],
+        dk: &DmcfeDecKey<L>,
+        y: &[BigInt; L],
+        label: &str,
+    ) -> Result<DpResult, DpError> {
+        self.release(label, y, || client.decrypt_with_label(ciphers, dk, label))
+    }
+
+    fn release<F>(&mut self, label: &str, y: &[BigInt], decrypt: F) -> Result<DpResult, DpError>
+    where
+        F: FnOnce() -> Result<BigInt, DecryptError>,
+    {
+        let (ys, guarantee) = self.tentative(label, y)?;
+        let value = decrypt().map_err(DpError::Decrypt)?;
+        self.released.insert(label.to_string(), ys);
+        Ok(DpResult { value, guarantee })
+    }
+
+    /// Functions released under `label` if `y` is released too, and their guarantee.
+    fn tentative(&self, label: &str, y: &[BigInt]) -> Result<(Vec<Vec<BigInt>>, DpGuarantee), DpError> {
+        let mut ys = self.released.get(label).cloned().unwrap_or_default();
+        ys.push(y.to_vec());
+        let guarantee = self.config.guarantee(&ys, self.delta);
+        if guarantee.epsilon > self.epsilon_budget {
+            return Err(DpError::BudgetExceeded {
+                label: label.to_string(),
+                epsilon: guarantee.epsilon,
+                budget: self.epsilon_budget,
+            });
+        }
+        Ok((ys, guarantee))
+    }
+}
@@ -0,0 +1,126 @@
+use num_bigint::{BigInt};
+use num_traits::{Signed, ToPrimitive, Zero, One};
+
+use crate::utils::rand_utils::{RandUtilsRng, Sample};
+
+
+/// A non-negative rational number `num / den`, used to specify noise parameters exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ratio {
+    pub num: BigInt,
+    pub den: BigInt,
+}
+
+impl Ratio {
+    pub fn new(num: &BigInt, den: &BigInt) -> Self {
+        if num.is_negative() || !den.is_positive() {
+            panic!("Malformed input: ratio {} / {} must be non-negative with a positive denominator", num, den);
+        }
+        Self {
+            num: num.clone(),
+            den: den.clone(),
+        }
+    }
+
+    pub fn from_int(n: i64) -> Self {
+        Self::new(&BigInt::from(n), &BigInt::one())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num.to_f64().unwrap() / self.den.to_f64().unwrap()
+    }
+}
+
+/// Exact samplers over the integers, following
+///
+/// Canonne, C. L., Kamath, G., & Steinke, T. (2020). The Discrete Gaussian for Differential Privacy. NeurIPS 2020.
+///
+/// Link: https://arxiv.org/abs/2004.00010
+///
+/// All randomness is drawn as uniform integers, and no floating point arithmetic is involved.
+pub trait DiscreteNoise {
+    /// Sample from Bernoulli(`num / den`), where `0 <= num <= den`.
+    fn sample_bernoulli(&mut self, num: &BigInt, den: &BigInt) -> bool;
+
+    /// Sample from Bernoulli(exp(-`gamma`)).
+    fn sample_bernoulli_exp(&mut self, gamma: &Ratio) -> bool;
+
+    /// Sample from the discrete Laplace distribution with the given scale, i.e. `P[x] ∝ exp(-|x| / scale)`.
+    fn sample_discrete_laplace(&mut self, scale: &Ratio) -> BigInt;
+
+    /// Sample from the discrete Gaussian distribution with the given variance parameter, i.e. `P[x] ∝ exp(-x^2 / (2 sigma2))`.
+    fn sample_discrete_gaussian(&mut self, sigma2: &Ratio) -> BigInt;
+}
+
+impl DiscreteNoise for RandUtilsRng {
+    fn sample_bernoulli(&mut self, num: &BigInt, den: &BigInt) -> bool {
+        &self.sample(den) < num
+    }
+
+    fn sample_bernoulli_exp(&mut self, gamma: &Ratio) -> bool {
+        if gamma.num <= gamma.den {
+            // Algorithm 1 of the paper, for gamma in [0, 1]
+            let mut k = BigInt::one();
+            while self.sample_bernoulli(&gamma.num, &(&gamma.den * &k)) {
+                k += 1;
+            }
+            return (k % 2u32).is_one();
+        }
+
+        let one = Ratio::from_int(1);
+        let floor = &gamma.num / &gamma.den;
+        let mut i = BigInt::zero();
+        while i < floor {
+            if !self.sample_bernoulli_exp(&one) {
+                return false;
+            }
+            i += 1;
+        }
+        let rest = Ratio::new(&(&gamma.num - &floor * &gamma.den), &gamma.den);
+        self.sample_bernoulli_exp(&rest)
+    }
+
+    fn sample_discrete_laplace(&mut self, scale: &Ratio) -> BigInt {
+        if scale.num.is_zero() {
+            return BigInt::zero();
+        }
+        // Algorithm 2 of the paper, with scale t / s
+        let (t, s) = (&scale.num, &scale.den);
+        let one = Ratio::from_int(1);
+        loop {
+            let u = self.sample(t);
+            if !self.sample_bernoulli_exp(&Ratio::new(&u, t)) {
+                continue;
+            }
+            let mut v = BigInt::zero();
+            while self.sample_bernoulli_exp(&one) {
+                v += 1;
+            }
+            let x = u + t * v;
+            let y = x / s;
+            let negative = self.sample_bernoulli(&BigInt::one(), &BigInt::from(2));
+            if negative && y.is_zero() {
+                continue;
+            }
+            return if negative { -y } else { y };
+        }
+    }
+
+    fn sample_discrete_gaussian(&mut self, sigma2: &Ratio) -> BigInt {
+        if sigma2.num.is_zero() {
+            return BigInt::zero();
+        }
+        // Algorithm 3 of the paper
+        let (num, den) = (&sigma2.num, &sigma2.den);
+        let t = (num / den).sqrt() + 1;
+        let laplace_scale = Ratio::new(&t, &BigInt::one());
+        loop {
+            let y = self.sample_discrete_laplace(&laplace_scale);
+            let diff = y.abs() * den * &t - num;
+            let gamma = Ratio::new(&(&diff * &diff), &(BigInt::from(2) * num * den * &t * &t));
+            if self.sample_bernoulli_exp(&gamma) {
+                return y;
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use num_bigint::{BigInt};
+use num_traits::Zero;
+use std::collections::HashMap;
+
+use crate::define::{MODULUS};
+use crate::math::matrix::{BigIntMatrix};
+use crate::utils::{reduce};
+use crate::traits::FunctionalEncryption;
+
+
+/// Function data that can be viewed as a vector of coefficients acting linearly on the (possibly tensored) plaintext.
+///
+/// For `Sip` and `Dmcfe`, a key for `y` reveals `<x, y>`, so the vector is `y` itself. For `Sgp`, a key for `F` reveals
+/// `x^T F y = <vec(F), vec(x y^T)>`, so the vector is `F` flattened in row-major order.
+pub trait FunctionVector {
+    fn to_function_vector(&self) -> Vec<BigInt>;
+}
+
+impl<const L: usize> FunctionVector for [BigInt; L] {
+    fn to_function_vector(&self) -> Vec<BigInt> {
+        self.to_vec()
+    }
+}
+
+impl FunctionVector for BigIntMatrix {
+    fn to_function_vector(&self) -> Vec<BigInt> {
+        let mut v: Vec<BigInt> = Vec::with_capacity(self.n_rows * self.n_cols);
+        for i in 0..self.n_rows {
+            for j in 0..self.n_cols {
+                v.push(self.get_element(i, j).clone());
+            }
+        }
+        v
+    }
+}
+
+/// Leakage budget enforced by a `KeyIssuer`.
+///
+/// A decryptor holding keys for `k` linearly independent function vectors learns the projection of the plaintext onto
+/// a `k`-dimensional subspace. Once `k` reaches the dimension of the function vectors, the plaintext (or `x y^T` for
+/// `Sgp`) is recovered entirely. The budget caps `k` per requester and, optionally, across all requesters together to
+/// account for colluding decryptors.
+#[derive(Debug, Clone)]
+pub struct IssuancePolicy {
+    /// Maximal rank of the span of the functions issued to one requester.
+    pub max_rank_per_requester: usize,
+    /// Maximal rank of the span of the functions issued to all requesters.
+    pub max_rank_total: Option<usize>,
+}
+
+impl IssuancePolicy {
+    /// Constructs a policy that only limits the rank per requester.
+    pub fn new(max_rank_per_requester: usize) -> Self {
+        Self {
+            max_rank_per_requester,
+            max_rank_total: None,
+        }
+    }
+}
+
+/// Reasons for refusing a key request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssuanceError {
+    /// The requested function is not on the allow-list.
+    NotAllowListed,
+    /// The requested function does not have the dimension of the previously approved functions.
+    DimensionMismatch { expected: usize, actual: usize },
+    /// Issuing the key would raise the rank of the requester's span above the budget.
+    RequesterBudgetExceeded { requester: String, rank: usize, budget: usize },
+    /// Issuing the key would raise the rank of the span of all issued keys above the budget.
+    TotalBudgetExceeded { rank: usize, budget: usize },
+}
+
+/// An entry of the issuance log.
+#[derive(Debug, Clone)]
+pub struct IssuanceRecord {
+    /// Identity of the requester.
+    pub requester: String,
+    /// The function vector the key was derived for, reduced modulo `MODULUS`.
+    pub function: Vec<BigInt>,
+    /// Rank of the requester's span after issuing the key.
+    pub rank: usize,
+}
+
+/// Span of a set of vectors over the field of order `MODULUS`, kept in row echelon form.
+#[derive(Debug, Clone, Default)]
+struct LinearSpan {
+    /// Basis rows, each normalized to 1 at its pivot and zero at the pivots of the rows before it.
+    rows: Vec<(usize, Vec<BigInt>)>,
+}
+
+impl LinearSpan {
+    fn rank(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Reduce `v` by the basis. The result is zero iff `v` lies in the span.
+    fn residue(&self, v: &[BigInt]) -> Vec<BigInt> {
+        let mut r: Vec<BigInt> = v.iter().map(|vi| reduce(vi, &MODULUS)).collect();
+        for (pivot, row) in self.rows.iter() {
+            let coef = r[*pivot].clone();
+            if coef.is_zero() {
+                continue;
+            }
+            for (rj, bj) in r.iter_mut().zip(row.iter()) {
+                *rj = reduce(&(&*rj - &coef * bj), &MODULUS);
+            }
+        }
+        r
+    }
+
+    /// Rank of the span after adding `v`, without adding it.
+    fn rank_with(&self, v: &[BigInt]) -> usize {
+        if self.residue(v).iter().all(|ri| ri.is_zero()) {
+            self.rank()
+        } else {
+            self.rank() + 1
+        }
+    }
+
+    fn insert(&mut self, v: &[BigInt]) {
+        let r = self.residue(v);
+        if let Some(pivot) = r.iter().position(|ri| !ri.is_zero()) {
+            let exp = &*MODULUS - BigInt::from(2);
+            let inv = r[pivot].modpow(&exp, &MODULUS);
+            let row = r.iter().map(|ri| reduce(&(ri * &inv), &MODULUS)).collect();
+            self.rows.push((pivot, row));
+        }
+    }
+}
+
+/// Key issuance service enforcing an allow-list of approved functions and a leakage budget.
+///
+/// Every issued function is recorded, and the rank of the span of the functions issued to each requester is tracked.
+/// A request is refused if the function is not allow-listed, or if it would raise a tracked rank above the budget.
+/// Requests for functions already in the requester's span do not consume budget.
+///
+/// # Examples
+///
+/// ```ignore
+/// let sip = Sip::<L>::new();
+/// let mut issuer = KeyIssuer::new(sip, IssuancePolicy::new(L - 1));
+/// issuer.approve(&y);
+/// let dk = issuer.issue("analyst", &y).unwrap();
+/// ```
+pub struct KeyIssuer<S: FunctionalEncryption> {
+    fe: S,
+    policy: IssuancePolicy,
+    allow_list: Vec<Vec<BigInt>>,
+    spans: HashMap<String, LinearSpan>,
+    total_span: LinearSpan,
+    log: Vec<IssuanceRecord>,
+}
+
+impl<S> KeyIssuer<S>
+where
+    S: FunctionalEncryption,
+    S::FEKeyData: FunctionVector,
+{
+    /// Constructs a new `KeyIssuer` holding the master secret key of `fe`.
+    pub fn new(fe: S, policy: IssuancePolicy) -> Self {
+        Self {
+            fe,
+            policy,
+            allow_list: Vec::new(),
+            spans: HashMap::new(),
+            total_span: LinearSpan::default(),
+            log: Vec::new(),
+        }
+    }
+
+    /// The underlying functional encryption scheme, e.g. for encryption.
+    pub fn scheme(&self) -> &S {
+        &self.fe
+    }
+
+    pub fn policy(&self) -> &IssuancePolicy {
+        &self.policy
+    }
+
+    /// Add a function to the allow-list.
+    pub fn approve(&mut self, f: &S::FEKeyData) -> Result<(), IssuanceError> {
+        let v = self.normalize(f)?;
+        if !self.allow_list.contains(&v) {
+            self.allow_list.push(v);
+        }
+        Ok(())
+    }
+
+    /// Whether a function is on the allow-list.
+    pub fn is_approved(&self, f: &S::FEKeyData) -> bool {
+        match self.normalize(f) {
+            Ok(v) => self.allow_list.contains(&v),
+            Err(_) => false,
+        }
+    }
+
+    /// Rank of the span of the functions issued to `requester`.
+    pub fn rank(&self, requester: &str) -> usize {
+        self.spans.get(requester).map_or(0, |span| span.rank())
+    }
+
+    /// Rank of the span of the functions issued to all requesters.
+    pub fn total_rank(&self) -> usize {
+        self.total_span.rank()
+    }
+
+    /// Remaining number of independent functions that can be issued to `requester`.
+    pub fn remaining_budget(&self, requester: &str) -> usize {
+        self.policy.max_rank_per_requester.saturating_sub(self.rank(requester))
+    }
+
+    /// All issued keys, in issuance order.
+    pub fn issued(&self) -> &[IssuanceRecord] {
+        &self.log
+    }
+
+    /// Issued keys of one requester, in issuance order.
+    pub fn issued_to<'a>(&'a self, requester: &'a str) -> impl Iterator<Item = &'a IssuanceRecord> + 'a {
+        self.log.iter().filter(move |rec| rec.requester == requester)
+    }
+
+    /// Derive a functional evaluation key for `requester` if the policy allows it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// match issuer.issue("analyst", &y) {
+    ///     Ok(dk) => { /* hand out dk */ }
+    ///     Err(IssuanceError::RequesterBudgetExceeded { .. }) => { /* refuse */ }
+    ///     Err(e) => panic!("{:?}", e),
+    /// }
+    /// ```
+    pub fn issue(&mut self, requester: &str, f: &S::FEKeyData) -> Result<S::EvaluationKey, IssuanceError> {
+        let v = self.normalize(f)?;
+        if !self.allow_list.contains(&v) {
+            return Err(IssuanceError::NotAllowListed);
+        }
+
+        let rank = self.spans.get(requester).map_or_else(
+            || LinearSpan::default().rank_with(&v),
+            |span| span.rank_with(&v)
+        );
+        if rank > self.policy.max_rank_per_requester {
+            return Err(IssuanceError::RequesterBudgetExceeded {
+                requester: requester.to_string(),
+                rank,
+                budget: self.policy.max_rank_per_requester,
+            });
+        }
+        if let Some(budget) = self.policy.max_rank_total {
+            let total_rank = self.total_span.rank_with(&v);
+            if total_rank > budget {
+                return Err(IssuanceError::TotalBudgetExceeded { rank: total_rank, budget });
+            }
+        }
+
+        self.spans.entry(requester.to_string()).or_default().insert(&v);
+        self.total_span.insert(&v);
+        self.log.push(IssuanceRecord {
+            requester: requester.to_string(),
+            function: v,
+            rank,
+        });
+        Ok(self.fe.derive_fe_key(f))
+    }
+
+    /// Reduce a function modulo `MODULUS` and check its dimension against the previously seen functions.
+    fn normalize(&self, f: &S::FEKeyData) -> Result<Vec<BigInt>, IssuanceError> {
+        let v: Vec<BigInt> = f.to_function_vector().iter().map(|vi| reduce(vi, &MODULUS)).collect();
+        match self.allow_list.first().map(|a| a.len()) {
+            Some(dim) if dim != v.len() => Err(IssuanceError::DimensionMismatch { expected: dim, actual: v.len() }),
+            _ => Ok(v),
+        }
+    }
+}
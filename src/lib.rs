@@ -15,3 +15,6 @@ pub mod simple_ip;
 pub mod ml;
 pub mod zk;
 pub mod traits;
+pub mod bounds;
+pub mod key_issuance;
+pub mod dp;
@@ -0,0 +1,169 @@
+use num_bigint::{BigInt};
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive};
+use std::convert::TryInto;
+
+use crate::define::{MODULUS};
+use crate::math::matrix::{BigIntMatrix};
+use crate::bounds::{DecryptError};
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixedPointError {
+    /// The input is NaN or infinite.
+    NotFinite(f64),
+    /// The encoded value, or the range of a result, does not fit into (-MODULUS/2, MODULUS/2).
+    Overflow,
+    /// The input does not have the expected length.
+    LengthMismatch { expected: usize, actual: usize },
+    /// Values with different scales were combined.
+    ScaleMismatch,
+    /// An encoded input exceeds its declared bound.
+    OutOfBound { bound: BigInt },
+    /// The underlying decryption failed.
+    Decrypt(DecryptError),
+}
+
+impl From<DecryptError> for FixedPointError {
+    fn from(e: DecryptError) -> Self {
+        FixedPointError::Decrypt(e)
+    }
+}
+
+/// Scale of a fixed-point value, kept as the list of the scales it is composed of.
+///
+/// An encoded vector has a single factor. The inner product of two encoded vectors has scale `sx * sy`
+/// (scale² for equal scales), and the quadratic form `x^T F y` has scale `sx * sf * sy` (scale³).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scale {
+    pub factors: Vec<BigInt>,
+}
+
+impl Scale {
+    pub fn new(scale: &BigInt) -> Self {
+        Self {
+            factors: vec![scale.clone()],
+        }
+    }
+
+    /// Scale of a product of values with scales `self` and `other`.
+    pub fn compose(&self, other: &Scale) -> Self {
+        let mut factors = self.factors.clone();
+        factors.extend_from_slice(&other.factors);
+        Self {
+            factors
+        }
+    }
+
+    /// The factor the encoded value is multiplied by.
+    pub fn product(&self) -> BigInt {
+        self.factors.iter().product()
+    }
+
+    /// The largest factor, used to encode bounds that hold for all components.
+    pub fn max(&self) -> BigInt {
+        self.factors.iter().max().cloned().unwrap_or_else(BigInt::one)
+    }
+}
+
+/// A ciphertext, key or plaintext together with the scale of the values it holds.
+#[derive(Debug, Clone)]
+pub struct Fixed<T> {
+    pub inner: T,
+    pub scale: Scale,
+}
+
+/// Fixed-point encoder and decoder between floating point values and the integers taken by the FE schemes.
+///
+/// A value `v` is encoded as `round(v * scale)`. Encoded values must lie in (-MODULUS/2, MODULUS/2) to be
+/// decoded unambiguously, which is checked during encoding.
+///
+/// # Examples
+///
+/// ```
+/// use ruby::math::fixed_point::FixedPoint;
+/// let codec = FixedPoint::new(100);
+/// let x: [num_bigint::BigInt; 2] = codec.encode_array(&[0.5f32, -1.25f32]).unwrap();
+/// assert_eq!(x[1], num_bigint::BigInt::from(-125));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedPoint {
+    pub scale: BigInt,
+}
+
+impl FixedPoint {
+    pub fn new(scale: u64) -> Self {
+        if scale == 0 {
+            panic!("Malformed input: scale must be positive");
+        }
+        Self {
+            scale: BigInt::from(scale),
+        }
+    }
+
+    pub fn scale(&self) -> Scale {
+        Scale::new(&self.scale)
+    }
+
+    /// Encode a single value.
+    pub fn encode<T: Copy + Into<f64>>(&self, v: T) -> Result<BigInt, FixedPointError> {
+        let v: f64 = v.into();
+        let scaled = (v * self.scale.to_f64().unwrap()).round();
+        if !scaled.is_finite() {
+            return Err(FixedPointError::NotFinite(v));
+        }
+        let e = BigInt::from_f64(scaled).ok_or(FixedPointError::NotFinite(v))?;
+        check_range(&e)?;
+        Ok(e)
+    }
+
+    pub fn encode_vec<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Vec<BigInt>, FixedPointError> {
+        x.iter().map(|&xi| self.encode(xi)).collect()
+    }
+
+    /// Encode a slice into the fixed-length arrays taken by the schemes.
+    pub fn encode_array<T: Copy + Into<f64>, const L: usize>(&self, x: &[T]) -> Result<[BigInt; L], FixedPointError> {
+        if x.len() != L {
+            return Err(FixedPointError::LengthMismatch { expected: L, actual: x.len() });
+        }
+        let v = self.encode_vec(x)?;
+        Ok(v.try_into().unwrap())
+    }
+
+    /// Encode a matrix given in row-major order.
+    pub fn encode_matrix<T: Copy + Into<f64>>(&self, a: &[T], n_rows: usize, n_cols: usize) -> Result<BigIntMatrix, FixedPointError> {
+        if a.len() != n_rows * n_cols {
+            return Err(FixedPointError::LengthMismatch { expected: n_rows * n_cols, actual: a.len() });
+        }
+        let v = self.encode_vec(a)?;
+        Ok(BigIntMatrix::new_bigints(&v, n_rows, n_cols))
+    }
+
+    /// Decode a single value encoded by this codec.
+    pub fn decode(&self, v: &BigInt) -> f64 {
+        decode(v, &self.scale())
+    }
+}
+
+/// Decode a value of the given scale, e.g. the result of a decryption.
+pub fn decode(v: &BigInt, scale: &Scale) -> f64 {
+    v.to_f64().unwrap() / scale.product().to_f64().unwrap()
+}
+
+/// Encode an absolute bound on real values for values of the given scale, rounding up.
+pub fn encode_bound(bound: f64, scale: &Scale) -> Result<BigInt, FixedPointError> {
+    let scaled = (bound.abs() * scale.max().to_f64().unwrap()).ceil();
+    if !scaled.is_finite() {
+        return Err(FixedPointError::NotFinite(bound));
+    }
+    let e = BigInt::from_f64(scaled).ok_or(FixedPointError::NotFinite(bound))?;
+    check_range(&e)?;
+    Ok(e)
+}
+
+/// Check that `v` can be represented unambiguously modulo `MODULUS`.
+pub fn check_range(v: &BigInt) -> Result<(), FixedPointError> {
+    if v.abs() * 2 >= *MODULUS {
+        return Err(FixedPointError::Overflow);
+    }
+    Ok(())
+}
@@ -1 +1,2 @@
 pub mod matrix;
+pub mod fixed_point;
@@ -0,0 +1,151 @@
+use crate::dmcfe_ip::{DmcfeCipher};
+use crate::math::fixed_point::{Fixed};
+use crate::ml::linear_model::{LinearModel, LinearModelService, ModelError, Score};
+
+
+/// How the scores of a linear model are turned into a class.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Binary logistic regression over the single output of the model. The positive class is predicted if its
+    /// probability reaches `threshold`.
+    Logistic { threshold: f64, positive: String, negative: String },
+    /// Multi-class softmax regression over all outputs of the model, predicting the argmax.
+    Softmax,
+}
+
+/// A predicted class together with the probabilities of all classes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub class: String,
+    pub probabilities: Vec<Score>,
+}
+
+pub fn sigmoid(z: f64) -> f64 {
+    if z >= 0.0 {
+        1.0 / (1.0 + (-z).exp())
+    } else {
+        let e = z.exp();
+        e / (1.0 + e)
+    }
+}
+
+/// Softmax of `z`, computed stably by shifting with the maximum.
+pub fn softmax(z: &[f64]) -> Vec<f64> {
+    let max = z.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let e: Vec<f64> = z.iter().map(|zi| (zi - max).exp()).collect();
+    let sum: f64 = e.iter().sum();
+    e.iter().map(|ei| ei / sum).collect()
+}
+
+/// Apply a decision rule to the scores of a linear model. A logistic decision takes exactly one score, a softmax
+/// decision at least one.
+pub fn decide(decision: &Decision, scores: &[Score]) -> Result<Prediction, ModelError> {
+    match decision {
+        Decision::Logistic { .. } if scores.len() != 1 => Err(ModelError::UnsupportedOutputs(scores.len())),
+        Decision::Softmax if scores.is_empty() => Err(ModelError::UnsupportedOutputs(0)),
+        Decision::Logistic { threshold, positive, negative } => {
+            let p = sigmoid(scores[0].value);
+            let class = if p >= *threshold { positive } else { negative };
+            Ok(Prediction {
+                class: class.clone(),
+                probabilities: vec![
+                    Score { name: negative.clone(), value: 1.0 - p },
+                    Score { name: positive.clone(), value: p },
+                ],
+            })
+        }
+        Decision::Softmax => {
+            let p = softmax(&scores.iter().map(|s| s.value).collect::<Vec<f64>>());
+            let probabilities: Vec<Score> = scores
+                .iter()
+                .zip(p.iter())
+                .map(|(s, &pi)| Score { name: s.name.clone(), value: pi })
+                .collect();
+            let class = probabilities
+                .iter()
+                .fold(&probabilities[0], |best, s| if s.value > best.value { s } else { best })
+                .name
+                .clone();
+            Ok(Prediction { class, probabilities })
+        }
+    }
+}
+
+/// Classification on top of the encrypted scores of a `LinearModelService`.
+///
+/// The biases are carried by a constant-1 feature appended during encryption, so the decryptions are the complete
+/// scores, which are then calibrated to probabilities.
+///
+/// # Examples
+///
+/// ```ignore
+/// let model = LinearModel::load("diabetes.json").unwrap();
+/// let classifier = Classifier::<8>::logistic(model, "diabetes", 0.5).unwrap();
+/// let cipher = classifier.encrypt(&x).unwrap();
+/// let prediction = classifier.predict(&cipher).unwrap();
+/// println!("{} ({:?})", prediction.class, prediction.probabilities);
+/// ```
+pub struct Classifier<const L: usize> {
+    pub decision: Decision,
+    service: LinearModelService<L>,
+}
+
+impl<const L: usize> Classifier<L> {
+
+    /// Constructs a binary classifier for a model with a single output. The positive class is named after the
+    /// output, the negative class is `not <output>`.
+    pub fn logistic(model: LinearModel, label: &str, threshold: f64) -> Result<Self, ModelError> {
+        if model.outputs.len() != 1 {
+            return Err(ModelError::UnsupportedOutputs(model.outputs.len()));
+        }
+        let positive = model.outputs[0].name.clone();
+        let decision = Decision::Logistic {
+            threshold,
+            negative: format!("not {}", positive),
+            positive,
+        };
+        Ok(Self {
+            decision,
+            service: LinearModelService::with_bias_feature(model, label)?,
+        })
+    }
+
+    /// Constructs a multi-class classifier with one class per output of the model.
+    pub fn softmax(model: LinearModel, label: &str) -> Result<Self, ModelError> {
+        if model.outputs.len() < 2 {
+            return Err(ModelError::UnsupportedOutputs(model.outputs.len()));
+        }
+        Ok(Self {
+            decision: Decision::Softmax,
+            service: LinearModelService::with_bias_feature(model, label)?,
+        })
+    }
+
+    pub fn model(&self) -> &LinearModel {
+        &self.service.model
+    }
+
+    /// Change the threshold of a logistic classifier. Has no effect for softmax classifiers.
+    pub fn set_threshold(&mut self, t: f64) {
+        if let Decision::Logistic { threshold, .. } = &mut self.decision {
+            *threshold = t;
+        }
+    }
+
+    /// Encrypt a feature vector with one entry per feature of the model.
+    pub fn encrypt<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Fixed<Vec<DmcfeCipher>>, ModelError> {
+        self.service.encrypt(x)
+    }
+
+    /// Predict the class of an encrypted feature vector.
+    pub fn predict(&self, cipher: &Fixed<Vec<DmcfeCipher>>) -> Result<Prediction, ModelError> {
+        let scores = self.service.compute(cipher)?;
+        decide(&self.decision, &scores)
+    }
+
+    /// Plaintext reference prediction.
+    pub fn predict_plain<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Prediction, ModelError> {
+        let scores = self.service.model.evaluate(x)?;
+        decide(&self.decision, &scores)
+    }
+}
@@ -0,0 +1,155 @@
+use crate::math::fixed_point::{FixedPoint};
+use crate::math::matrix::{BigIntMatrix};
+use crate::ml::ModelError;
+use crate::ml::quadratic_network::{QuadraticWeights};
+
+
+/// A 2-dimensional convolution layer, expressed as the projection matrix `P` applied by `Sgp::project`.
+///
+/// Images are flattened channel by channel in row-major order, so the entry `(c, h, w)` of an input of shape
+/// `(channels, height, width)` is at index `(c * height + h) * width + w`, and likewise for the output. The entry of
+/// `P` at the indices of input `(c, h, w)` and output `(k, i, j)` is the kernel weight `K[k][c][h - i * stride +
+/// padding][w - j * stride + padding]`, or zero outside the kernel, so that `P^T x` is the convolution of `x`.
+/// Padding is with zeros.
+///
+/// Followed by the square activation of `NeuralNetwork` and a linear layer, see `network`, it gives a
+/// conv-square-linear network on encrypted images.
+///
+/// # Examples
+///
+/// ```ignore
+/// // 4 kernels of size 3 x 3 over a 1 x 28 x 28 image
+/// let conv = Conv2d::new([1, 28, 28], [4, 1, 3, 3], kernels, 2, 1).unwrap();
+/// let weights = conv.network(&linear).unwrap();
+/// let x_bound = BigInt::from(256);
+/// let (network, _) = weights.quantize_bounded(&x_bound, &BigInt::from(1u64 << 32)).unwrap();
+/// let service = NeuralNetwork::<784>::from_quantized(&network, &x_bound).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conv2d {
+    /// Input shape `(channels, height, width)`.
+    pub input: [usize; 3],
+    /// Kernel shape `(out_channels, in_channels, kernel_height, kernel_width)`.
+    pub kernel_shape: [usize; 4],
+    /// Kernel weights, flattened in row-major order.
+    pub kernels: Vec<f64>,
+    pub stride: usize,
+    pub padding: usize,
+}
+
+impl Conv2d {
+    pub fn new(input: [usize; 3], kernel_shape: [usize; 4], kernels: Vec<f64>, stride: usize, padding: usize) -> Result<Self, ModelError> {
+        if input.contains(&0) || kernel_shape.contains(&0) || stride == 0 {
+            return Err(ModelError::Empty);
+        }
+        if kernel_shape[1] != input[0] {
+            return Err(ModelError::Shape { expected: vec![input[0]], actual: vec![kernel_shape[1]] });
+        }
+        if kernels.len() != kernel_shape.iter().product::<usize>() {
+            return Err(ModelError::Shape { expected: kernel_shape.to_vec(), actual: vec![kernels.len()] });
+        }
+        if kernel_shape[2] > input[1] + 2 * padding || kernel_shape[3] > input[2] + 2 * padding {
+            return Err(ModelError::Shape {
+                expected: vec![input[1] + 2 * padding, input[2] + 2 * padding],
+                actual: kernel_shape[2..].to_vec(),
+            });
+        }
+        Ok(Self { input, kernel_shape, kernels, stride, padding })
+    }
+
+    /// Output shape `(out_channels, height, width)`.
+    pub fn output(&self) -> [usize; 3] {
+        let [_, height, width] = self.input;
+        let [out_channels, _, kh, kw] = self.kernel_shape;
+        [
+            out_channels,
+            (height + 2 * self.padding - kh) / self.stride + 1,
+            (width + 2 * self.padding - kw) / self.stride + 1,
+        ]
+    }
+
+    /// Input dimension `n`, the number of rows of `P`.
+    pub fn n(&self) -> usize {
+        self.input.iter().product()
+    }
+
+    /// Output dimension `d`, the number of columns of `P`.
+    pub fn d(&self) -> usize {
+        self.output().iter().product()
+    }
+
+    fn kernel(&self, k: usize, c: usize, u: usize, v: usize) -> f64 {
+        let [_, in_channels, kh, kw] = self.kernel_shape;
+        self.kernels[((k * in_channels + c) * kh + u) * kw + v]
+    }
+
+    /// Calls `f(input index, output index, weight)` for every kernel weight applied to an input entry. Weights that
+    /// fall on the padding are skipped.
+    fn for_each_tap<F: FnMut(usize, usize, f64)>(&self, mut f: F) {
+        let [channels, height, width] = self.input;
+        let [out_channels, out_height, out_width] = self.output();
+        let [_, _, kh, kw] = self.kernel_shape;
+        for k in 0..out_channels {
+            for i in 0..out_height {
+                for j in 0..out_width {
+                    let out = (k * out_height + i) * out_width + j;
+                    for c in 0..channels {
+                        for u in 0..kh {
+                            for v in 0..kw {
+                                // the position in the padded image, minus the padding
+                                let (h, w) = (i * self.stride + u, j * self.stride + v);
+                                if h < self.padding || w < self.padding || h - self.padding >= height || w - self.padding >= width {
+                                    continue;
+                                }
+                                let x = (c * height + h - self.padding) * width + w - self.padding;
+                                f(x, out, self.kernel(k, c, u, v));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The `n x d` projection matrix `P` with `P^T x = conv(x)`, in the format of `QuadraticWeights::p`.
+    pub fn projection(&self) -> Vec<Vec<f64>> {
+        let mut p = vec![vec![0.0; self.d()]; self.n()];
+        self.for_each_tap(|x, out, w| p[x][out] += w);
+        p
+    }
+
+    /// The projection matrix `P`, encoded with `codec`, for `Sgp::project` and `Sgp::derive_fe_key_projected`.
+    pub fn projection_matrix(&self, codec: &FixedPoint) -> Result<BigIntMatrix, ModelError> {
+        Ok(codec.encode_matrix(&self.projection().concat(), self.n(), self.d())?)
+    }
+
+    /// Plaintext reference of the convolution, computed directly from the kernels.
+    pub fn forward(&self, x: &[f64]) -> Result<Vec<f64>, ModelError> {
+        if x.len() != self.n() {
+            return Err(ModelError::Shape { expected: self.input.to_vec(), actual: vec![x.len()] });
+        }
+        let mut y = vec![0.0; self.d()];
+        self.for_each_tap(|i, out, w| y[out] += w * x[i]);
+        Ok(y)
+    }
+
+    /// The weights of the network `x -> argmax_i sum_j linear[i][j] * conv(x)_j^2`: this layer as `P` and every row
+    /// of the linear layer as a diagonal `Q_i`.
+    pub fn network(&self, linear: &[Vec<f64>]) -> Result<QuadraticWeights, ModelError> {
+        let d = self.d();
+        if let Some(row) = linear.iter().find(|row| row.len() != d) {
+            return Err(ModelError::Shape { expected: vec![d], actual: vec![row.len()] });
+        }
+        let q = linear
+            .iter()
+            .map(|row| (0..d).map(|j| (0..d).map(|k| if j == k { row[j] } else { 0.0 }).collect()).collect())
+            .collect();
+        let weights = QuadraticWeights {
+            classes: (0..linear.len()).map(|i| i.to_string()).collect(),
+            p: self.projection(),
+            q,
+        };
+        weights.validate()?;
+        Ok(weights)
+    }
+}
@@ -1,19 +1,19 @@
-use num_bigint::{BigInt};
-use std::convert::TryInto;
-use crate::define::{G1Vector};
-use crate::dmcfe_ip::{Dmcfe};
-use crate::traits::FunctionalEncryption;
+use crate::dmcfe_ip::{DmcfeCipher};
+use crate::math::fixed_point::{Fixed};
+use crate::ml::linear_model::{LinearModel, LinearOutput, LinearModelService, VerifiableScore};
+use crate::zk::VerifyError;
 
 /// The disease prediction application in the following paper:
 /// 
 /// Marc, T., Stopar, M., Hartman, J., Bizjak, M., & Modic, J. (2019, September). Privacy-Enhanced Machine Learning with Functional Encryption. In European Symposium on Research in Computer Security (pp. 3-21). Springer, Cham.
+///
+/// It is a `LinearModelService` configured with the two weight vectors of the paper.
 pub struct DiseasePrediction<'a> {
     pub y1: [f32; 8],
     pub y2: [f32; 8],
     pub scale: f32,
-    pub bound: f32,
     pub label: &'a str, 
-    fe: Dmcfe<8>,
+    service: LinearModelService<8>,
 }
 
 impl<'a> Default for DiseasePrediction<'a> {
@@ -35,20 +35,32 @@ impl<'a> DiseasePrediction<'a> {
     pub fn new() -> Self {
         let y1: [f32; 8] = [0.34362, 2.63588, 1.8803, 1.12673, -0.90941, 0.59397, 0.5232, 0.68602];
         let y2: [f32; 8] = [0.48123, 3.39222, 1.39862, -0.00439, 0.16081, 0.99858, 0.19035, 0.49756];
-        let fe = Dmcfe::<8>::new();
         let scale: f32 = 100.0;
-        let bound: f32 = 10.0;
         let label: &'static str = "disease prediction";
+        let service = LinearModelService::new(Self::model(&y1, &y2, scale), label).unwrap();
         Self {
             y1,
             y2,
             scale,
-            bound,
             label,
-            fe
+            service
         }
     }
 
+    /// The linear model of the paper, with one output per weight vector and fixed-point scale `scale`.
+    pub fn model(y1: &[f32; 8], y2: &[f32; 8], scale: f32) -> LinearModel {
+        let features = ["x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8"];
+        let output = |name: &str, y: &[f32; 8]| LinearOutput {
+            name: name.to_string(),
+            weights: y.iter().map(|&yi| yi as f64).collect(),
+            bias: 0.0,
+        };
+        let mut model = LinearModel::new(&features, vec![output("y1", y1), output("y2", y2)]);
+        model.scales = vec![scale.round() as u64; features.len()];
+        model.weight_scale = scale.round() as u64;
+        model
+    }
+
     /// Encrypt client's input: a vector of floating point values.
     /// 
     /// # Examples
@@ -59,12 +71,8 @@ impl<'a> DiseasePrediction<'a> {
     /// let x: [f32; 8] = [0.1, -0.23, 1.1, 0.98, 5.6, -0.9, -5.0, 2.4];
     /// let ciphers = service.encrypt(&x); 
     /// ```
-    pub fn encrypt(&self, x: &[f32; 8]) -> G1Vector {
-        let mut int_x: [BigInt; 8] = Default::default();
-        for i in 0..8 {
-            int_x[i] = BigInt::from((x[i] * self.scale).round() as i64);
-        }
-        self.fe.encrypt(&int_x)
+    pub fn encrypt(&self, x: &[f32; 8]) -> Fixed<Vec<DmcfeCipher>> {
+        self.service.encrypt(x).unwrap()
     }
 
     /// Compute the inner product of client's input with the two parameter vectors in disease prediction.
@@ -75,27 +83,27 @@ impl<'a> DiseasePrediction<'a> {
     /// // Following the examples of `encrypt`
     /// let result = service.compute(&ciphers); 
     /// ```
-    pub fn compute(&self, ciphers: &G1Vector) -> Vec<f32> {
-        let mut int_y1: [BigInt; 8] = Default::default();
-        let mut int_y2: [BigInt; 8] = Default::default();
-        for i in 0..8 {
-            int_y1[i] = BigInt::from((self.y1[i] * self.scale).round() as i64);
-            int_y2[i] = BigInt::from((self.y2[i] * self.scale).round() as i64);
-        }
-
-        let key1 = self.fe.derive_fe_key(&int_y1);
-        let key2 = self.fe.derive_fe_key(&int_y2);
-
-        let bound = BigInt::from((self.bound * self.scale).round() as i64);
-        let x_mut_y1 = self.fe.decrypt(&ciphers, &key1, &bound).unwrap(); 
-        let x_mut_y2 = self.fe.decrypt(&ciphers, &key2, &bound).unwrap(); 
+    pub fn compute(&self, ciphers: &Fixed<Vec<DmcfeCipher>>) -> Vec<f32> {
+        self.service.compute(ciphers).unwrap().iter().map(|score| score.value as f32).collect()
+    }
 
-        let x_mut_y1: i64 = x_mut_y1.try_into().unwrap();
-        let x_mut_y2: i64 = x_mut_y2.try_into().unwrap();
+    /// Compute the two inner products, each with a proof that it was decrypted correctly.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Following the examples of `encrypt`
+    /// let scores = service.compute_verifiable(&ciphers);
+    /// service.verify(&ciphers, &scores).unwrap();
+    /// ```
+    pub fn compute_verifiable(&self, ciphers: &Fixed<Vec<DmcfeCipher>>) -> Vec<VerifiableScore> {
+        self.service.compute_verifiable(ciphers).unwrap()
+    }
 
-        let x_mut_y1 = (x_mut_y1 as f32) / (self.scale * self.scale);
-        let x_mut_y2 = (x_mut_y2 as f32) / (self.scale * self.scale);
-        vec![x_mut_y1, x_mut_y2]
+    /// Verify the scores of `compute_verifiable`. Only the public parameters `y1`, `y2`, `scale` and `label` are
+    /// used.
+    pub fn verify(&self, ciphers: &Fixed<Vec<DmcfeCipher>>, scores: &[VerifiableScore]) -> Result<(), VerifyError> {
+        Self::model(&self.y1, &self.y2, self.scale).verify_scores::<8>(self.label, ciphers, scores)
     }
 
 }
@@ -0,0 +1,156 @@
+use num_bigint::{BigInt};
+use num_traits::{Signed};
+use std::time::{Duration, Instant};
+
+use crate::ml::ModelError;
+use crate::ml::idx::{Dataset};
+use crate::ml::neural_network::NeuralNetwork;
+use crate::ml::quadratic_network::{QuadraticWeights, QuantizedNetwork};
+
+
+/// A sample on which encrypted inference and plaintext inference disagree. Encrypted inference computes the scores of
+/// the quantized network exactly, so a mismatch is caused by quantizing the weights or the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub index: usize,
+    pub label: usize,
+    pub encrypted: usize,
+    pub plain: usize,
+}
+
+/// Result of running encrypted inference over a dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationReport {
+    pub samples: usize,
+    /// Samples classified correctly by encrypted inference.
+    pub encrypted_correct: usize,
+    /// Samples classified correctly by plaintext inference with the float weights.
+    pub plain_correct: usize,
+    pub mismatches: Vec<Mismatch>,
+    /// Time to encrypt each sample.
+    pub encrypt_times: Vec<Duration>,
+    /// Time to compute the scores of each sample: projection, key derivation and decryption.
+    pub decrypt_times: Vec<Duration>,
+}
+
+impl EvaluationReport {
+    pub fn accuracy(&self) -> f64 {
+        self.encrypted_correct as f64 / self.samples as f64
+    }
+
+    pub fn plain_accuracy(&self) -> f64 {
+        self.plain_correct as f64 / self.samples as f64
+    }
+
+    /// Fraction of the samples on which encrypted and plaintext inference agree.
+    pub fn agreement(&self) -> f64 {
+        1.0 - self.mismatches.len() as f64 / self.samples as f64
+    }
+
+    pub fn mean_encrypt_time(&self) -> Duration {
+        mean(&self.encrypt_times)
+    }
+
+    pub fn mean_decrypt_time(&self) -> Duration {
+        mean(&self.decrypt_times)
+    }
+}
+
+/// Encrypted classification of a dataset with `NeuralNetwork`, compared with plaintext inference, as in the
+/// experiments of \[SGP2018\].
+///
+/// The entries of a sample are multiplied with `input_scale` and rounded before encryption, and must then be
+/// bounded by `x_bound`, the bound the network was quantized for. For MNIST, the raw pixel values fit a bound of
+/// 256 with scale 1.
+///
+/// # Examples
+///
+/// ```ignore
+/// let dataset = Dataset::load_idx("t10k-images-idx3-ubyte", "t10k-labels-idx1-ubyte").unwrap();
+/// let evaluator = EncryptedEvaluator::<784>::new(weights, network, 1.0, &BigInt::from(256)).unwrap();
+/// let report = evaluator.evaluate(&dataset, Some(100)).unwrap();
+/// println!("accuracy {}, plaintext {}, {} mismatches", report.accuracy(), report.plain_accuracy(), report.mismatches.len());
+/// ```
+pub struct EncryptedEvaluator<const L: usize> {
+    pub weights: QuadraticWeights,
+    pub network: QuantizedNetwork,
+    pub input_scale: f64,
+    pub service: NeuralNetwork<L>,
+}
+
+impl<const L: usize> EncryptedEvaluator<L> {
+    /// Constructs an evaluator for the float `weights` and their quantization `network` for inputs bounded by
+    /// `x_bound`.
+    pub fn new(weights: QuadraticWeights, network: QuantizedNetwork, input_scale: f64, x_bound: &BigInt) -> Result<Self, ModelError> {
+        let service = NeuralNetwork::from_quantized(&network, x_bound)?;
+        Ok(Self {
+            weights,
+            network,
+            input_scale,
+            service,
+        })
+    }
+
+    /// Encode a sample as the integer input of the network.
+    pub fn encode(&self, x: &[f64]) -> Result<[BigInt; L], ModelError> {
+        if x.len() != L {
+            return Err(ModelError::FeatureMismatch { expected: L, actual: x.len() });
+        }
+        let encoded: [BigInt; L] = array_init::array_init(|i| BigInt::from((x[i] * self.input_scale).round() as i64));
+        if encoded.iter().any(|xi| xi.abs() > self.service.bound) {
+            return Err(ModelError::InputOutOfBound { bound: self.service.bound.clone() });
+        }
+        Ok(encoded)
+    }
+
+    /// Run encrypted and plaintext inference on the first `limit` samples of the dataset, or on all of them.
+    pub fn evaluate(&self, dataset: &Dataset, limit: Option<usize>) -> Result<EvaluationReport, ModelError> {
+        let samples = limit.map_or(dataset.len(), |l| l.min(dataset.len()));
+        if samples == 0 {
+            return Err(ModelError::Empty);
+        }
+        let mut report = EvaluationReport {
+            samples,
+            encrypted_correct: 0,
+            plain_correct: 0,
+            mismatches: Vec::new(),
+            encrypt_times: Vec::with_capacity(samples),
+            decrypt_times: Vec::with_capacity(samples),
+        };
+        for index in 0..samples {
+            let (x, label) = (&dataset.samples[index], dataset.labels[index]);
+            let encoded = self.encode(x)?;
+
+            let start = Instant::now();
+            let cipher = self.service.encrypt(&encoded);
+            report.encrypt_times.push(start.elapsed());
+            let start = Instant::now();
+            let scores = self.service.compute(&cipher);
+            report.decrypt_times.push(start.elapsed());
+
+            let encrypted = argmax(&scores);
+            let plain = self.weights.predict(x)?;
+            if encrypted == label {
+                report.encrypted_correct += 1;
+            }
+            if plain == label {
+                report.plain_correct += 1;
+            }
+            if encrypted != plain {
+                report.mismatches.push(Mismatch { index, label, encrypted, plain });
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn argmax(scores: &[BigInt]) -> usize {
+    (0..scores.len()).fold(0, |best, i| if scores[i] > scores[best] { i } else { best })
+}
+
+fn mean(times: &[Duration]) -> Duration {
+    if times.is_empty() {
+        return Duration::default();
+    }
+    times.iter().sum::<Duration>() / times.len() as u32
+}
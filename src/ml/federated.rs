@@ -0,0 +1,267 @@
+use num_bigint::{BigInt};
+use num_traits::{One, ToPrimitive};
+
+use crate::bounds::{DecryptError};
+use crate::define::{G1, G2Vector};
+use crate::dmcfe_ip::{Dmcfe, DmcfeCipher};
+use crate::math::fixed_point::{FixedPoint, FixedPointError, Fixed};
+
+
+/// How the server weights the updates of the clients. The weights are fixed for the whole session when the clients
+/// are set up, as keys for two different weight vectors would let the server subtract the two averages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Weighting {
+    /// Plain average, with the all-ones functional key.
+    Uniform,
+    /// Average weighted by the number of samples of each client, agreed at setup. The sample counts are revealed to
+    /// the server, as they are the entries of the functional key.
+    SampleCount(Vec<u64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FederatedError {
+    /// The update or the weights do not have the expected length.
+    DimensionMismatch { expected: usize, actual: usize },
+    /// A coordinate of the update exceeds the bound agreed by the clients.
+    UpdateOutOfBound { coordinate: usize, bound: f64 },
+    /// The update was encrypted for another round than the current one.
+    WrongRound { expected: u64, actual: u64 },
+    /// The client already submitted an update in this round.
+    DuplicateUpdate(usize),
+    /// The client index is not part of the federation.
+    UnknownClient(usize),
+    /// The round cannot be aggregated before these clients submit their updates.
+    MissingUpdates(Vec<usize>),
+    /// The weights have a zero entry, which would exclude updates from the sum, or a client is asked for a key share
+    /// for other weights than those fixed at setup. Keys for two different weight vectors decrypt to two
+    /// combinations of the updates whose difference can reveal the update of a single client, in every round.
+    InvalidWeights,
+    Encoding(FixedPointError),
+    Decrypt(DecryptError),
+}
+
+impl From<FixedPointError> for FederatedError {
+    fn from(e: FixedPointError) -> Self {
+        match e {
+            FixedPointError::Decrypt(e) => FederatedError::Decrypt(e),
+            e => FederatedError::Encoding(e),
+        }
+    }
+}
+
+impl Weighting {
+    /// The weight vector of `L` clients, the function of the key the server decrypts with.
+    pub fn weights<const L: usize>(&self) -> Result<[BigInt; L], FederatedError> {
+        match self {
+            Weighting::Uniform => Ok(array_init::array_init(|_| BigInt::one())),
+            Weighting::SampleCount(samples) => {
+                if samples.len() != L {
+                    return Err(FederatedError::DimensionMismatch { expected: L, actual: samples.len() });
+                }
+                if samples.contains(&0) {
+                    return Err(FederatedError::InvalidWeights);
+                }
+                Ok(array_init::array_init(|i| BigInt::from(samples[i])))
+            }
+        }
+    }
+}
+
+/// A model update encrypted by one client. Every coordinate is encrypted under its own label, derived from the
+/// session, the round and the coordinate, so that ciphertexts cannot be combined across rounds or coordinates.
+#[derive(Debug, Clone)]
+pub struct ClientUpdate {
+    pub index: usize,
+    pub round: u64,
+    pub ciphers: Vec<Fixed<DmcfeCipher>>,
+}
+
+/// One of the `L` clients of a federation. Clients encode their updates with a common fixed-point scale and bound
+/// every coordinate by a common `bound`, which determines the range searched by the server. Each client only derives
+/// key shares for the weights fixed at setup.
+pub struct FederatedClient<const L: usize> {
+    pub index: usize,
+    pub session: String,
+    pub bound: f64,
+    codec: FixedPoint,
+    weights: [BigInt; L],
+    fe: Dmcfe<L>,
+}
+
+/// The aggregation server of a federation of `L` clients: it collects the encrypted updates of a round and
+/// recovers only their (weighted) average.
+///
+/// # Examples
+///
+/// ```ignore
+/// let weighting = Weighting::SampleCount(vec![100, 250, 40]);
+/// let clients = FederatedClient::<3>::setup_weighted("session", 1000, 1.0, &weighting).unwrap();
+/// let mut server = FederatedServer::<3>::new("session", 10, weighting);
+/// for (client, update) in clients.iter().zip(updates.iter()) {
+///     server.submit(client.encrypt_update(server.round(), update).unwrap()).unwrap();
+/// }
+/// let weights = server.weights().unwrap();
+/// let shares: Vec<G2Vector> = clients.iter().map(|c| c.key_share(&weights).unwrap()).collect();
+/// let average = server.aggregate(&shares).unwrap();
+/// ```
+pub struct FederatedServer<const L: usize> {
+    pub session: String,
+    pub dim: usize,
+    weighting: Weighting,
+    round: u64,
+    updates: Vec<Option<ClientUpdate>>,
+    fe: Dmcfe<L>,
+}
+
+fn round_label(session: &str, round: u64, coordinate: usize) -> String {
+    format!("{} round {} coordinate {}", session, round, coordinate)
+}
+
+impl<const L: usize> FederatedClient<L> {
+    /// Constructs the `L` clients of a federation averaging uniformly and exchanges their public keys.
+    pub fn setup(session: &str, scale: u64, bound: f64) -> Vec<Self> {
+        Self::with_weights(session, scale, bound, Weighting::Uniform.weights().unwrap())
+    }
+
+    /// Constructs the `L` clients of a federation and exchanges their public keys. The weights are fixed for the
+    /// session.
+    pub fn setup_weighted(session: &str, scale: u64, bound: f64, weighting: &Weighting) -> Result<Vec<Self>, FederatedError> {
+        Ok(Self::with_weights(session, scale, bound, weighting.weights()?))
+    }
+
+    fn with_weights(session: &str, scale: u64, bound: f64, weights: [BigInt; L]) -> Vec<Self> {
+        let mut fes: Vec<Dmcfe<L>> = (0..L).map(Dmcfe::<L>::new_single).collect();
+        let pub_keys: Vec<G1> = fes.iter().map(|fe| fe.client_pub_key.clone()).collect();
+        for fe in fes.iter_mut() {
+            fe.set_share(&pub_keys);
+        }
+        fes.into_iter()
+            .enumerate()
+            .map(|(index, fe)| Self {
+                index,
+                session: session.to_string(),
+                bound,
+                codec: FixedPoint::new(scale),
+                weights: weights.clone(),
+                fe,
+            })
+            .collect()
+    }
+
+    /// Encrypt a model update for `round`.
+    pub fn encrypt_update<T: Copy + Into<f64>>(&self, round: u64, update: &[T]) -> Result<ClientUpdate, FederatedError> {
+        if let Some(coordinate) = update.iter().position(|&u| u.into().abs() > self.bound) {
+            return Err(FederatedError::UpdateOutOfBound { coordinate, bound: self.bound });
+        }
+        let ciphers = update
+            .iter()
+            .enumerate()
+            .map(|(j, &u)| self.fe.encrypt_single_fixed(u, self.bound, &round_label(&self.session, round, j), &self.codec))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ClientUpdate { index: self.index, round, ciphers })
+    }
+
+    /// Derive this client's share of the functional key for the weights of the clients, which must be the weights
+    /// fixed at setup.
+    pub fn key_share(&self, weights: &[BigInt]) -> Result<G2Vector, FederatedError> {
+        if weights.len() != L {
+            return Err(FederatedError::DimensionMismatch { expected: L, actual: weights.len() });
+        }
+        if weights != self.weights {
+            return Err(FederatedError::InvalidWeights);
+        }
+        Ok(self.fe.derive_fe_key_share(&self.weights))
+    }
+}
+
+impl<const L: usize> FederatedServer<L> {
+    /// Constructs a server aggregating updates of dimension `dim` with the weighting the clients were set up with,
+    /// starting with round 0.
+    pub fn new(session: &str, dim: usize, weighting: Weighting) -> Self {
+        Self {
+            session: session.to_string(),
+            dim,
+            weighting,
+            round: 0,
+            updates: vec![None; L],
+            fe: Dmcfe::<L>::new_single(0),
+        }
+    }
+
+    pub fn weighting(&self) -> &Weighting {
+        &self.weighting
+    }
+
+    /// The round the server currently collects updates for.
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    /// Accept the encrypted update of a client for the current round.
+    pub fn submit(&mut self, update: ClientUpdate) -> Result<(), FederatedError> {
+        if update.index >= L {
+            return Err(FederatedError::UnknownClient(update.index));
+        }
+        if update.round != self.round {
+            return Err(FederatedError::WrongRound { expected: self.round, actual: update.round });
+        }
+        if update.ciphers.len() != self.dim {
+            return Err(FederatedError::DimensionMismatch { expected: self.dim, actual: update.ciphers.len() });
+        }
+        if self.updates[update.index].is_some() {
+            return Err(FederatedError::DuplicateUpdate(update.index));
+        }
+        let index = update.index;
+        self.updates[index] = Some(update);
+        Ok(())
+    }
+
+    /// Clients that have not submitted an update in the current round.
+    pub fn missing(&self) -> Vec<usize> {
+        (0..L).filter(|&i| self.updates[i].is_none()).collect()
+    }
+
+    /// The weights of the clients, for which the clients derive their key shares once all updates of the current
+    /// round are submitted.
+    pub fn weights(&self) -> Result<Vec<BigInt>, FederatedError> {
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(FederatedError::MissingUpdates(missing));
+        }
+        Ok(self.weighting.weights::<L>()?.to_vec())
+    }
+
+    /// Combine the key shares of all clients, recover the weighted average of the updates of the current round and
+    /// move on to the next round.
+    pub fn aggregate(&mut self, key_shares: &[G2Vector]) -> Result<Vec<f64>, FederatedError> {
+        let total: BigInt = self.weights()?.iter().sum();
+        let total = total.to_f64().unwrap();
+        Ok(self.aggregate_sum(key_shares)?.iter().map(|s| s / total).collect())
+    }
+
+    /// Combine the key shares of all clients, recover the weighted sum of the updates of the current round and move
+    /// on to the next round.
+    pub fn aggregate_sum(&mut self, key_shares: &[G2Vector]) -> Result<Vec<f64>, FederatedError> {
+        let weights = self.weights()?;
+        if key_shares.len() != L {
+            return Err(FederatedError::DimensionMismatch { expected: L, actual: key_shares.len() });
+        }
+        let weights: [BigInt; L] = array_init::array_init(|i| weights[i].clone());
+        let dk = Fixed {
+            inner: self.fe.key_comb(key_shares, &weights),
+            scale: FixedPoint::new(1).scale(),
+        };
+
+        let updates: Vec<&ClientUpdate> = self.updates.iter().flatten().collect();
+        let mut sum = Vec::with_capacity(self.dim);
+        for j in 0..self.dim {
+            let ciphers: Vec<Fixed<DmcfeCipher>> = updates.iter().map(|u| u.ciphers[j].clone()).collect();
+            sum.push(self.fe.decrypt_with_label_fixed(&ciphers, &dk, &round_label(&self.session, self.round, j))?);
+        }
+
+        self.round += 1;
+        self.updates = vec![None; L];
+        Ok(sum)
+    }
+
+}
@@ -0,0 +1,192 @@
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::ml::ModelError;
+
+
+/// Element types of the IDX format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdxType {
+    U8,
+    I8,
+    I16,
+    I32,
+    F32,
+    F64,
+}
+
+impl IdxType {
+    fn code(self) -> u8 {
+        match self {
+            IdxType::U8 => 0x08,
+            IdxType::I8 => 0x09,
+            IdxType::I16 => 0x0B,
+            IdxType::I32 => 0x0C,
+            IdxType::F32 => 0x0D,
+            IdxType::F64 => 0x0E,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x08 => Some(IdxType::U8),
+            0x09 => Some(IdxType::I8),
+            0x0B => Some(IdxType::I16),
+            0x0C => Some(IdxType::I32),
+            0x0D => Some(IdxType::F32),
+            0x0E => Some(IdxType::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            IdxType::U8 | IdxType::I8 => 1,
+            IdxType::I16 => 2,
+            IdxType::I32 | IdxType::F32 => 4,
+            IdxType::F64 => 8,
+        }
+    }
+}
+
+/// A dense array in the IDX format of the MNIST database, with the entries in row-major order. The entries are
+/// kept as `f64`; `dtype` is the type they are stored with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxArray {
+    pub dtype: IdxType,
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl IdxArray {
+    pub fn new(dtype: IdxType, shape: Vec<usize>, data: Vec<f64>) -> Self {
+        if shape.iter().product::<usize>() != data.len() {
+            panic!("Malformed input: shape {:?}, data.len ({})", shape, data.len());
+        }
+        Self { dtype, shape, data }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let bytes = fs::read(path).map_err(|e| ModelError::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ModelError> {
+        fs::write(path, self.to_bytes()).map_err(|e| ModelError::Io(e.to_string()))
+    }
+
+    /// Parse an IDX file: two zero bytes, the element type, the number of dimensions, the dimensions as big-endian
+    /// 32-bit integers and the big-endian entries.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        if bytes.len() < 4 || bytes[0] != 0 || bytes[1] != 0 {
+            return Err(ModelError::Idx("missing magic number".to_string()));
+        }
+        let dtype = IdxType::from_code(bytes[2])
+            .ok_or_else(|| ModelError::Idx(format!("unsupported type 0x{:02x}", bytes[2])))?;
+        let dims = bytes[3] as usize;
+        let offset = 4 + 4 * dims;
+        if bytes.len() < offset {
+            return Err(ModelError::Idx("truncated dimensions".to_string()));
+        }
+        let shape: Vec<usize> = bytes[4..offset]
+            .chunks(4)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()) as usize)
+            .collect();
+
+        let size = checked_len(&shape)?
+            .checked_mul(dtype.size())
+            .ok_or_else(|| ModelError::Idx("dimensions overflow".to_string()))?;
+        let body = &bytes[offset..];
+        if body.len() != size {
+            return Err(ModelError::Idx(format!("expected {} bytes of data, found {}", size, body.len())));
+        }
+        let data = body
+            .chunks(dtype.size())
+            .map(|c| match dtype {
+                IdxType::U8 => c[0] as f64,
+                IdxType::I8 => c[0] as i8 as f64,
+                IdxType::I16 => i16::from_be_bytes(c.try_into().unwrap()) as f64,
+                IdxType::I32 => i32::from_be_bytes(c.try_into().unwrap()) as f64,
+                IdxType::F32 => f32::from_be_bytes(c.try_into().unwrap()) as f64,
+                IdxType::F64 => f64::from_be_bytes(c.try_into().unwrap()),
+            })
+            .collect();
+        Ok(Self { dtype, shape, data })
+    }
+
+    /// Serialize the array, converting the entries to `dtype`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0, 0, self.dtype.code(), self.shape.len() as u8];
+        for &dim in self.shape.iter() {
+            bytes.extend_from_slice(&(dim as u32).to_be_bytes());
+        }
+        for &v in self.data.iter() {
+            match self.dtype {
+                IdxType::U8 => bytes.push(v as u8),
+                IdxType::I8 => bytes.push(v as i8 as u8),
+                IdxType::I16 => bytes.extend_from_slice(&(v as i16).to_be_bytes()),
+                IdxType::I32 => bytes.extend_from_slice(&(v as i32).to_be_bytes()),
+                IdxType::F32 => bytes.extend_from_slice(&(v as f32).to_be_bytes()),
+                IdxType::F64 => bytes.extend_from_slice(&v.to_be_bytes()),
+            }
+        }
+        bytes
+    }
+}
+
+/// A labelled dataset of flattened samples, such as the images of MNIST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    pub samples: Vec<Vec<f64>>,
+    pub labels: Vec<usize>,
+}
+
+impl Dataset {
+    /// Load a dataset from an IDX file of samples, e.g. `train-images-idx3-ubyte`, and an IDX file of labels, e.g.
+    /// `train-labels-idx1-ubyte`. All dimensions of a sample after the first are flattened.
+    pub fn load_idx<P: AsRef<Path>, Q: AsRef<Path>>(samples_path: P, labels_path: Q) -> Result<Self, ModelError> {
+        Self::from_arrays(&IdxArray::load(samples_path)?, &IdxArray::load(labels_path)?)
+    }
+
+    pub fn from_arrays(samples: &IdxArray, labels: &IdxArray) -> Result<Self, ModelError> {
+        if samples.shape.is_empty() || labels.shape.len() != 1 {
+            return Err(ModelError::Shape { expected: vec![samples.shape.first().cloned().unwrap_or(0)], actual: labels.shape.clone() });
+        }
+        let count = samples.shape[0];
+        if labels.shape[0] != count {
+            return Err(ModelError::FeatureMismatch { expected: count, actual: labels.shape[0] });
+        }
+        if let Some(&l) = labels.data.iter().find(|&&l| l < 0.0 || l.fract() != 0.0) {
+            return Err(ModelError::Idx(format!("invalid label {}", l)));
+        }
+        let len = checked_len(&samples.shape[1..])?;
+        let total = checked_len(&samples.shape)?;
+        if samples.data.len() != total {
+            return Err(ModelError::Idx(format!("expected {} entries, found {}", total, samples.data.len())));
+        }
+        if labels.data.len() != count {
+            return Err(ModelError::Idx(format!("expected {} labels, found {}", count, labels.data.len())));
+        }
+        Ok(Self {
+            samples: (0..count).map(|i| samples.data[i * len..(i + 1) * len].to_vec()).collect(),
+            labels: labels.data.iter().map(|&l| l as usize).collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// Number of entries of an array of the given shape.
+fn checked_len(shape: &[usize]) -> Result<usize, ModelError> {
+    shape
+        .iter()
+        .try_fold(1usize, |len, &dim| len.checked_mul(dim))
+        .ok_or_else(|| ModelError::Idx("dimensions overflow".to_string()))
+}
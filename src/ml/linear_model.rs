@@ -0,0 +1,426 @@
+use num_bigint::{BigInt};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::dmcfe_ip::{Dmcfe, DmcfeCipher, DmcfeDecKey};
+use crate::math::fixed_point::{FixedPoint, Fixed, Scale, decode, encode_bound};
+use crate::traits::FunctionalEncryption;
+use crate::zk::VerifyError;
+use crate::zk::decryption::{prove_dmcfe_decryption, verify_dmcfe_decryption};
+use crate::zk::sigma::{PairingProof};
+
+pub use crate::ml::ModelError;
+
+
+/// Default fixed-point scale of features and weights.
+pub const DEFAULT_SCALE: u64 = 100;
+
+/// Default public bound on the absolute value of the features.
+pub const DEFAULT_FEATURE_BOUND: f64 = 100.0;
+
+/// Name of the constant-1 feature appended by `LinearModel::with_bias_feature`.
+pub const BIAS_FEATURE: &str = "bias";
+
+/// One output of a linear model: `score = <weights, x> + bias`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearOutput {
+    pub name: String,
+    pub weights: Vec<f64>,
+    #[serde(default)]
+    pub bias: f64,
+}
+
+/// A linear model with named features and any number of named outputs.
+///
+/// Feature `i` is encoded with the fixed-point scale `scales[i]`, so that features of different magnitudes can be
+/// given different precisions. All scales must divide the largest one, `M`. The weights of feature `i` are then
+/// encoded with scale `weight_scale * M / scales[i]`, and every score has scale `M * weight_scale`.
+///
+/// `feature_bound` is the public bound on the absolute value of the features. It is carried by the ciphertexts in
+/// place of the actual largest feature, and determines the range searched during decryption.
+///
+/// # File formats
+///
+/// JSON:
+///
+/// ```text
+/// {
+///   "features": ["age", "bmi"],
+///   "scales": [10, 100],
+///   "weight_scale": 100,
+///   "feature_bound": 50.0,
+///   "outputs": [{"name": "risk", "weights": [0.02, 0.3], "bias": -1.5}]
+/// }
+/// ```
+///
+/// `scales` and `weight_scale` are optional and default to `DEFAULT_SCALE`, `feature_bound` defaults to
+/// `DEFAULT_FEATURE_BOUND`, `bias` defaults to 0.
+///
+/// CSV: a header `name,bias,<feature>,...` followed by one row per output. An optional row named `scale` gives the
+/// feature scales, with the weight scale in the bias column. CSV models have the default feature bound.
+///
+/// ```text
+/// name,bias,age,bmi
+/// scale,100,10,100
+/// risk,-1.5,0.02,0.3
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub scales: Vec<u64>,
+    #[serde(default = "default_scale")]
+    pub weight_scale: u64,
+    #[serde(default = "default_feature_bound")]
+    pub feature_bound: f64,
+    pub outputs: Vec<LinearOutput>,
+}
+
+fn default_scale() -> u64 {
+    DEFAULT_SCALE
+}
+
+fn default_feature_bound() -> f64 {
+    DEFAULT_FEATURE_BOUND
+}
+
+/// A named score computed by `LinearModelService`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub name: String,
+    pub value: f64,
+}
+
+/// A score computed by `LinearModelService::compute_verifiable`, with the decrypted integer `result` and a proof that
+/// it is the decryption of the ciphertext.
+#[derive(Debug, Clone)]
+pub struct VerifiableScore {
+    pub score: Score,
+    pub result: BigInt,
+    pub proof: PairingProof,
+}
+
+impl LinearModel {
+    /// Constructs a model where all features have the default scale.
+    pub fn new(features: &[&str], outputs: Vec<LinearOutput>) -> Self {
+        Self {
+            features: features.iter().map(|f| f.to_string()).collect(),
+            scales: vec![DEFAULT_SCALE; features.len()],
+            weight_scale: DEFAULT_SCALE,
+            feature_bound: DEFAULT_FEATURE_BOUND,
+            outputs,
+        }
+    }
+
+    /// Load a model from a `.json` or `.csv` file, chosen by the extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| ModelError::Io(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Self::from_csv(&content),
+            _ => Self::from_json(&content),
+        }
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, ModelError> {
+        let mut model: LinearModel = serde_json::from_str(content).map_err(|e| ModelError::Json(e.to_string()))?;
+        model.fill_scales();
+        model.validate()?;
+        Ok(model)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_csv(content: &str) -> Result<Self, ModelError> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let header = match lines.next() {
+            Some((_, l)) => split_csv(l),
+            None => return Err(ModelError::Empty),
+        };
+        if header.len() < 3 || header[0] != "name" || header[1] != "bias" {
+            return Err(ModelError::Csv { line: 1, message: "expected header name,bias,<features>".to_string() });
+        }
+        let features: Vec<String> = header[2..].to_vec();
+
+        let mut model = LinearModel {
+            features,
+            scales: Vec::new(),
+            weight_scale: DEFAULT_SCALE,
+            feature_bound: DEFAULT_FEATURE_BOUND,
+            outputs: Vec::new(),
+        };
+        for (i, l) in lines {
+            let line = i + 1;
+            let fields = split_csv(l);
+            if fields.len() != header.len() {
+                return Err(ModelError::Csv { line, message: format!("expected {} fields, found {}", header.len(), fields.len()) });
+            }
+            if fields[0] == "scale" {
+                model.weight_scale = parse_field(&fields[1], line)?;
+                model.scales = fields[2..].iter().map(|f| parse_field(f, line)).collect::<Result<_, _>>()?;
+            } else {
+                model.outputs.push(LinearOutput {
+                    name: fields[0].clone(),
+                    bias: parse_field(&fields[1], line)?,
+                    weights: fields[2..].iter().map(|f| parse_field(f, line)).collect::<Result<_, _>>()?,
+                });
+            }
+        }
+        model.fill_scales();
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Check that the model is well-formed.
+    pub fn validate(&self) -> Result<(), ModelError> {
+        let n = self.features.len();
+        if n == 0 || self.outputs.is_empty() {
+            return Err(ModelError::Empty);
+        }
+        if self.scales.len() != n {
+            return Err(ModelError::FeatureMismatch { expected: n, actual: self.scales.len() });
+        }
+        if let Some(out) = self.outputs.iter().find(|out| out.weights.len() != n) {
+            return Err(ModelError::FeatureMismatch { expected: n, actual: out.weights.len() });
+        }
+        if self.weight_scale == 0 {
+            return Err(ModelError::InvalidScale(0));
+        }
+        let max = self.max_scale();
+        match self.scales.iter().find(|&&s| s == 0 || !max.is_multiple_of(s)) {
+            Some(&s) => Err(ModelError::InvalidScale(s)),
+            None => Ok(()),
+        }
+    }
+
+    /// Plaintext reference evaluation of all outputs.
+    pub fn evaluate<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Vec<Score>, ModelError> {
+        if x.len() != self.features.len() {
+            return Err(ModelError::FeatureMismatch { expected: self.features.len(), actual: x.len() });
+        }
+        Ok(self.outputs
+            .iter()
+            .map(|out| Score {
+                name: out.name.clone(),
+                value: out.weights.iter().zip(x.iter()).map(|(&w, &xi)| w * xi.into()).sum::<f64>() + out.bias,
+            })
+            .collect())
+    }
+
+    /// The equivalent model with the biases moved into the weights of an additional constant-1 feature, which has
+    /// the largest feature scale.
+    pub fn with_bias_feature(&self) -> LinearModel {
+        let mut model = self.clone();
+        model.features.push(BIAS_FEATURE.to_string());
+        model.scales.push(self.max_scale());
+        for out in model.outputs.iter_mut() {
+            out.weights.push(out.bias);
+            out.bias = 0.0;
+        }
+        model
+    }
+
+    /// Verify the scores of `LinearModelService::compute_verifiable` for `cipher`, encrypted with `label` for a
+    /// service supporting `L` features. The model must be the one the keys were derived for: the model of the
+    /// service, or its `with_bias_feature` model if the service was constructed with `with_bias_feature`.
+    pub fn verify_scores<const L: usize>(&self, label: &str, cipher: &Fixed<Vec<DmcfeCipher>>, scores: &[VerifiableScore]) -> Result<(), VerifyError> {
+        if scores.len() != self.outputs.len() {
+            return Err(VerifyError::InputLength { expected: self.outputs.len(), actual: scores.len() });
+        }
+        let scale = self.result_scale();
+        for (out, score) in self.outputs.iter().zip(scores.iter()) {
+            let y = pad::<L>(&self.encode_weights(out).map_err(|_| VerifyError::InputMismatch { input: out.name.clone() })?);
+            verify_dmcfe_decryption(&cipher.inner, &y, label, &score.result, &score.proof)?;
+            if score.score.name != out.name || score.score.value != decode(&score.result, &scale) + out.bias {
+                return Err(VerifyError::InputMismatch { input: out.name.clone() });
+            }
+        }
+        Ok(())
+    }
+
+    /// Scale of the scores before decoding.
+    pub fn result_scale(&self) -> Scale {
+        Scale::new(&BigInt::from(self.max_scale())).compose(&Scale::new(&BigInt::from(self.weight_scale)))
+    }
+
+    fn max_scale(&self) -> u64 {
+        self.scales.iter().copied().max().unwrap_or(DEFAULT_SCALE)
+    }
+
+    fn fill_scales(&mut self) {
+        if self.scales.is_empty() {
+            self.scales = vec![DEFAULT_SCALE; self.features.len()];
+        }
+    }
+
+    fn encode_features<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Vec<BigInt>, ModelError> {
+        if x.len() != self.features.len() {
+            return Err(ModelError::FeatureMismatch { expected: self.features.len(), actual: x.len() });
+        }
+        x.iter()
+            .zip(self.scales.iter())
+            .map(|(&xi, &s)| FixedPoint::new(s).encode(xi).map_err(ModelError::from))
+            .collect()
+    }
+
+    fn encode_weights(&self, out: &LinearOutput) -> Result<Vec<BigInt>, ModelError> {
+        let max = self.max_scale();
+        out.weights
+            .iter()
+            .zip(self.scales.iter())
+            .map(|(&w, &s)| FixedPoint::new(self.weight_scale * (max / s)).encode(w).map_err(ModelError::from))
+            .collect()
+    }
+}
+
+fn split_csv(line: &str) -> Vec<String> {
+    line.split(',').map(|f| f.trim().to_string()).collect()
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str, line: usize) -> Result<T, ModelError> {
+    field.parse().map_err(|_| ModelError::Csv { line, message: format!("invalid number '{}'", field) })
+}
+
+/// Inference service for linear models over encrypted feature vectors.
+///
+/// `L` is the largest number of features the service supports; feature vectors and weights of smaller models are
+/// padded with zeros. A functional evaluation key is derived once for every output of the model, so the service
+/// learns the scores and nothing else about the features.
+///
+/// By default the biases are added to the decrypted scores. A service constructed with `with_bias_feature` instead
+/// appends a constant-1 feature during encryption, so that the decryptions are the complete scores.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ruby::ml::linear_model::{LinearModel, LinearModelService};
+/// let model = LinearModel::load("model.json").unwrap();
+/// let service = LinearModelService::<16>::new(model, "linear-model").unwrap();
+/// let cipher = service.encrypt(&[0.5f64, 1.25, -3.0]).unwrap();
+/// for score in service.compute(&cipher).unwrap() {
+///     println!("{}: {}", score.name, score.value);
+/// }
+/// ```
+pub struct LinearModelService<const L: usize> {
+    pub model: LinearModel,
+    pub label: String,
+    bias_feature: bool,
+    /// The model the keys are derived for: `model`, or `model.with_bias_feature()`
+    encoded: LinearModel,
+    fe: Dmcfe<L>,
+    keys: Vec<DmcfeDecKey<L>>,
+}
+
+impl<const L: usize> LinearModelService<L> {
+
+    /// Constructs a new `LinearModelService` and derives the functional evaluation keys of all outputs.
+    pub fn new(model: LinearModel, label: &str) -> Result<Self, ModelError> {
+        Self::build(model, label, false)
+    }
+
+    /// Constructs a new `LinearModelService` which appends a constant-1 feature to the encrypted feature vectors,
+    /// carrying the biases. The model may have at most `L - 1` features.
+    pub fn with_bias_feature(model: LinearModel, label: &str) -> Result<Self, ModelError> {
+        Self::build(model, label, true)
+    }
+
+    fn build(model: LinearModel, label: &str, bias_feature: bool) -> Result<Self, ModelError> {
+        model.validate()?;
+        let encoded = if bias_feature { model.with_bias_feature() } else { model.clone() };
+        if encoded.features.len() > L {
+            return Err(ModelError::TooManyFeatures { max: L, actual: encoded.features.len() });
+        }
+        let fe = Dmcfe::<L>::new();
+        let keys = encoded.outputs
+            .iter()
+            .map(|out| Ok(fe.derive_fe_key(&pad(&encoded.encode_weights(out)?))))
+            .collect::<Result<_, ModelError>>()?;
+        Ok(Self {
+            model,
+            label: label.to_string(),
+            bias_feature,
+            encoded,
+            fe,
+            keys,
+        })
+    }
+
+    /// Encrypt a feature vector with one entry per feature of the model, each bounded by the feature bound of the
+    /// model.
+    pub fn encrypt<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<Fixed<Vec<DmcfeCipher>>, ModelError> {
+        if x.len() != self.model.features.len() {
+            return Err(ModelError::FeatureMismatch { expected: self.model.features.len(), actual: x.len() });
+        }
+        let mut x: Vec<f64> = x.iter().map(|&xi| xi.into()).collect();
+        if self.bias_feature {
+            x.push(1.0);
+        }
+        let int_x = pad::<L>(&self.encoded.encode_features(&x)?);
+        let scale = Scale::new(&BigInt::from(self.encoded.max_scale()));
+        let mut bound = encode_bound(self.model.feature_bound, &scale)?;
+        if self.bias_feature {
+            bound = bound.max(BigInt::from(self.encoded.max_scale()));
+        }
+        Ok(Fixed {
+            inner: self.fe.try_encrypt_with_label(&int_x, &bound, &self.label).map_err(ModelError::encrypt)?,
+            scale,
+        })
+    }
+
+    /// Compute the scores of all outputs of the model.
+    pub fn compute(&self, cipher: &Fixed<Vec<DmcfeCipher>>) -> Result<Vec<Score>, ModelError> {
+        let scale = self.encoded.result_scale();
+        self.encoded.outputs
+            .iter()
+            .zip(self.keys.iter())
+            .map(|(out, dk)| {
+                let result = self.fe.decrypt_with_label(&cipher.inner, dk, &self.label).map_err(ModelError::Decrypt)?;
+                Ok(Score {
+                    name: out.name.clone(),
+                    value: decode(&result, &scale) + out.bias,
+                })
+            })
+            .collect()
+    }
+
+    /// Compute the scores of all outputs of the model, each with a proof that it was decrypted correctly, which anyone
+    /// holding the ciphertext and the model can check with `LinearModel::verify_scores`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let scores = service.compute_verifiable(&cipher).unwrap();
+    /// service.model.verify_scores::<16>(&service.label, &cipher, &scores).unwrap();
+    /// ```
+    pub fn compute_verifiable(&self, cipher: &Fixed<Vec<DmcfeCipher>>) -> Result<Vec<VerifiableScore>, ModelError> {
+        let scale = self.encoded.result_scale();
+        self.encoded.outputs
+            .iter()
+            .zip(self.keys.iter())
+            .map(|(out, dk)| {
+                let result = self.fe.decrypt_with_label(&cipher.inner, dk, &self.label).map_err(ModelError::Decrypt)?;
+                Ok(VerifiableScore {
+                    score: Score {
+                        name: out.name.clone(),
+                        value: decode(&result, &scale) + out.bias,
+                    },
+                    proof: prove_dmcfe_decryption(&cipher.inner, dk, &self.label, &result),
+                    result,
+                })
+            })
+            .collect()
+    }
+}
+
+fn pad<const L: usize>(v: &[BigInt]) -> [BigInt; L] {
+    let mut padded = v.to_vec();
+    padded.resize(L, BigInt::from(0));
+    padded.try_into().unwrap()
+}
@@ -0,0 +1,228 @@
+use num_bigint::{BigInt};
+
+use crate::define::{G2Vector};
+use crate::ml::ModelError;
+use crate::ml::federated::{ClientUpdate, FederatedClient, FederatedServer, Weighting};
+
+
+/// The sufficient statistics `X^T X` and `X^T y` of a least-squares fit. With an intercept, `X` has an additional
+/// constant-1 column, the last one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SufficientStatistics {
+    pub dim: usize,
+    /// `X^T X` as a row-major `dim x dim` matrix.
+    pub xtx: Vec<f64>,
+    pub xty: Vec<f64>,
+}
+
+/// A fitted linear regression `y = <weights, x> + intercept`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearRegression {
+    pub weights: Vec<f64>,
+    pub intercept: f64,
+}
+
+impl SufficientStatistics {
+    pub fn from_data(x: &[Vec<f64>], y: &[f64], fit_intercept: bool) -> Result<Self, ModelError> {
+        if x.is_empty() || x[0].is_empty() {
+            return Err(ModelError::Empty);
+        }
+        if y.len() != x.len() {
+            return Err(ModelError::FeatureMismatch { expected: x.len(), actual: y.len() });
+        }
+        let features = x[0].len();
+        if let Some(xs) = x.iter().find(|xs| xs.len() != features) {
+            return Err(ModelError::FeatureMismatch { expected: features, actual: xs.len() });
+        }
+        let dim = features + fit_intercept as usize;
+        let mut stats = Self { dim, xtx: vec![0.0; dim * dim], xty: vec![0.0; dim] };
+        for (xs, &ys) in x.iter().zip(y.iter()) {
+            let row: Vec<f64> = xs.iter().cloned().chain(if fit_intercept { Some(1.0) } else { None }).collect();
+            for i in 0..dim {
+                for j in 0..dim {
+                    stats.xtx[i * dim + j] += row[i] * row[j];
+                }
+                stats.xty[i] += row[i] * ys;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Number of entries of `to_vec`: the upper triangle of `X^T X` followed by `X^T y`.
+    pub fn vec_len(dim: usize) -> usize {
+        dim * (dim + 1) / 2 + dim
+    }
+
+    /// Flatten the statistics into the upper triangle of the symmetric `X^T X`, row by row, followed by `X^T y`.
+    pub fn to_vec(&self) -> Vec<f64> {
+        let mut v = Vec::with_capacity(Self::vec_len(self.dim));
+        for i in 0..self.dim {
+            v.extend_from_slice(&self.xtx[i * self.dim + i..(i + 1) * self.dim]);
+        }
+        v.extend_from_slice(&self.xty);
+        v
+    }
+
+    pub fn from_vec(dim: usize, v: &[f64]) -> Result<Self, ModelError> {
+        if v.len() != Self::vec_len(dim) {
+            return Err(ModelError::FeatureMismatch { expected: Self::vec_len(dim), actual: v.len() });
+        }
+        let mut xtx = vec![0.0; dim * dim];
+        let mut k = 0;
+        for i in 0..dim {
+            for j in i..dim {
+                xtx[i * dim + j] = v[k];
+                xtx[j * dim + i] = v[k];
+                k += 1;
+            }
+        }
+        Ok(Self { dim, xtx, xty: v[k..].to_vec() })
+    }
+
+    /// Solve the normal equations `(X^T X + ridge * I) w = X^T y`. The intercept, if any, is not penalized.
+    pub fn solve(&self, ridge: f64, fit_intercept: bool) -> Result<LinearRegression, ModelError> {
+        let dim = self.dim;
+        let mut a = self.xtx.clone();
+        let penalized = if fit_intercept { dim - 1 } else { dim };
+        for i in 0..penalized {
+            a[i * dim + i] += ridge;
+        }
+        let mut w = solve_linear(&mut a, &mut self.xty.clone(), dim)?;
+        let intercept = if fit_intercept { w.pop().unwrap() } else { 0.0 };
+        Ok(LinearRegression { weights: w, intercept })
+    }
+}
+
+impl LinearRegression {
+    pub fn predict(&self, x: &[f64]) -> f64 {
+        self.weights.iter().zip(x.iter()).map(|(w, xi)| w * xi).sum::<f64>() + self.intercept
+    }
+}
+
+/// Gaussian elimination with partial pivoting on the row-major `n x n` matrix `a`.
+fn solve_linear(a: &mut [f64], b: &mut [f64], n: usize) -> Result<Vec<f64>, ModelError> {
+    let scale = a.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i * n + col].abs().partial_cmp(&a[j * n + col].abs()).unwrap())
+            .unwrap();
+        if a[pivot * n + col].abs() <= scale * 1e-12 {
+            return Err(ModelError::Singular);
+        }
+        for k in 0..n {
+            a.swap(col * n + k, pivot * n + k);
+        }
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let f = a[row * n + col] / a[col * n + col];
+            for k in col..n {
+                a[row * n + k] -= f * a[col * n + k];
+            }
+            b[row] -= f * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let s: f64 = (row + 1..n).map(|k| a[row * n + k] * x[k]).sum();
+        x[row] = (b[row] - s) / a[row * n + row];
+    }
+    Ok(x)
+}
+
+/// A data holder, e.g. a hospital, taking part in the encrypted fit of a linear regression over the union of the
+/// datasets of `L` holders.
+///
+/// Every holder encrypts the entries of its local `X^T X` and `X^T y` with `Dmcfe`, one label per entry, so that the
+/// aggregator only learns their sums over all holders. The entries must be bounded by `bound` in absolute value,
+/// which holders agree on in advance.
+///
+/// # Examples
+///
+/// ```ignore
+/// let holders = RegressionClient::<3>::setup("regression", 2, true, 1000, 1e4);
+/// let mut aggregator = RegressionAggregator::<3>::new("regression", 2, true, 0.1);
+/// for (h, (x, y)) in holders.iter().zip(datasets.iter()) {
+///     aggregator.submit(h.encrypt_statistics(aggregator.round(), x, y).unwrap()).unwrap();
+/// }
+/// let weights = aggregator.weights().unwrap();
+/// let shares: Vec<G2Vector> = holders.iter().map(|h| h.key_share(&weights).unwrap()).collect();
+/// let model = aggregator.fit(&shares).unwrap();
+/// ```
+pub struct RegressionClient<const L: usize> {
+    pub features: usize,
+    pub fit_intercept: bool,
+    client: FederatedClient<L>,
+}
+
+/// The aggregator of an encrypted linear regression fit: decrypts the summed statistics of all holders and solves
+/// the normal equations, with an optional ridge penalty.
+pub struct RegressionAggregator<const L: usize> {
+    pub features: usize,
+    pub fit_intercept: bool,
+    pub ridge: f64,
+    server: FederatedServer<L>,
+}
+
+impl<const L: usize> RegressionClient<L> {
+    /// Constructs the `L` data holders of a fit over `features` features. Statistics are encoded with `scale`.
+    pub fn setup(session: &str, features: usize, fit_intercept: bool, scale: u64, bound: f64) -> Vec<Self> {
+        FederatedClient::<L>::setup(session, scale, bound)
+            .into_iter()
+            .map(|client| Self { features, fit_intercept, client })
+            .collect()
+    }
+
+    pub fn index(&self) -> usize {
+        self.client.index
+    }
+
+    /// Encrypt the statistics of the local dataset for `round`.
+    pub fn encrypt_statistics(&self, round: u64, x: &[Vec<f64>], y: &[f64]) -> Result<ClientUpdate, ModelError> {
+        let stats = SufficientStatistics::from_data(x, y, self.fit_intercept)?;
+        if stats.dim != self.features + self.fit_intercept as usize {
+            return Err(ModelError::FeatureMismatch { expected: self.features, actual: x[0].len() });
+        }
+        Ok(self.client.encrypt_update(round, &stats.to_vec())?)
+    }
+
+    /// Derive this holder's share of the functional key summing the statistics.
+    pub fn key_share(&self, weights: &[BigInt]) -> Result<G2Vector, ModelError> {
+        Ok(self.client.key_share(weights)?)
+    }
+}
+
+impl<const L: usize> RegressionAggregator<L> {
+    pub fn new(session: &str, features: usize, fit_intercept: bool, ridge: f64) -> Self {
+        let dim = features + fit_intercept as usize;
+        Self {
+            features,
+            fit_intercept,
+            ridge,
+            server: FederatedServer::new(session, SufficientStatistics::vec_len(dim), Weighting::Uniform),
+        }
+    }
+
+    pub fn round(&self) -> u64 {
+        self.server.round()
+    }
+
+    pub fn submit(&mut self, update: ClientUpdate) -> Result<(), ModelError> {
+        Ok(self.server.submit(update)?)
+    }
+
+    /// The all-ones weights the holders derive their key shares for.
+    pub fn weights(&self) -> Result<Vec<BigInt>, ModelError> {
+        Ok(self.server.weights()?)
+    }
+
+    /// Decrypt the summed statistics of the current round.
+    pub fn aggregate(&mut self, key_shares: &[G2Vector]) -> Result<SufficientStatistics, ModelError> {
+        let sum = self.server.aggregate_sum(key_shares)?;
+        SufficientStatistics::from_vec(self.features + self.fit_intercept as usize, &sum)
+    }
+
+    /// Decrypt the summed statistics of the current round and fit the model.
+    pub fn fit(&mut self, key_shares: &[G2Vector]) -> Result<LinearRegression, ModelError> {
+        self.aggregate(key_shares)?.solve(self.ridge, self.fit_intercept)
+    }
+}
@@ -1,2 +1,85 @@
+use num_bigint::{BigInt};
+
+use crate::bounds::{DecryptError};
+use crate::math::fixed_point::{FixedPointError};
+use crate::ml::federated::{FederatedError};
+
+pub mod classification;
+pub mod convolution;
 pub mod disease_prediction;
+pub mod evaluation;
+pub mod federated;
+pub mod idx;
+pub mod linear_model;
+pub mod linear_regression;
+pub mod nearest_centroid;
 pub mod neural_network;
+pub mod npy;
+pub mod quadratic_network;
+pub mod quadratic_trainer;
+pub mod statistics;
+
+/// Errors of loading, encoding and evaluating the models of the ML applications.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    /// The model file could not be read.
+    Io(String),
+    /// The JSON model file is malformed.
+    Json(String),
+    /// The CSV model file is malformed at the given (1-based) line.
+    Csv { line: usize, message: String },
+    /// The model has no features, outputs or weights.
+    Empty,
+    /// A weight vector, the scales or an input does not have one entry per feature.
+    FeatureMismatch { expected: usize, actual: usize },
+    /// The model has more features than the service supports.
+    TooManyFeatures { max: usize, actual: usize },
+    /// A scale is zero, too large for the weights, or a feature scale does not divide the largest feature scale.
+    InvalidScale(u64),
+    /// The model does not have a number of outputs the classifier supports.
+    UnsupportedOutputs(usize),
+    /// Encoding the inputs or weights failed.
+    Encoding(FixedPointError),
+    /// Decrypting a score failed.
+    Decrypt(DecryptError),
+    /// The `.npy` file is malformed or has an unsupported data type.
+    Npy(String),
+    /// The IDX file is malformed or has an unsupported data type.
+    Idx(String),
+    /// An input exceeds the bound of the encryption scheme once encoded.
+    InputOutOfBound { bound: BigInt },
+    /// The normal equations of a regression have no unique solution.
+    Singular,
+    /// Encrypted aggregation across clients failed.
+    Federated(FederatedError),
+    /// A training label is not the index of a class.
+    InvalidLabel(usize),
+    /// A weight array does not have the expected shape.
+    Shape { expected: Vec<usize>, actual: Vec<usize> },
+}
+
+impl ModelError {
+    /// The error of a `try_encrypt_*` function of a scheme, for an input of a model.
+    pub(crate) fn encrypt(e: DecryptError) -> Self {
+        match e {
+            DecryptError::OutOfRange { range } => ModelError::InputOutOfBound { bound: range },
+            DecryptError::DimensionMismatch { expected, actual } => ModelError::FeatureMismatch { expected, actual },
+            e => ModelError::Decrypt(e),
+        }
+    }
+}
+
+impl From<FixedPointError> for ModelError {
+    fn from(e: FixedPointError) -> Self {
+        match e {
+            FixedPointError::Decrypt(e) => ModelError::Decrypt(e),
+            e => ModelError::Encoding(e),
+        }
+    }
+}
+
+impl From<FederatedError> for ModelError {
+    fn from(e: FederatedError) -> Self {
+        ModelError::Federated(e)
+    }
+}
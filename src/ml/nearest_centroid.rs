@@ -0,0 +1,134 @@
+use num_bigint::{BigInt};
+use num_traits::{One};
+
+use crate::bounds::{max_abs};
+use crate::math::fixed_point::{FixedPoint, encode_bound};
+use crate::math::matrix::{BigIntMatrix};
+use crate::ml::ModelError;
+use crate::quadratic_sgp::{Sgp, SgpCipher, SgpDecKey, SgpPlain};
+use crate::traits::FunctionalEncryption;
+use crate::utils::{quadratic_result};
+
+
+/// Nearest-centroid classification, or the assignment step of k-means, for points encrypted with `Sgp`.
+///
+/// A point `x` with `L - 1` features is encoded with fixed-point scale `s` and extended with a constant 1, and is
+/// encrypted as both vectors of `SgpPlain`. For an encoded centroid `c`,
+///
+/// ```text
+/// F = |  I    -c   |
+///     | -c^T  c^Tc |
+/// ```
+///
+/// gives `x^T F x = ||x - c||^2` at scale `s^2`. Every centroid becomes one functional key, and the service only
+/// reveals the index of the nearest centroid.
+///
+/// # Examples
+///
+/// ```ignore
+/// // points with 2 features
+/// let service = NearestCentroid::<3>::new(vec![vec![0.0, 0.0], vec![2.5, 1.0]], 10, 5.0).unwrap();
+/// let cipher = service.encrypt(&[2.0, 0.5]).unwrap();
+/// assert_eq!(service.assign(&cipher).unwrap(), 1);
+/// ```
+pub struct NearestCentroid<const L: usize> {
+    pub centroids: Vec<Vec<f64>>,
+    /// Bound on the absolute value of the features.
+    pub bound: f64,
+    codec: FixedPoint,
+    f: Vec<BigIntMatrix>,
+    keys: Vec<SgpDecKey>,
+    sgp: Sgp<L>,
+}
+
+impl<const L: usize> NearestCentroid<L> {
+    /// Constructs the service for `centroids` with `L - 1` features each, bounded by `bound` in absolute value, and
+    /// derives one functional key per centroid.
+    pub fn new(centroids: Vec<Vec<f64>>, scale: u64, bound: f64) -> Result<Self, ModelError> {
+        if centroids.is_empty() || L < 2 {
+            return Err(ModelError::Empty);
+        }
+        if let Some(c) = centroids.iter().find(|c| c.len() != L - 1) {
+            return Err(ModelError::FeatureMismatch { expected: L - 1, actual: c.len() });
+        }
+        let codec = FixedPoint::new(scale);
+        let f = centroids
+            .iter()
+            .map(|c| Ok(distance_matrix(&codec.encode_vec(c)?)))
+            .collect::<Result<Vec<_>, ModelError>>()?;
+        let sgp = Sgp::<L>::new();
+        let keys = f.iter().map(|fi| sgp.derive_fe_key(fi)).collect();
+        Ok(Self { centroids, bound, codec, f, keys, sgp })
+    }
+
+    /// Encode a point as the plaintext vector of `Sgp`: the encoded features followed by a constant 1.
+    pub fn encode<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<[BigInt; L], ModelError> {
+        if x.len() != L - 1 {
+            return Err(ModelError::FeatureMismatch { expected: L - 1, actual: x.len() });
+        }
+        let bound = self.encoded_bound()?;
+        let encoded = self.codec.encode_vec(x)?;
+        if max_abs(&encoded) > bound {
+            return Err(ModelError::InputOutOfBound { bound });
+        }
+        Ok(array_init::array_init(|i| if i < L - 1 { encoded[i].clone() } else { BigInt::one() }))
+    }
+
+    pub fn encrypt<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<SgpCipher<L>, ModelError> {
+        let encoded = self.encode(x)?;
+        let bound = self.encoded_bound()?.max(BigInt::one());
+        let plain = SgpPlain { x: encoded.clone(), y: encoded };
+        self.sgp.try_encrypt_with_bound(&plain, &bound, &bound).map_err(ModelError::encrypt)
+    }
+
+    /// Index of the centroid nearest to the encrypted point. Ties are broken towards the smaller index.
+    pub fn assign(&self, cipher: &SgpCipher<L>) -> Result<usize, ModelError> {
+        let distances = self.keys
+            .iter()
+            .map(|dk| self.sgp.decrypt(cipher, dk).map_err(ModelError::Decrypt))
+            .collect::<Result<Vec<BigInt>, _>>()?;
+        Ok(argmin(&distances))
+    }
+
+    /// Assign every encrypted point to its nearest centroid.
+    pub fn assign_all(&self, ciphers: &[SgpCipher<L>]) -> Result<Vec<usize>, ModelError> {
+        ciphers.iter().map(|c| self.assign(c)).collect()
+    }
+
+    /// Plaintext reference of `assign`, evaluating the same matrices on the encoded point.
+    pub fn assign_plain<T: Copy + Into<f64>>(&self, x: &[T]) -> Result<usize, ModelError> {
+        let encoded = self.encode(x)?;
+        let distances: Vec<BigInt> = self.f.iter().map(|fi| quadratic_result(&encoded, &encoded, fi)).collect();
+        Ok(argmin(&distances))
+    }
+
+    fn encoded_bound(&self) -> Result<BigInt, ModelError> {
+        Ok(encode_bound(self.bound, &self.codec.scale())?)
+    }
+}
+
+/// The matrix `F` with `(x, 1)^T F (x, 1) = ||x - c||^2`.
+fn distance_matrix(c: &[BigInt]) -> BigIntMatrix {
+    let n = c.len();
+    let mut f = BigIntMatrix::new(n + 1, n + 1);
+    for (i, ci) in c.iter().enumerate() {
+        f.set_element(i, i, &BigInt::one());
+        f.set_element(i, n, &-ci);
+        f.set_element(n, i, &-ci);
+    }
+    f.set_element(n, n, &c.iter().map(|ci| ci * ci).sum());
+    f
+}
+
+fn argmin(v: &[BigInt]) -> usize {
+    (0..v.len()).fold(0, |best, i| if v[i] < v[best] { i } else { best })
+}
+
+/// Plaintext squared distances of a point to every centroid.
+pub fn squared_distances(x: &[f64], centroids: &[Vec<f64>]) -> Vec<f64> {
+    centroids
+        .iter()
+        .map(|c| c.iter().zip(x.iter()).map(|(ci, xi)| (ci - xi) * (ci - xi)).sum())
+        .collect()
+}
+
@@ -1,8 +1,13 @@
 use num_bigint::{BigInt};
 
-use crate::quadratic_sgp::{Sgp, SgpPlain, SgpCipher};
+use crate::quadratic_sgp::{Sgp, SgpPlain, SgpCipher, SgpPubKey};
 use crate::math::matrix::{BigIntMatrix};
+use crate::ml::ModelError;
+use crate::ml::quadratic_network::{QuantizedNetwork};
 use crate::traits::FunctionalEncryption;
+use crate::zk::VerifyError;
+use crate::zk::decryption::{prove_sgp_decryption, verify_sgp_decryption};
+use crate::zk::sigma::{PairingProof};
 
 /// The neural network application in the following papers:
 ///
@@ -19,7 +24,8 @@ pub struct NeuralNetwork<const L: usize> {
 impl<const L: usize> NeuralNetwork<L> {
 
     /// Constructs a new `NeuralNetwork` application. `p` is the projection matrix for dimensionality reduction.
-    /// `q` is a vector of model matrices, each of which denotes a model for a binary prediction of a class. 
+    /// `q` is a vector of model matrices, each of which denotes a model for a binary prediction of a class. The inputs
+    /// are bounded by 256, the range of raw pixel values; `from_quantized` takes the bound of a quantized network.
     ///
     /// # Examples
     /// ```ignore
@@ -47,8 +53,30 @@ impl<const L: usize> NeuralNetwork<L> {
             sgp
         }
     }
+
+    /// Constructs a new `NeuralNetwork` application from an imported and quantized network, for inputs bounded by
+    /// `x_bound` in absolute value, i.e. the bound the network was quantized for. The input dimension of the network
+    /// must be `L`.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let weights = QuadraticWeights::load_npy("p.npy", "q.npy").unwrap();
+    /// let x_bound = BigInt::from(256);
+    /// let (network, report) = weights.quantize_bounded(&x_bound, &BigInt::from(1u64 << 32)).unwrap();
+    /// println!("max quantization error: {}", report.max_abs());
+    /// network.save("network.json").unwrap();
+    /// let service = NeuralNetwork::<784>::from_quantized(&QuantizedNetwork::load("network.json").unwrap(), &x_bound).unwrap();
+    /// ```
+    pub fn from_quantized(network: &QuantizedNetwork, x_bound: &BigInt) -> Result<Self, ModelError> {
+        if network.n != L {
+            return Err(ModelError::FeatureMismatch { expected: L, actual: network.n });
+        }
+        let mut service = Self::new(&network.p(), &network.q());
+        service.bound = x_bound.clone();
+        Ok(service)
+    }
  
-    /// Encrypt client's input: a vector of integer values.
+    /// Encrypt client's input: a vector of integer values, bounded by `bound` in absolute value.
     ///
     /// # Examples
     ///
@@ -60,7 +88,7 @@ impl<const L: usize> NeuralNetwork<L> {
     /// ```
     pub fn encrypt(&self, x: &[BigInt; L]) -> SgpCipher<L> {
         let plain = SgpPlain {x: x.clone(), y: x.clone()};
-        self.sgp.encrypt(&plain)
+        self.sgp.encrypt_with_bound(&plain, &self.bound, &self.bound)
     }
 
     /// Compute the one-laye neural network model. 
@@ -76,14 +104,53 @@ impl<const L: usize> NeuralNetwork<L> {
         
         for i in 0..self.q.len() {
             let dk_i = self.sgp.derive_fe_key_projected(&self.q[i], &self.p);
-            let res_i = self.sgp.decrypt(&new_cipher, &dk_i, &self.bound).unwrap();
+            let res_i = self.sgp.decrypt(&new_cipher, &dk_i).unwrap();
             res.push(res_i);
         }
         res
     }
 
-}
-
+    /// The master public key, which together with `p` and `q` lets anyone verify the results of
+    /// `compute_verifiable`.
+    pub fn pk(&self) -> &SgpPubKey {
+        self.sgp.pk()
+    }
 
+    /// Compute the one-layer neural network model, and prove for every output that it is the decryption of the
+    /// projected ciphertext with the key of its model matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let results = service.compute_verifiable(&cipher);
+    /// // the client, or anyone holding the ciphertext
+    /// NeuralNetwork::verify(service.pk(), &service.p, &service.q, &cipher, &results).unwrap();
+    /// ```
+    pub fn compute_verifiable(&self, cipher: &SgpCipher<L>) -> Vec<(BigInt, PairingProof)> {
+        let new_cipher = cipher.project(&self.p);
+        let pk = self.sgp.pk().project(&self.p);
+        self.q
+            .iter()
+            .map(|q| {
+                let dk = self.sgp.derive_fe_key_projected(q, &self.p);
+                let res = self.sgp.decrypt(&new_cipher, &dk).unwrap();
+                let proof = prove_sgp_decryption(&pk, &new_cipher, &dk, &res);
+                (res, proof)
+            })
+            .collect()
+    }
 
+    /// Verify the results of `compute_verifiable` for `cipher`, with the master public key `pk` and the model.
+    pub fn verify(pk: &SgpPubKey, p: &BigIntMatrix, q: &[BigIntMatrix], cipher: &SgpCipher<L>, results: &[(BigInt, PairingProof)]) -> Result<(), VerifyError> {
+        if results.len() != q.len() {
+            return Err(VerifyError::InputLength { expected: q.len(), actual: results.len() });
+        }
+        let new_cipher = cipher.project(p);
+        let pk = pk.project(p);
+        for (qi, (res, proof)) in q.iter().zip(results.iter()) {
+            verify_sgp_decryption(&pk, &new_cipher, qi, res, proof)?;
+        }
+        Ok(())
+    }
 
+}
@@ -0,0 +1,147 @@
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::ml::ModelError;
+
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// A dense array read from or written to a NumPy `.npy` file, with the entries in row-major (C) order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl NpyArray {
+    pub fn new(shape: Vec<usize>, data: Vec<f64>) -> Self {
+        if shape.iter().product::<usize>() != data.len() {
+            panic!("Malformed input: shape {:?}, data.len ({})", shape, data.len());
+        }
+        Self { shape, data }
+    }
+
+    /// Read an array from a `.npy` file (format versions 1.0 to 3.0). Little-endian `f8`, `f4`, `i8` and `i4`
+    /// arrays are supported, in C or Fortran order; all of them are converted to `f64`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let bytes = fs::read(path).map_err(|e| ModelError::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Write the array as a version 1.0 `.npy` file of little-endian `f8`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ModelError> {
+        fs::write(path, self.to_bytes()).map_err(|e| ModelError::Io(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        if bytes.len() < 10 || &bytes[..6] != MAGIC {
+            return Err(ModelError::Npy("missing magic string".to_string()));
+        }
+        let (header_len, offset) = match bytes[6] {
+            1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+            2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize, 12),
+            v => return Err(ModelError::Npy(format!("unsupported version {}", v))),
+        };
+        let header = bytes
+            .get(offset..offset + header_len)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .ok_or_else(|| ModelError::Npy("malformed header".to_string()))?;
+
+        let descr = header_value(header, "descr")?;
+        let descr = descr.trim_matches(['\'', '"']);
+        let fortran_order = match header_value(header, "fortran_order")? {
+            "True" => true,
+            "False" => false,
+            v => return Err(ModelError::Npy(format!("invalid fortran_order '{}'", v))),
+        };
+        let shape = parse_shape(header_value(header, "shape")?)?;
+
+        let size = match descr {
+            "<f8" | "<i8" => 8,
+            "<f4" | "<i4" => 4,
+            d => return Err(ModelError::Npy(format!("unsupported dtype '{}'", d))),
+        };
+        let len: usize = shape.iter().product();
+        let body = &bytes[offset + header_len..];
+        if body.len() != len * size {
+            return Err(ModelError::Npy(format!("expected {} bytes of data, found {}", len * size, body.len())));
+        }
+        let values: Vec<f64> = body
+            .chunks(size)
+            .map(|c| match descr {
+                "<f8" => f64::from_le_bytes(c.try_into().unwrap()),
+                "<i8" => i64::from_le_bytes(c.try_into().unwrap()) as f64,
+                "<f4" => f32::from_le_bytes(c.try_into().unwrap()) as f64,
+                _ => i32::from_le_bytes(c.try_into().unwrap()) as f64,
+            })
+            .collect();
+
+        let data = if fortran_order { fortran_to_c(&values, &shape) } else { values };
+        Ok(Self { shape, data })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let shape = match self.shape.len() {
+            1 => format!("({},)", self.shape[0]),
+            _ => format!("({})", self.shape.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+        // the header is padded with spaces so that the data is 64-byte aligned, and ends with a newline
+        let total = MAGIC.len() + 4 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - total % 64) % 64));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + 8 * self.data.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for v in self.data.iter() {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// The raw value of `key` in the Python dict literal of a `.npy` header.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, ModelError> {
+    let missing = || ModelError::Npy(format!("header has no '{}'", key));
+    let start = header
+        .find(&format!("'{}'", key))
+        .ok_or_else(missing)?;
+    let rest = header[start + key.len() + 2..].trim_start();
+    let rest = rest.strip_prefix(':').ok_or_else(missing)?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|e| e + 1)
+    } else {
+        rest.find([',', '}'])
+    };
+    end.map(|e| rest[..e].trim()).ok_or_else(missing)
+}
+
+fn parse_shape(s: &str) -> Result<Vec<usize>, ModelError> {
+    s.trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse::<usize>().map_err(|_| ModelError::Npy(format!("invalid shape {}", s))))
+        .collect()
+}
+
+/// Reorder column-major (Fortran) data to row-major (C) order.
+fn fortran_to_c(values: &[f64], shape: &[usize]) -> Vec<f64> {
+    let mut data = Vec::with_capacity(values.len());
+    for flat in 0..values.len() {
+        // the C-order index `flat` as a multi-index, then as a Fortran-order offset
+        let mut rem = flat;
+        let mut offset = 0;
+        for k in (0..shape.len()).rev() {
+            offset += (rem % shape[k]) * shape[..k].iter().product::<usize>();
+            rem /= shape[k];
+        }
+        data.push(values[offset]);
+    }
+    data
+}
@@ -0,0 +1,325 @@
+use num_bigint::{BigInt};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::bounds::{DecryptError, max_abs_column_sum, max_abs_matrix, quadratic_range};
+use crate::define::{MODULUS};
+use crate::math::matrix::{BigIntMatrix};
+use crate::ml::ModelError;
+use crate::ml::npy::{NpyArray};
+
+
+/// The float weights of a trained quadratic-activation network `x -> argmax_i (P^T x)^T Q_i (P^T x)`, as computed
+/// by `NeuralNetwork`. `p` is the `n x d` projection matrix and `q` holds one `d x d` matrix per class.
+///
+/// # File formats
+///
+/// JSON, with nested row-major arrays and optional class names (defaulting to `0`, `1`, ...):
+///
+/// ```text
+/// {
+///   "classes": ["zero", "one"],
+///   "p": [[0.5, -0.25], [0.125, 1.0], [0.0, 0.75]],
+///   "q": [[[1.0, 0.0], [0.0, -1.0]], [[-1.0, 0.5], [0.5, 1.0]]]
+/// }
+/// ```
+///
+/// NumPy: a 2-dimensional `.npy` file for `P` and a 3-dimensional one of shape `(classes, d, d)` for the `Q_i`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuadraticWeights {
+    #[serde(default)]
+    pub classes: Vec<String>,
+    pub p: Vec<Vec<f64>>,
+    pub q: Vec<Vec<Vec<f64>>>,
+}
+
+/// The quantization error of one weight matrix, in the units of the float weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizationError {
+    pub max_abs: f64,
+    pub rms: f64,
+}
+
+/// The quantization errors of `P` and of every `Q_i`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizationReport {
+    pub p: QuantizationError,
+    pub q: Vec<QuantizationError>,
+}
+
+/// A quantized quadratic network: `P` and the `Q_i` rounded to integers after multiplying with `p_scale` and
+/// `q_scale`. It is serialized as JSON with the matrices flattened in row-major order, and can be loaded into
+/// `NeuralNetwork::from_quantized`.
+///
+/// For inputs encoded with scale `x_scale`, the scores computed by `NeuralNetwork` have the scale
+/// `x_scale^2 * p_scale^2 * q_scale`, see `result_scale`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedNetwork {
+    pub classes: Vec<String>,
+    pub n: usize,
+    pub d: usize,
+    pub p_scale: u64,
+    pub q_scale: u64,
+    pub p: Vec<i64>,
+    pub q: Vec<Vec<i64>>,
+}
+
+impl QuadraticWeights {
+    /// Load the weights from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let content = fs::read_to_string(path).map_err(|e| ModelError::Io(e.to_string()))?;
+        Self::from_json(&content)
+    }
+
+    /// Load the weights from a `.npy` file for `P` and one for the stacked `Q_i`.
+    pub fn load_npy<P: AsRef<Path>, Q: AsRef<Path>>(p_path: P, q_path: Q) -> Result<Self, ModelError> {
+        Self::from_arrays(&NpyArray::load(p_path)?, &NpyArray::load(q_path)?)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, ModelError> {
+        let mut weights: QuadraticWeights = serde_json::from_str(content).map_err(|e| ModelError::Json(e.to_string()))?;
+        weights.fill_classes();
+        weights.validate()?;
+        Ok(weights)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Constructs the weights from an `n x d` array `p` and a `k x d x d` array `q`.
+    pub fn from_arrays(p: &NpyArray, q: &NpyArray) -> Result<Self, ModelError> {
+        if p.shape.len() != 2 {
+            return Err(ModelError::Npy(format!("expected a 2-dimensional array for P, found shape {:?}", p.shape)));
+        }
+        let (n, d) = (p.shape[0], p.shape[1]);
+        if q.shape.len() != 3 || q.shape[1] != d || q.shape[2] != d {
+            let k = q.shape.first().cloned().unwrap_or(0);
+            return Err(ModelError::Shape { expected: vec![k, d, d], actual: q.shape.clone() });
+        }
+        let mut weights = Self {
+            classes: Vec::new(),
+            p: (0..n).map(|i| p.data[i * d..(i + 1) * d].to_vec()).collect(),
+            q: q.data
+                .chunks(d * d)
+                .map(|qi| qi.chunks(d).map(|row| row.to_vec()).collect())
+                .collect(),
+        };
+        weights.fill_classes();
+        weights.validate()?;
+        Ok(weights)
+    }
+
+    /// Input dimension `n` of the network.
+    pub fn n(&self) -> usize {
+        self.p.len()
+    }
+
+    /// Dimension `d` of the projection.
+    pub fn d(&self) -> usize {
+        self.p.first().map(|row| row.len()).unwrap_or(0)
+    }
+
+    /// Check that `P` is an `n x d` matrix, that every `Q_i` is `d x d` and that there is one class name per `Q_i`.
+    pub fn validate(&self) -> Result<(), ModelError> {
+        let (n, d) = (self.n(), self.d());
+        if n == 0 || d == 0 || self.q.is_empty() {
+            return Err(ModelError::Empty);
+        }
+        if let Some(row) = self.p.iter().find(|row| row.len() != d) {
+            return Err(ModelError::Shape { expected: vec![d], actual: vec![row.len()] });
+        }
+        for qi in self.q.iter() {
+            if qi.len() != d || qi.iter().any(|row| row.len() != d) {
+                return Err(ModelError::Shape { expected: vec![d, d], actual: vec![qi.len(), qi.first().map(|r| r.len()).unwrap_or(0)] });
+            }
+        }
+        if self.classes.len() != self.q.len() {
+            return Err(ModelError::UnsupportedOutputs(self.classes.len()));
+        }
+        Ok(())
+    }
+
+    /// Plaintext float evaluation: the score `(P^T x)^T Q_i (P^T x)` of every class.
+    pub fn evaluate(&self, x: &[f64]) -> Result<Vec<f64>, ModelError> {
+        if x.len() != self.n() {
+            return Err(ModelError::FeatureMismatch { expected: self.n(), actual: x.len() });
+        }
+        let z: Vec<f64> = (0..self.d())
+            .map(|j| x.iter().zip(self.p.iter()).map(|(xi, row)| xi * row[j]).sum())
+            .collect();
+        Ok(self.q
+            .iter()
+            .map(|qi| {
+                qi.iter()
+                    .zip(z.iter())
+                    .map(|(row, zj)| zj * row.iter().zip(z.iter()).map(|(qjk, zk)| qjk * zk).sum::<f64>())
+                    .sum()
+            })
+            .collect())
+    }
+
+    /// Plaintext float prediction: the index of the class with the largest score.
+    pub fn predict(&self, x: &[f64]) -> Result<usize, ModelError> {
+        let scores = self.evaluate(x)?;
+        Ok((0..scores.len()).fold(0, |best, i| if scores[i] > scores[best] { i } else { best }))
+    }
+
+    /// Quantize `P` with `p_scale` and the `Q_i` with `q_scale`, reporting the quantization error of every matrix.
+    pub fn quantize(&self, p_scale: u64, q_scale: u64) -> Result<(QuantizedNetwork, QuantizationReport), ModelError> {
+        self.validate()?;
+        let p_flat: Vec<f64> = self.p.concat();
+        let q_flat: Vec<Vec<f64>> = self.q.iter().map(|qi| qi.concat()).collect();
+
+        let network = QuantizedNetwork {
+            classes: self.classes.clone(),
+            n: self.n(),
+            d: self.d(),
+            p_scale,
+            q_scale,
+            p: quantize_all(&p_flat, p_scale)?,
+            q: q_flat.iter().map(|qi| quantize_all(qi, q_scale)).collect::<Result<_, _>>()?,
+        };
+        let report = QuantizationReport {
+            p: quantization_error(&p_flat, &network.p, p_scale),
+            q: q_flat
+                .iter()
+                .zip(network.q.iter())
+                .map(|(qi, qi_int)| quantization_error(qi, qi_int, q_scale))
+                .collect(),
+        };
+        Ok((network, report))
+    }
+
+    /// Quantize `P` and the `Q_i` with the largest common power-of-two scale for which the range searched by
+    /// `Sgp::decrypt` stays within `max_range`, for inputs bounded by `x_bound` in absolute value. Decryption of
+    /// the scores of such inputs then always succeeds, and takes time proportional to `sqrt(max_range)`.
+    pub fn quantize_bounded(&self, x_bound: &BigInt, max_range: &BigInt) -> Result<(QuantizedNetwork, QuantizationReport), ModelError> {
+        if max_range * 2 >= *MODULUS {
+            return Err(ModelError::Decrypt(DecryptError::RangeTooLarge { range: max_range.clone() }));
+        }
+        let mut best = self.quantize(1, 1)?;
+        let range = best.0.decryption_range(x_bound);
+        if range > *max_range {
+            return Err(ModelError::Decrypt(DecryptError::RangeTooLarge { range }));
+        }
+        for shift in 1..32 {
+            let candidate = self.quantize(1 << shift, 1 << shift)?;
+            if candidate.0.decryption_range(x_bound) > *max_range {
+                break;
+            }
+            best = candidate;
+        }
+        Ok(best)
+    }
+
+    fn fill_classes(&mut self) {
+        if self.classes.is_empty() {
+            self.classes = (0..self.q.len()).map(|i| i.to_string()).collect();
+        }
+    }
+}
+
+impl QuantizationReport {
+    /// Largest absolute quantization error over all matrices.
+    pub fn max_abs(&self) -> f64 {
+        self.q.iter().map(|e| e.max_abs).fold(self.p.max_abs, f64::max)
+    }
+}
+
+impl QuantizedNetwork {
+    /// Load a quantized network from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let content = fs::read_to_string(path).map_err(|e| ModelError::Io(e.to_string()))?;
+        Self::from_json(&content)
+    }
+
+    /// Save the quantized network as a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ModelError> {
+        fs::write(path, self.to_json()).map_err(|e| ModelError::Io(e.to_string()))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, ModelError> {
+        let network: QuantizedNetwork = serde_json::from_str(content).map_err(|e| ModelError::Json(e.to_string()))?;
+        network.validate()?;
+        Ok(network)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Check the dimensions of the matrices, the number of class names and the scales.
+    pub fn validate(&self) -> Result<(), ModelError> {
+        if self.n == 0 || self.d == 0 || self.q.is_empty() {
+            return Err(ModelError::Empty);
+        }
+        if self.p.len() != self.n * self.d {
+            return Err(ModelError::Shape { expected: vec![self.n * self.d], actual: vec![self.p.len()] });
+        }
+        if let Some(qi) = self.q.iter().find(|qi| qi.len() != self.d * self.d) {
+            return Err(ModelError::Shape { expected: vec![self.d * self.d], actual: vec![qi.len()] });
+        }
+        if self.classes.len() != self.q.len() {
+            return Err(ModelError::UnsupportedOutputs(self.classes.len()));
+        }
+        if self.p_scale == 0 || self.q_scale == 0 {
+            return Err(ModelError::InvalidScale(0));
+        }
+        Ok(())
+    }
+
+    /// The integer projection matrix `P`.
+    pub fn p(&self) -> BigIntMatrix {
+        BigIntMatrix::new_ints(&self.p, self.n, self.d)
+    }
+
+    /// The integer model matrices `Q_i`.
+    pub fn q(&self) -> Vec<BigIntMatrix> {
+        self.q.iter().map(|qi| BigIntMatrix::new_ints(qi, self.d, self.d)).collect()
+    }
+
+    /// The range searched by `Sgp::decrypt` for the scores of inputs bounded by `x_bound` in absolute value, as
+    /// derived from the bounds carried by the projected ciphertext and the keys of `NeuralNetwork`.
+    pub fn decryption_range(&self, x_bound: &BigInt) -> BigInt {
+        let z_bound = x_bound * max_abs_column_sum(&self.p());
+        let q_bound = self.q().iter().map(max_abs_matrix).max().unwrap_or_default();
+        quadratic_range(&z_bound, &z_bound, &q_bound, self.d, self.d)
+    }
+
+    /// Scale of the scores computed by `NeuralNetwork` for inputs encoded with scale `x_scale`.
+    pub fn result_scale(&self, x_scale: u64) -> BigInt {
+        let xp = BigInt::from(x_scale) * self.p_scale;
+        &xp * &xp * self.q_scale
+    }
+}
+
+fn quantize_all(w: &[f64], scale: u64) -> Result<Vec<i64>, ModelError> {
+    if scale == 0 {
+        return Err(ModelError::InvalidScale(0));
+    }
+    w.iter()
+        .map(|wi| {
+            let v = (wi * scale as f64).round();
+            // also rejects NaN weights
+            if v.abs() < i64::MAX as f64 {
+                Ok(v as i64)
+            } else {
+                Err(ModelError::InvalidScale(scale))
+            }
+        })
+        .collect()
+}
+
+fn quantization_error(w: &[f64], w_int: &[i64], scale: u64) -> QuantizationError {
+    let errors: Vec<f64> = w
+        .iter()
+        .zip(w_int.iter())
+        .map(|(wi, &vi)| (wi - vi as f64 / scale as f64).abs())
+        .collect();
+    QuantizationError {
+        max_abs: errors.iter().cloned().fold(0.0, f64::max),
+        rms: (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt(),
+    }
+}
@@ -0,0 +1,198 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::ml::ModelError;
+use crate::ml::classification::{softmax};
+use crate::ml::quadratic_network::{QuadraticWeights};
+
+
+/// The update rule of `QuadraticTrainer`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Optimizer {
+    /// Plain minibatch gradient descent.
+    Sgd { learning_rate: f64 },
+    /// Adam, see Kingma, D.P., Ba, J.: Adam: A method for stochastic optimization. ICLR 2015.
+    Adam { learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64 },
+}
+
+impl Optimizer {
+    /// Adam with the default parameters of the paper.
+    pub fn adam(learning_rate: f64) -> Self {
+        Optimizer::Adam { learning_rate, beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }
+    }
+}
+
+/// A plaintext trainer for the quadratic-activation network of \[SGP2018\], `x -> argmax_i (P^T x)^T Q_i (P^T x)`,
+/// minimizing the softmax cross-entropy of the scores. The trained float weights are turned into the integer model
+/// of `NeuralNetwork` with `QuadraticWeights::quantize_bounded`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let trainer = QuadraticTrainer::new(5, Optimizer::adam(0.05));
+/// let weights = trainer.train(&x, &labels, 10).unwrap();
+/// let x_bound = BigInt::from(256);
+/// let (network, report) = weights.quantize_bounded(&x_bound, &BigInt::from(1u64 << 32)).unwrap();
+/// let service = NeuralNetwork::<784>::from_quantized(&network, &x_bound).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadraticTrainer {
+    /// Dimension of the projection `P^T x`.
+    pub d: usize,
+    pub optimizer: Optimizer,
+    pub epochs: usize,
+    pub batch_size: usize,
+    /// Seed of the weight initialization and of the shuffling of the samples.
+    pub seed: u64,
+}
+
+impl QuadraticTrainer {
+    /// Constructs a trainer running 50 epochs with minibatches of 16 samples.
+    pub fn new(d: usize, optimizer: Optimizer) -> Self {
+        Self {
+            d,
+            optimizer,
+            epochs: 50,
+            batch_size: 16,
+            seed: 0,
+        }
+    }
+
+    /// Train a network on the samples `x` with the class indices `labels`.
+    pub fn train(&self, x: &[Vec<f64>], labels: &[usize], classes: usize) -> Result<QuadraticWeights, ModelError> {
+        check_data(x, labels, classes)?;
+        if self.d == 0 {
+            return Err(ModelError::Empty);
+        }
+        let (n, d) = (x[0].len(), self.d);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        // P and the Q_i, flattened into one parameter vector
+        let p_len = n * d;
+        let p_init = 1.0 / (n as f64).sqrt();
+        let q_init = 1.0 / d as f64;
+        let mut theta: Vec<f64> = Vec::with_capacity(p_len + classes * d * d);
+        theta.extend((0..p_len).map(|_| rng.gen_range(-p_init..p_init)));
+        theta.extend((0..classes * d * d).map(|_| rng.gen_range(-q_init..q_init)));
+        let mut m = vec![0.0; theta.len()];
+        let mut v = vec![0.0; theta.len()];
+        let mut t = 0;
+
+        let mut order: Vec<usize> = (0..x.len()).collect();
+        for _epoch in 0..self.epochs {
+            order.shuffle(&mut rng);
+            for batch in order.chunks(self.batch_size.max(1)) {
+                let mut grad = vec![0.0; theta.len()];
+                for &s in batch {
+                    accumulate_gradient(&theta, n, d, classes, &x[s], labels[s], &mut grad);
+                }
+                grad.iter_mut().for_each(|g| *g /= batch.len() as f64);
+
+                t += 1;
+                match self.optimizer {
+                    Optimizer::Sgd { learning_rate } => {
+                        for (w, g) in theta.iter_mut().zip(grad.iter()) {
+                            *w -= learning_rate * g;
+                        }
+                    }
+                    Optimizer::Adam { learning_rate, beta1, beta2, epsilon } => {
+                        let c1 = 1.0 - beta1.powi(t);
+                        let c2 = 1.0 - beta2.powi(t);
+                        for i in 0..theta.len() {
+                            m[i] = beta1 * m[i] + (1.0 - beta1) * grad[i];
+                            v[i] = beta2 * v[i] + (1.0 - beta2) * grad[i] * grad[i];
+                            theta[i] -= learning_rate * (m[i] / c1) / ((v[i] / c2).sqrt() + epsilon);
+                        }
+                    }
+                }
+            }
+        }
+
+        let weights = QuadraticWeights {
+            classes: (0..classes).map(|i| i.to_string()).collect(),
+            p: theta[..p_len].chunks(d).map(|row| row.to_vec()).collect(),
+            q: theta[p_len..]
+                .chunks(d * d)
+                .map(|qi| qi.chunks(d).map(|row| row.to_vec()).collect())
+                .collect(),
+        };
+        weights.validate()?;
+        Ok(weights)
+    }
+}
+
+/// Mean softmax cross-entropy of the network on the samples `x` with the class indices `labels`.
+pub fn cross_entropy(weights: &QuadraticWeights, x: &[Vec<f64>], labels: &[usize]) -> Result<f64, ModelError> {
+    check_data(x, labels, weights.q.len())?;
+    let mut loss = 0.0;
+    for (xs, &l) in x.iter().zip(labels.iter()) {
+        let p = softmax(&weights.evaluate(xs)?);
+        loss -= p[l].max(f64::MIN_POSITIVE).ln();
+    }
+    Ok(loss / x.len() as f64)
+}
+
+/// Fraction of the samples `x` the network assigns to the class given by `labels`.
+pub fn accuracy(weights: &QuadraticWeights, x: &[Vec<f64>], labels: &[usize]) -> Result<f64, ModelError> {
+    check_data(x, labels, weights.q.len())?;
+    let mut correct = 0;
+    for (xs, &l) in x.iter().zip(labels.iter()) {
+        if weights.predict(xs)? == l {
+            correct += 1;
+        }
+    }
+    Ok(correct as f64 / x.len() as f64)
+}
+
+fn check_data(x: &[Vec<f64>], labels: &[usize], classes: usize) -> Result<(), ModelError> {
+    if x.is_empty() || x[0].is_empty() {
+        return Err(ModelError::Empty);
+    }
+    if labels.len() != x.len() {
+        return Err(ModelError::FeatureMismatch { expected: x.len(), actual: labels.len() });
+    }
+    if classes < 2 {
+        return Err(ModelError::UnsupportedOutputs(classes));
+    }
+    if let Some(xs) = x.iter().find(|xs| xs.len() != x[0].len()) {
+        return Err(ModelError::FeatureMismatch { expected: x[0].len(), actual: xs.len() });
+    }
+    if let Some(&l) = labels.iter().find(|&&l| l >= classes) {
+        return Err(ModelError::InvalidLabel(l));
+    }
+    Ok(())
+}
+
+/// Add the gradient of the cross-entropy of one sample to `grad`, with the parameters laid out as in `theta`.
+fn accumulate_gradient(theta: &[f64], n: usize, d: usize, classes: usize, x: &[f64], label: usize, grad: &mut [f64]) {
+    let (p, q) = theta.split_at(n * d);
+    let (grad_p, grad_q) = grad.split_at_mut(n * d);
+
+    let z: Vec<f64> = (0..d).map(|j| (0..n).map(|i| x[i] * p[i * d + j]).sum()).collect();
+    let scores: Vec<f64> = (0..classes)
+        .map(|c| {
+            let qc = &q[c * d * d..(c + 1) * d * d];
+            (0..d).map(|j| z[j] * (0..d).map(|k| qc[j * d + k] * z[k]).sum::<f64>()).sum()
+        })
+        .collect();
+    let probs = softmax(&scores);
+
+    // d loss / d score_c = p_c - [c == label], then backpropagate through z^T Q_c z and z = P^T x
+    let mut grad_z = vec![0.0; d];
+    for c in 0..classes {
+        let g = probs[c] - if c == label { 1.0 } else { 0.0 };
+        let qc = &q[c * d * d..(c + 1) * d * d];
+        for j in 0..d {
+            for k in 0..d {
+                grad_q[c * d * d + j * d + k] += g * z[j] * z[k];
+                grad_z[j] += g * (qc[j * d + k] + qc[k * d + j]) * z[k];
+            }
+        }
+    }
+    for i in 0..n {
+        for j in 0..d {
+            grad_p[i * d + j] += x[i] * grad_z[j];
+        }
+    }
+}
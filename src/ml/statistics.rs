@@ -0,0 +1,236 @@
+use num_bigint::{BigInt};
+use num_traits::{One};
+
+use crate::math::fixed_point::{FixedPoint, decode, encode_bound};
+use crate::math::matrix::{BigIntMatrix};
+use crate::ml::ModelError;
+use crate::quadratic_sgp::{Sgp, SgpCipher, SgpPlain, SgpPubKey};
+use crate::simple_ip::{Sip, SipCipher, SipMpk};
+use crate::traits::FunctionalEncryption;
+
+
+/// A dataset with `L` columns, encrypted row by row: once with `Sip` for the first moments and once with `Sgp` for
+/// the second moments. The shape is that of the ciphertexts, so it cannot be changed after encryption.
+pub struct EncryptedDataset<const L: usize> {
+    rows: Vec<EncryptedRow<L>>,
+}
+
+struct EncryptedRow<const L: usize> {
+    sip: SipCipher<L>,
+    sgp: SgpCipher<L>,
+}
+
+impl<const L: usize> EncryptedDataset<L> {
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        L
+    }
+}
+
+/// Descriptive statistics of the columns of a dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Description {
+    pub count: usize,
+    pub mean: Vec<f64>,
+    pub variance: Vec<f64>,
+    /// Row-major `columns x columns` matrix.
+    pub covariance: Vec<f64>,
+}
+
+/// The data owner of a `StatisticsService`: encrypts the rows of a dataset with the master public keys only.
+///
+/// Entries are encoded with the fixed-point scale of the service and must be bounded by `bound` in absolute value.
+///
+/// # Examples
+///
+/// ```ignore
+/// let client = StatisticsClient::<3>::new(service.sip_mpk(), service.sgp_pk(), 100, 10.0);
+/// let data = client.encrypt(&rows).unwrap();
+/// ```
+pub struct StatisticsClient<const L: usize> {
+    pub bound: f64,
+    codec: FixedPoint,
+    sip: SipMpk<L>,
+    sgp: SgpPubKey,
+}
+
+impl<const L: usize> StatisticsClient<L> {
+    pub fn new(sip: &SipMpk<L>, sgp: &SgpPubKey, scale: u64, bound: f64) -> Self {
+        Self {
+            bound,
+            codec: FixedPoint::new(scale),
+            sip: sip.clone(),
+            sgp: sgp.clone(),
+        }
+    }
+
+    /// Encrypt every row of a dataset with `L` columns.
+    pub fn encrypt(&self, rows: &[Vec<f64>]) -> Result<EncryptedDataset<L>, ModelError> {
+        if rows.is_empty() || L == 0 {
+            return Err(ModelError::Empty);
+        }
+        if let Some(r) = rows.iter().find(|r| r.len() != L) {
+            return Err(ModelError::FeatureMismatch { expected: L, actual: r.len() });
+        }
+        let bound = encode_bound(self.bound, &self.codec.scale())?.max(BigInt::one());
+        let rows = rows
+            .iter()
+            .map(|r| {
+                let x: [BigInt; L] = self.codec.encode_array(r)?;
+                Ok(EncryptedRow {
+                    sip: self.sip.try_encrypt_with_bound(&x, &bound).map_err(ModelError::encrypt)?,
+                    sgp: self.sgp.try_encrypt_with_bound(&SgpPlain { x: x.clone(), y: x }, &bound, &bound).map_err(ModelError::encrypt)?,
+                })
+            })
+            .collect::<Result<Vec<_>, ModelError>>()?;
+        Ok(EncryptedDataset { rows })
+    }
+}
+
+/// Mean, variance and covariance of the `L` columns of a dataset encrypted row by row.
+///
+/// The column sums are inner products of the sum of the row ciphertexts with the indicator vector of a column, so
+/// only the sums are decrypted. The second moments `sum_r x_rj x_rk` are the sums of the decryptions of every row
+/// with the key for the matrix that has a single 1 at `(j, k)`, so the service learns the product of every row,
+/// and with `j = k` the absolute value of its entry. The service holds the master keys and only derives these keys. The rows are encrypted by a
+/// `StatisticsClient` with the master public keys, and their number is not bounded.
+///
+/// # Examples
+///
+/// ```ignore
+/// let service = StatisticsService::<3>::new(100, 10.0);
+/// let data = service.client().encrypt(&rows).unwrap();
+/// let description = service.describe(&data).unwrap();
+/// ```
+pub struct StatisticsService<const L: usize> {
+    pub bound: f64,
+    codec: FixedPoint,
+    sip: Sip<L>,
+    sgp: Sgp<L>,
+}
+
+impl<const L: usize> StatisticsService<L> {
+    pub fn new(scale: u64, bound: f64) -> Self {
+        Self {
+            bound,
+            codec: FixedPoint::new(scale),
+            sip: Sip::<L>::new(),
+            sgp: Sgp::<L>::new(),
+        }
+    }
+
+    pub fn sip_mpk(&self) -> &SipMpk<L> {
+        self.sip.mpk()
+    }
+
+    pub fn sgp_pk(&self) -> &SgpPubKey {
+        self.sgp.pk()
+    }
+
+    /// A client with the master public keys, scale and bound of this service.
+    pub fn client(&self) -> StatisticsClient<L> {
+        StatisticsClient {
+            bound: self.bound,
+            codec: self.codec.clone(),
+            sip: self.sip.mpk().clone(),
+            sgp: self.sgp.pk().clone(),
+        }
+    }
+
+    /// Sum of every column.
+    pub fn sum(&self, data: &EncryptedDataset<L>) -> Result<Vec<f64>, ModelError> {
+        let total = sum_ciphers(&data.rows).ok_or(ModelError::Empty)?;
+        (0..L)
+            .map(|j| {
+                let indicator: [BigInt; L] = array_init::array_init(|i| BigInt::from((i == j) as u8));
+                let dk = self.sip.derive_fe_key(&indicator);
+                let sum = self.sip.decrypt(&total, &dk).map_err(ModelError::Decrypt)?;
+                Ok(self.codec.decode(&sum))
+            })
+            .collect()
+    }
+
+    /// Sum of the products of the entries of columns `j` and `k` over all rows.
+    pub fn second_moment(&self, data: &EncryptedDataset<L>, j: usize, k: usize) -> Result<f64, ModelError> {
+        if j >= L || k >= L {
+            return Err(ModelError::FeatureMismatch { expected: L, actual: j.max(k) + 1 });
+        }
+        let mut f = BigIntMatrix::new(L, L);
+        f.set_element(j, k, &BigInt::one());
+        let dk = self.sgp.derive_fe_key(&f);
+        let mut moment = BigInt::from(0);
+        for row in data.rows.iter() {
+            moment += self.sgp.decrypt(&row.sgp, &dk).map_err(ModelError::Decrypt)?;
+        }
+        Ok(decode(&moment, &self.codec.scale().compose(&self.codec.scale())))
+    }
+
+    pub fn mean(&self, data: &EncryptedDataset<L>) -> Result<Vec<f64>, ModelError> {
+        Ok(self.sum(data)?.iter().map(|s| s / data.rows() as f64).collect())
+    }
+
+    /// Sample covariance matrix of the columns, with `rows - 1` degrees of freedom, as a row-major matrix.
+    pub fn covariance(&self, data: &EncryptedDataset<L>) -> Result<Vec<f64>, ModelError> {
+        let mean = self.mean(data)?;
+        self.covariance_with_mean(data, &mean)
+    }
+
+    /// Sample variance of every column.
+    pub fn variance(&self, data: &EncryptedDataset<L>) -> Result<Vec<f64>, ModelError> {
+        let mean = self.mean(data)?;
+        (0..L).map(|j| self.sample_covariance(data, &mean, j, j)).collect()
+    }
+
+    /// Count, mean, variance and covariance of the columns.
+    pub fn describe(&self, data: &EncryptedDataset<L>) -> Result<Description, ModelError> {
+        let mean = self.mean(data)?;
+        let covariance = self.covariance_with_mean(data, &mean)?;
+        Ok(Description {
+            count: data.rows(),
+            variance: (0..L).map(|j| covariance[j * L + j]).collect(),
+            mean,
+            covariance,
+        })
+    }
+
+    fn covariance_with_mean(&self, data: &EncryptedDataset<L>, mean: &[f64]) -> Result<Vec<f64>, ModelError> {
+        let mut covariance = vec![0.0; L * L];
+        for j in 0..L {
+            for k in j..L {
+                let c = self.sample_covariance(data, mean, j, k)?;
+                covariance[j * L + k] = c;
+                covariance[k * L + j] = c;
+            }
+        }
+        Ok(covariance)
+    }
+
+    fn sample_covariance(&self, data: &EncryptedDataset<L>, mean: &[f64], j: usize, k: usize) -> Result<f64, ModelError> {
+        if data.rows() < 2 {
+            return Err(ModelError::Empty);
+        }
+        let n = data.rows() as f64;
+        Ok((self.second_moment(data, j, k)? - n * mean[j] * mean[k]) / (n - 1.0))
+    }
+}
+
+/// The `Sip` ciphertext of the sum of the rows, whose entries are bounded by the sum of the bounds of the rows.
+fn sum_ciphers<const L: usize>(rows: &[EncryptedRow<L>]) -> Option<SipCipher<L>> {
+    let (first, rest) = rows.split_first()?;
+    let mut total = SipCipher {
+        c0: first.sip.c0.clone(),
+        c: first.sip.c.clone(),
+        bound: first.sip.bound.clone(),
+    };
+    for row in rest.iter() {
+        total.c0.add(&row.sip.c0);
+        for i in 0..L {
+            total.c[i].add(&row.sip.c[i]);
+        }
+        total.bound += &row.sip.bound;
+    }
+    Some(total)
+}
@@ -5,6 +5,8 @@ use crate::define::{BigNum, G1, G2, Gt, G1Vector, G2Vector, CURVE_ORDER, MODULUS
 use crate::math::matrix::{BigNumMatrix, BigIntMatrix, BigNumMatrix2x2, convert};
 use crate::utils::{reduce, baby_step_giant_step};
 use crate::utils::rand_utils::{RandUtilsRand, Sample};
+use crate::math::fixed_point::{FixedPoint, FixedPointError, Fixed, decode, encode_bound};
+use crate::bounds::{DecryptError, DEFAULT_PLAIN_BOUND, max_abs, max_abs_matrix, max_abs_column_sum, quadratic_range, solve_in_range};
 use crate::traits::FunctionalEncryption;
 
 
@@ -25,7 +27,11 @@ use crate::traits::FunctionalEncryption;
 #[derive(Debug)]
 pub struct Sgp<const L: usize> {
     msk: SgpSecKey,
-    pk: SgpPubKey
+    pk: SgpPubKey,
+    /// Public bound on the absolute value of the entries of `x` encrypted by `encrypt`
+    x_bound: BigInt,
+    /// Public bound on the absolute value of the entries of `y` encrypted by `encrypt`
+    y_bound: BigInt,
 }
 
 /// Master secret key
@@ -36,10 +42,10 @@ pub struct SgpSecKey {
 }
 
 /// Master public key
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SgpPubKey {
-    g1s: G1Vector,
-    g2t: G2Vector,
+    pub(crate) g1s: G1Vector,
+    pub(crate) g2t: G2Vector,
 }
 
 pub struct SgpPlain<const L: usize> {
@@ -50,16 +56,22 @@ pub struct SgpPlain<const L: usize> {
 /// Ciphertext
 #[derive(Debug)]
 pub struct SgpCipher<const L: usize> {
-    g1_mul_gamma: G1,
-    a: G1Vector,
-    b: G2Vector,
+    pub(crate) g1_mul_gamma: G1,
+    pub(crate) a: G1Vector,
+    pub(crate) b: G2Vector,
+    /// Bound on the absolute value of the entries of `x`
+    pub x_bound: BigInt,
+    /// Bound on the absolute value of the entries of `y`
+    pub y_bound: BigInt,
 }
 
 /// Functional evaluation key
 #[derive(Debug)]
 pub struct SgpDecKey {
-    key: G2,
-    f: BigNumMatrix,
+    pub(crate) key: G2,
+    pub(crate) f: BigNumMatrix,
+    /// Bound on the absolute value of the entries of `f`
+    pub bound: BigInt,
 }
 
 impl<const L: usize> FunctionalEncryption for Sgp<L> {
@@ -82,11 +94,14 @@ impl<const L: usize> FunctionalEncryption for Sgp<L> {
         let (msk, pk) = Sgp::<L>::generate_sec_key();
         Sgp {
             msk,
-            pk
+            pk,
+            x_bound: BigInt::from(DEFAULT_PLAIN_BOUND),
+            y_bound: BigInt::from(DEFAULT_PLAIN_BOUND)
         }
     }
 
-    /// Encrypt two vectors of numbers, resulting in a single ciphertext.
+    /// Encrypt two vectors of numbers, resulting in a single ciphertext. The entries must be bounded by the plaintext
+    /// bounds of the scheme, `DEFAULT_PLAIN_BOUND` unless they were changed with `set_bound`.
     ///
     /// # Examples
     ///
@@ -99,61 +114,13 @@ impl<const L: usize> FunctionalEncryption for Sgp<L> {
     /// }
     /// let cipher = sgp.encrypt(&x, &y);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` does not have `L` entries, or exceeds the bound declared with `set_bound`,
+    /// `DEFAULT_PLAIN_BOUND` if none was declared. Use `try_encrypt_with_bound` to get an error instead.
     fn encrypt(&self, plain: &Self::PlainData) -> Self::CipherText {
-        let (x, y) = (&plain.x, &plain.y);
-        if x.len() != L ||  y.len() != L {
-            panic!("Malformed input: x.len ({}), y.len ({}), expected len ({})", x.len(), y.len(), L);
-        }
-
-        let mut rng = RandUtilsRand::new();
-
-        let w = BigNumMatrix2x2::new_random(&(CURVE_ORDER));
-        let mut w_inv = w.invmod(&(CURVE_ORDER));
-        w_inv.transpose();
-
-        let gamma = rng.sample(&(CURVE_ORDER));
-        let mut g1_mul_gamma = G1::generator();
-        g1_mul_gamma = g1_mul_gamma.mul(&gamma);
-
-        let mut a: G1Vector = vec![G1::generator(); L * 2];
-        let mut b: G2Vector = vec![G2::generator(); L * 2];
-
-        for i in 0..L {
-
-            let xi = reduce(&x[i], &MODULUS);
-            let xi = BigNum::fromstring(xi.to_str_radix(16));
-
-            let yi = reduce(&y[i], &MODULUS);
-            let yi = BigNum::fromstring(yi.to_str_radix(16));
-
-            let w00_mul_xi = BigNum::modmul(w_inv.get_element(0, 0), &xi, &CURVE_ORDER);
-            let w01_mul_gamma = BigNum::modmul(w_inv.get_element(0, 1), &gamma, &CURVE_ORDER);
-            let w10_mul_xi = BigNum::modmul(w_inv.get_element(1, 0), &xi, &CURVE_ORDER);
-            let w11_mul_gamma = BigNum::modmul(w_inv.get_element(1, 1), &gamma, &CURVE_ORDER);
-
-            a[i*2] = a[i*2].mul(&w00_mul_xi);
-            a[i*2].add(&(self.pk.g1s[i].mul(&w01_mul_gamma)));
-
-            a[i*2+1] = a[i*2+1].mul(&w10_mul_xi);
-            a[i*2+1].add(&(self.pk.g1s[i].mul(&w11_mul_gamma)));
-
-
-            let w00_mul_yi = BigNum::modmul(w.get_element(0, 0), &yi, &CURVE_ORDER);
-            let w01_neg = BigNum::modneg(w.get_element(0, 1), &CURVE_ORDER);
-            let w10_mul_yi = BigNum::modmul(w.get_element(1, 0), &yi, &CURVE_ORDER);
-            let w11_neg = BigNum::modneg(w.get_element(1, 1), &CURVE_ORDER);
-
-            b[i*2] = b[i*2].mul(&w00_mul_yi);
-            b[i*2].add(&(self.pk.g2t[i].mul(&w01_neg)));
-
-            b[i*2+1] = b[i*2+1].mul(&w10_mul_yi);
-            b[i*2+1].add(&(self.pk.g2t[i].mul(&w11_neg)));
-        }
-        SgpCipher {
-            g1_mul_gamma,
-            a,
-            b,
-        }
+        self.encrypt_with_bound(plain, &self.x_bound, &self.y_bound)
     }
 
     /// Derive functional evaluation key for a matrix of numbers.
@@ -174,40 +141,32 @@ impl<const L: usize> FunctionalEncryption for Sgp<L> {
         let exp = exp.get_element(0, 0);
         SgpDecKey {
             key: (G2::generator()).mul(exp),
-            f: new_f 
+            f: new_f,
+            bound: max_abs_matrix(f)
         }
     }
 
 
-    /// Decrypt a ciphertext with the functional evaluation key. The parameter `bound` is the absolute value bound for
-    /// numbers used in the inner product.
+    /// Decrypt a ciphertext with the functional evaluation key. The result is searched in [-range, range] with
+    /// `range = ct.x_bound * ct.y_bound * dk.bound * n_rows * n_cols`.
     ///
     /// # Examples
     ///
     /// ```ignore
     /// // Following the example of `derive_fe_key`
-    /// let result = sgp.decrypt(&cipher, &dk, &BigInt::from(100)); 
+    /// let result = sgp.decrypt(&cipher, &dk); 
     /// ```
-    fn decrypt(&self, ct: &Self::CipherText, dk: &Self::EvaluationKey, bound: &BigInt) -> Option<BigInt> {
-        if ct.a.len() != dk.f.n_rows * 2 || ct.b.len() != dk.f.n_cols * 2 {
-            panic!("Malformed input: a.len ({}), b.len ({}), f dimension ({} x {}).", ct.a.len() / 2, ct.b.len() / 2, dk.f.n_rows, dk.f.n_cols);
+    fn decrypt(&self, ct: &Self::CipherText, dk: &Self::EvaluationKey) -> Result<BigInt, DecryptError> {
+        if ct.a.len() != dk.f.n_rows * 2 {
+            return Err(DecryptError::DimensionMismatch { expected: ct.a.len() / 2, actual: dk.f.n_rows });
+        }
+        if ct.b.len() != dk.f.n_cols * 2 {
+            return Err(DecryptError::DimensionMismatch { expected: ct.b.len() / 2, actual: dk.f.n_cols });
         }
 
         let mut out: Gt = pair::ate(&dk.key, &ct.g1_mul_gamma);
         out = pair::fexp(&out);
-        let (mut proj0, mut proj1): (Gt, Gt);
-        for i in 0..dk.f.n_rows {
-            for j in 0..dk.f.n_cols {
-                proj0 = pair::ate(&ct.b[j*2], &ct.a[i*2]);
-                proj0 = pair::fexp(&proj0);
-                proj1 = pair::ate(&ct.b[j*2 + 1], &ct.a[i*2 + 1]);
-                proj1 = pair::fexp(&proj1);
-                
-                proj0.mul(&proj1);
-                proj0 = proj0.pow(dk.f.get_element(i, j));
-                out.mul(&proj0);
-            }
-        }
+        out.mul(&projections(ct, &dk.f));
 
         let g1 = G1::generator();
         let g2 = G2::generator();
@@ -215,17 +174,30 @@ impl<const L: usize> FunctionalEncryption for Sgp<L> {
         let pair = pair::fexp(&pair);
 
         //dlog
-        let mut result_bound = BigNum::fromstring(bound.to_str_radix(16));
-        result_bound = result_bound.powmod(&BigNum::new_int(3), &CURVE_ORDER);
-        result_bound = BigNum::modmul(&result_bound, &BigNum::new_int((dk.f.n_rows * dk.f.n_cols) as isize), &CURVE_ORDER);
-
-        baby_step_giant_step(&out, &pair, &result_bound)
+        let range = quadratic_range(&ct.x_bound, &ct.y_bound, &dk.bound, dk.f.n_rows, dk.f.n_cols);
+        solve_in_range(&range, |bound| baby_step_giant_step(&out, &pair, bound))
     }
 }
 
 
 impl<const L: usize> Sgp<L> {
 
+    /// The master public key.
+    pub fn pk(&self) -> &SgpPubKey {
+        &self.pk
+    }
+
+    /// The public plaintext bounds of `x` and `y` used by `encrypt`.
+    pub fn bound(&self) -> (&BigInt, &BigInt) {
+        (&self.x_bound, &self.y_bound)
+    }
+
+    /// Declare the public bounds on the absolute value of the entries of `x` and `y` encrypted by `encrypt`.
+    pub fn set_bound(&mut self, x_bound: &BigInt, y_bound: &BigInt) {
+        self.x_bound = x_bound.clone();
+        self.y_bound = y_bound.clone();
+    }
+
     /// Generate a pair of master secret key and master public key.
     pub fn generate_sec_key() -> (SgpSecKey, SgpPubKey) {
         let mut rng = RandUtilsRand::new();
@@ -244,12 +216,128 @@ impl<const L: usize> Sgp<L> {
         (msk, pk)
     }
 
-    /// Project a ciphertext into another ciphertext with a projection matrix.
+    /// Encrypt two vectors of numbers whose entries are bounded by `x_bound` and `y_bound` in absolute value. The
+    /// bounds are carried by the ciphertext and determine the range searched during decryption.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let cipher = sgp.encrypt_with_bound(&plain, &BigInt::from(100), &BigInt::from(10));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` or `y` does not have `L` entries, or exceeds its bound.
+    pub fn encrypt_with_bound(&self, plain: &SgpPlain<L>, x_bound: &BigInt, y_bound: &BigInt) -> SgpCipher<L> {
+        let (x, y) = (&plain.x, &plain.y);
+        match self.try_encrypt_with_bound(plain, x_bound, y_bound) {
+            Ok(cipher) => cipher,
+            Err(DecryptError::DimensionMismatch { .. }) => {
+                panic!("Malformed input: x.len ({}), y.len ({}), expected len ({})", x.len(), y.len(), L)
+            }
+            Err(_) => panic!("Malformed input: x or y exceeds its bound ({}, {})", x_bound, y_bound),
+        }
+    }
+
+    /// Encrypt like `encrypt_with_bound`, and return `DecryptError::DimensionMismatch` if `x` or `y` does not have
+    /// `L` entries, or `DecryptError::OutOfRange` with the exceeded bound.
+    pub fn try_encrypt_with_bound(&self, plain: &SgpPlain<L>, x_bound: &BigInt, y_bound: &BigInt) -> Result<SgpCipher<L>, DecryptError> {
+        self.pk.try_encrypt_with_bound(plain, x_bound, y_bound)
+    }
+
+    /// Project a ciphertext into another ciphertext with a projection matrix. The bounds of the projected vectors
+    /// are the bounds of `x` and `y` times the largest absolute column sum of `p`.
     /// 
     /// Read the paper for details.
     pub fn project(&self, cipher: &SgpCipher<L>, p: &BigIntMatrix) -> SgpCipher<L> {
+        cipher.project(p)
+    }
+
+    /// Derive functional evaluation key for a matrix of numbers, with a projection matrix.
+    ///
+    /// Read the paper for details.
+    pub fn derive_fe_key_projected(&self, f: &BigIntMatrix, p: &BigIntMatrix) -> SgpDecKey {
+        if L != p.n_rows || f.n_rows != f.n_cols || f.n_rows != p.n_cols {
+            panic!("Malformed input: f.dim ({} x {}), P.dim ({} x {})", f.n_rows, f.n_cols, p.n_rows, p.n_cols);
+        }
+        let new_f = convert(f, &MODULUS);
+        let new_p = convert(p, &MODULUS);
+        let new_s = BigNumMatrix::new_bigints(&self.msk.s, 1, self.msk.s.len(), &CURVE_ORDER);
+        let new_t = BigNumMatrix::new_bigints(&self.msk.t, 1, self.msk.t.len(), &CURVE_ORDER);
+        let proj_s = new_s.matmul(&new_p);
+        let proj_t = new_t.matmul(&new_p).transpose();
+
+        let exp = proj_s.matmul(&new_f);
+        let exp = exp.matmul(&proj_t);
+        let exp = exp.get_element(0, 0);
+        SgpDecKey {
+            key: (G2::generator()).mul(exp),
+            f: new_f,
+            bound: max_abs_matrix(f)
+        }
+    }
+
+    /// Encrypt two vectors of floating point numbers, encoded with `x_codec` and `y_codec` respectively. `bound` is
+    /// the public bound on the absolute value of the (real) entries of both vectors.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let codec = FixedPoint::new(100);
+    /// let cipher = sgp.encrypt_fixed(&[0.5, -0.25], &codec, &[1.5, 0.75], &codec, 2.0).unwrap();
+    /// ```
+    pub fn encrypt_fixed<T: Copy + Into<f64>>(&self, x: &[T], x_codec: &FixedPoint, y: &[T], y_codec: &FixedPoint, bound: f64) -> Result<Fixed<SgpCipher<L>>, FixedPointError> {
+        let plain = SgpPlain {
+            x: x_codec.encode_array(x)?,
+            y: y_codec.encode_array(y)?
+        };
+        let x_bound = encode_bound(bound, &x_codec.scale())?;
+        let y_bound = encode_bound(bound, &y_codec.scale())?;
+        if max_abs(&plain.x) > x_bound {
+            return Err(FixedPointError::OutOfBound { bound: x_bound });
+        }
+        if max_abs(&plain.y) > y_bound {
+            return Err(FixedPointError::OutOfBound { bound: y_bound });
+        }
+        Ok(Fixed {
+            inner: self.encrypt_with_bound(&plain, &x_bound, &y_bound),
+            scale: x_codec.scale().compose(&y_codec.scale())
+        })
+    }
+
+    /// Derive functional evaluation key for a matrix of floating point numbers given in row-major order, encoded
+    /// with `codec`.
+    pub fn derive_fe_key_fixed<T: Copy + Into<f64>>(&self, f: &[T], n_rows: usize, n_cols: usize, codec: &FixedPoint) -> Result<Fixed<SgpDecKey>, FixedPointError> {
+        let int_f = codec.encode_matrix(f, n_rows, n_cols)?;
+        Ok(Fixed {
+            inner: self.derive_fe_key(&int_f),
+            scale: codec.scale()
+        })
+    }
+
+    /// Decrypt a ciphertext of floating point numbers. The result has scale `sx * sf * sy`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Following the example of `encrypt_fixed`
+    /// let dk = sgp.derive_fe_key_fixed(&[1.0, 0.0, 0.0, 1.0], 2, 2, &codec).unwrap();
+    /// let result = sgp.decrypt_fixed(&cipher, &dk).unwrap();
+    /// ```
+    pub fn decrypt_fixed(&self, ct: &Fixed<SgpCipher<L>>, dk: &Fixed<SgpDecKey>) -> Result<f64, FixedPointError> {
+        let scale = ct.scale.compose(&dk.scale);
+        let result = self.decrypt(&ct.inner, &dk.inner)?;
+        Ok(decode(&result, &scale))
+    }
+
+}
+
+impl<const L: usize> SgpCipher<L> {
+    /// Project the ciphertext with a projection matrix, see `Sgp::project`. Projection only needs the ciphertext, so
+    /// anyone can project a ciphertext, e.g. to verify a decryption.
+    pub fn project(&self, p: &BigIntMatrix) -> SgpCipher<L> {
         if L != p.n_rows {
-            panic!("Malformed input: self.n ({}), cipher.n ({}), P.dim ({} x {})", L, L, p.n_rows, p.n_cols);
+            panic!("Malformed input: self.n ({}), P.dim ({} x {})", L, p.n_rows, p.n_cols);
         }
         let new_p = convert(p, &MODULUS);
         let d = p.n_cols;
@@ -261,47 +349,146 @@ impl<const L: usize> Sgp<L> {
             new_b[i * 2].inf();
             new_b[i * 2 + 1].inf();
             for j in 0..L {
-                let tmp1 = cipher.a[j * 2].mul(new_p.get_element(j, i));
-                let tmp2 = cipher.a[j * 2 + 1].mul(new_p.get_element(j, i));
+                let tmp1 = self.a[j * 2].mul(new_p.get_element(j, i));
+                let tmp2 = self.a[j * 2 + 1].mul(new_p.get_element(j, i));
                 new_a[i * 2].add(&tmp1);
                 new_a[i * 2 + 1].add(&tmp2);
 
-                let tmp1 = cipher.b[j * 2].mul(new_p.get_element(j, i));
-                let tmp2 = cipher.b[j * 2 + 1].mul(new_p.get_element(j, i));
+                let tmp1 = self.b[j * 2].mul(new_p.get_element(j, i));
+                let tmp2 = self.b[j * 2 + 1].mul(new_p.get_element(j, i));
                 new_b[i * 2].add(&tmp1);
                 new_b[i * 2 + 1].add(&tmp2);
             }
         }
 
+        let p_norm = max_abs_column_sum(p);
         SgpCipher {
-            g1_mul_gamma: cipher.g1_mul_gamma.clone(),
+            g1_mul_gamma: self.g1_mul_gamma.clone(),
             a: new_a,
             b: new_b,
+            x_bound: &self.x_bound * &p_norm,
+            y_bound: &self.y_bound * &p_norm,
         }
     }
+}
 
-    /// Derive functional evaluation key for a matrix of numbers, with a projection matrix.
-    ///
-    /// Read the paper for details.
-    pub fn derive_fe_key_projected(&self, f: &BigIntMatrix, p: &BigIntMatrix) -> SgpDecKey {
-        if L != p.n_rows || f.n_rows != f.n_cols || f.n_rows != p.n_cols {
-            panic!("Malformed input: f.dim ({} x {}), P.dim ({} x {})", f.n_rows, f.n_cols, p.n_rows, p.n_cols);
+impl SgpPubKey {
+    /// Encrypt with the master public key only, like `Sgp::try_encrypt_with_bound`. Also returns
+    /// `DecryptError::DimensionMismatch` if the key is not of length `L`.
+    pub fn try_encrypt_with_bound<const L: usize>(&self, plain: &SgpPlain<L>, x_bound: &BigInt, y_bound: &BigInt) -> Result<SgpCipher<L>, DecryptError> {
+        let (x, y) = (&plain.x, &plain.y);
+        if self.g1s.len() != L {
+            return Err(DecryptError::DimensionMismatch { expected: self.g1s.len(), actual: L });
+        }
+        for v in [x, y].iter() {
+            if v.len() != L {
+                return Err(DecryptError::DimensionMismatch { expected: L, actual: v.len() });
+            }
+        }
+        if max_abs(x) > *x_bound {
+            return Err(DecryptError::OutOfRange { range: x_bound.clone() });
+        }
+        if max_abs(y) > *y_bound {
+            return Err(DecryptError::OutOfRange { range: y_bound.clone() });
         }
-        let new_f = convert(f, &MODULUS);
-        let new_p = convert(p, &MODULUS);
-        let new_s = BigNumMatrix::new_bigints(&self.msk.s, 1, self.msk.s.len(), &CURVE_ORDER);
-        let new_t = BigNumMatrix::new_bigints(&self.msk.t, 1, self.msk.t.len(), &CURVE_ORDER);
-        let proj_s = new_s.matmul(&new_p);
-        let proj_t = new_t.matmul(&new_p).transpose();
 
-        let exp = proj_s.matmul(&new_f);
-        let exp = exp.matmul(&proj_t);
-        let exp = exp.get_element(0, 0);
-        SgpDecKey {
-            key: (G2::generator()).mul(exp),
-            f: new_f 
+        let mut rng = RandUtilsRand::new();
+
+        let w = BigNumMatrix2x2::new_random(&(CURVE_ORDER));
+        let mut w_inv = w.invmod(&(CURVE_ORDER));
+        w_inv.transpose();
+
+        let gamma = rng.sample(&(CURVE_ORDER));
+        let mut g1_mul_gamma = G1::generator();
+        g1_mul_gamma = g1_mul_gamma.mul(&gamma);
+
+        let mut a: G1Vector = vec![G1::generator(); L * 2];
+        let mut b: G2Vector = vec![G2::generator(); L * 2];
+
+        for i in 0..L {
+
+            let xi = reduce(&x[i], &MODULUS);
+            let xi = BigNum::fromstring(xi.to_str_radix(16));
+
+            let yi = reduce(&y[i], &MODULUS);
+            let yi = BigNum::fromstring(yi.to_str_radix(16));
+
+            let w00_mul_xi = BigNum::modmul(w_inv.get_element(0, 0), &xi, &CURVE_ORDER);
+            let w01_mul_gamma = BigNum::modmul(w_inv.get_element(0, 1), &gamma, &CURVE_ORDER);
+            let w10_mul_xi = BigNum::modmul(w_inv.get_element(1, 0), &xi, &CURVE_ORDER);
+            let w11_mul_gamma = BigNum::modmul(w_inv.get_element(1, 1), &gamma, &CURVE_ORDER);
+
+            a[i*2] = a[i*2].mul(&w00_mul_xi);
+            a[i*2].add(&(self.g1s[i].mul(&w01_mul_gamma)));
+
+            a[i*2+1] = a[i*2+1].mul(&w10_mul_xi);
+            a[i*2+1].add(&(self.g1s[i].mul(&w11_mul_gamma)));
+
+
+            let w00_mul_yi = BigNum::modmul(w.get_element(0, 0), &yi, &CURVE_ORDER);
+            let w01_neg = BigNum::modneg(w.get_element(0, 1), &CURVE_ORDER);
+            let w10_mul_yi = BigNum::modmul(w.get_element(1, 0), &yi, &CURVE_ORDER);
+            let w11_neg = BigNum::modneg(w.get_element(1, 1), &CURVE_ORDER);
+
+            b[i*2] = b[i*2].mul(&w00_mul_yi);
+            b[i*2].add(&(self.g2t[i].mul(&w01_neg)));
+
+            b[i*2+1] = b[i*2+1].mul(&w10_mul_yi);
+            b[i*2+1].add(&(self.g2t[i].mul(&w11_neg)));
         }
+        Ok(SgpCipher {
+            g1_mul_gamma,
+            a,
+            b,
+            x_bound: x_bound.clone(),
+            y_bound: y_bound.clone(),
+        })
     }
 
+    /// The public key matching `Sgp::project` and `Sgp::derive_fe_key_projected` with the projection matrix `p`,
+    /// i.e. the public key for the master secret keys `s P` and `t P`.
+    pub fn project(&self, p: &BigIntMatrix) -> SgpPubKey {
+        if self.g1s.len() != p.n_rows {
+            panic!("Malformed input: pk.len ({}), P.dim ({} x {})", self.g1s.len(), p.n_rows, p.n_cols);
+        }
+        let new_p = convert(p, &MODULUS);
+        let mut pk = SgpPubKey {
+            g1s: vec![G1::new(); p.n_cols],
+            g2t: vec![G2::new(); p.n_cols],
+        };
+        for i in 0..p.n_cols {
+            pk.g1s[i].inf();
+            pk.g2t[i].inf();
+            for j in 0..p.n_rows {
+                pk.g1s[i].add(&self.g1s[j].mul(new_p.get_element(j, i)));
+                pk.g2t[i].add(&self.g2t[j].mul(new_p.get_element(j, i)));
+            }
+        }
+        pk
+    }
 }
 
+/// The product of the pairings of the ciphertext components, weighted by the entries of `f`. Decryption multiplies
+/// it with `e(key, g1^gamma)` to get `e(g1, g2)^(x^T f y)`.
+pub(crate) fn projections<const L: usize>(ct: &SgpCipher<L>, f: &BigNumMatrix) -> Gt {
+    let mut out = Gt::new();
+    out.one();
+    let (mut proj0, mut proj1): (Gt, Gt);
+    for i in 0..f.n_rows {
+        for j in 0..f.n_cols {
+            // zero entries do not contribute, skip their pairings
+            if f.get_element(i, j).iszilch() {
+                continue;
+            }
+            proj0 = pair::ate(&ct.b[j*2], &ct.a[i*2]);
+            proj0 = pair::fexp(&proj0);
+            proj1 = pair::ate(&ct.b[j*2 + 1], &ct.a[i*2 + 1]);
+            proj1 = pair::fexp(&proj1);
+
+            proj0.mul(&proj1);
+            proj0 = proj0.pow(f.get_element(i, j));
+            out.mul(&proj0);
+        }
+    }
+    out
+}
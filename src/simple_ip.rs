@@ -3,6 +3,8 @@ use num_bigint::{BigInt};
 use crate::define::{BigNum, G1, CURVE_ORDER, MODULUS};
 use crate::utils::{baby_step_giant_step_g1, reduce};
 use crate::utils::rand_utils::{RandUtilsRand, Sample};
+use crate::math::fixed_point::{FixedPoint, FixedPointError, Fixed, decode, encode_bound};
+use crate::bounds::{DecryptError, DEFAULT_PLAIN_BOUND, max_abs, inner_product_range, solve_in_range};
 use crate::traits::FunctionalEncryption;
 
 
@@ -24,33 +26,39 @@ pub struct Sip<const L: usize> {
     /// Master secret key
     msk: SipMsk<L>,
     /// Master public key
-    mpk: SipMpk<L>
+    mpk: SipMpk<L>,
+    /// Public bound on the absolute value of the plaintext entries encrypted by `encrypt`
+    bound: BigInt,
 }
 
 /// Master secret key: a secret of length L.
 #[derive(Debug)]
 pub struct SipMsk<const L: usize> {
-    s: [BigNum; L]
+    pub(crate) s: [BigNum; L]
 }
 
 /// Master public key
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SipMpk<const L: usize> {
-    v: [G1; L],
+    pub(crate) v: [G1; L],
 }
 
 /// Functional encryption ciphertext
 #[derive(Debug)]
 pub struct SipCipher<const L: usize> {
-    c0: G1,
-    c: [G1; L]
+    pub(crate) c0: G1,
+    pub(crate) c: [G1; L],
+    /// Bound on the absolute value of the plaintext entries
+    pub bound: BigInt,
 }
 
 /// Functional evaluation key
 #[derive(Debug)]
 pub struct SipDk<const L: usize> {
-    y: [BigNum; L],
-    dk: BigNum
+    pub(crate) y: [BigNum; L],
+    pub(crate) dk: BigNum,
+    /// Bound on the absolute value of the function entries
+    pub bound: BigInt,
 }
 
 
@@ -74,11 +82,13 @@ impl<const L: usize> FunctionalEncryption for Sip<L> {
         let (msk, mpk) = Sip::generate_sec_key();
         Sip {
             msk,
-            mpk
+            mpk,
+            bound: BigInt::from(DEFAULT_PLAIN_BOUND)
         }
     }
 
-    /// Encrypt a vector of numbers.
+    /// Encrypt a vector of numbers bounded by the plaintext bound of the scheme, `DEFAULT_PLAIN_BOUND` unless it was
+    /// changed with `set_bound`.
     ///
     /// # Examples
     ///
@@ -88,27 +98,18 @@ impl<const L: usize> FunctionalEncryption for Sip<L> {
     /// let bound: i32 = 100;
     /// let low = (-bound).to_bigint().unwrap();
     /// let high = bound.to_bigint().unwrap();
-    /// let sip = Sip::<L>::new();
+    /// let mut sip = Sip::<L>::new();
+    /// sip.set_bound(&high);
     /// let x: [BigInt; L] = rng.sample_range_array::<L>(&low, &high); 
     /// let cipher = sip.encrypt(&x);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entry of `x` exceeds the bound declared with `set_bound`, `DEFAULT_PLAIN_BOUND` if none was
+    /// declared. Use `try_encrypt_with_bound` to get an error instead.
     fn encrypt(&self, x: &Self::PlainData) -> Self::CipherText {
-        let mut rng = RandUtilsRand::new();
-
-        let r = rng.sample(&(CURVE_ORDER));
-        let c0 = G1::generator().mul(&r);
-        let mut c: [G1; L] = array_init::array_init(|_| G1::generator()); 
-        for i in 0..L {
-            let xi = reduce(&x[i], &MODULUS);
-            let xi = BigNum::fromstring(xi.to_str_radix(16)); 
-
-            c[i] = c[i].mul(&xi);
-            c[i].add(&(self.mpk.v[i].mul(&r)));
-        }
-        SipCipher {
-            c0,
-            c
-        }
+        self.encrypt_with_bound(x, &self.bound)
     }
 
     /// Derive functional evaluation key for a vector of numbers.
@@ -131,20 +132,22 @@ impl<const L: usize> FunctionalEncryption for Sip<L> {
         }
         SipDk {
             y: new_y,
-            dk
+            dk,
+            bound: max_abs(y)
         }
     }
 
-    /// Decrypt a ciphertext with the functional evaluation key.
+    /// Decrypt a ciphertext with the functional evaluation key. The result is searched in [-range, range] with
+    /// `range = ct.bound * dk.bound * L`.
     ///
     /// # Examples
     ///
     /// ```ignore
     /// // Following the example of `derive_fe_key`
-    /// let result = sip.decrypt(&cipher, &y, &dk, &BigInt::from(bound)); 
+    /// let result = sip.decrypt(&cipher, &dk); 
     /// ```
     //fn decrypt(&self, ct: &Self::CipherText, y: &Self::FEKeyData, dk: &Self::EvaluationKey, bound: &BigInt) -> Option<BigInt> {
-    fn decrypt(&self, ct: &Self::CipherText, dk: &Self::EvaluationKey, bound: &BigInt) -> Option<BigInt> {
+    fn decrypt(&self, ct: &Self::CipherText, dk: &Self::EvaluationKey) -> Result<BigInt, DecryptError> {
         let mut res = G1::new();
         for i in 0..L {
             //let yi = reduce(&dk.y[i], &MODULUS);
@@ -156,18 +159,88 @@ impl<const L: usize> FunctionalEncryption for Sip<L> {
         res.sub(&ct.c0.mul(&dk.dk));
 
         //dlog
-        let mut result_bound = BigNum::fromstring(bound.to_str_radix(16));
-        result_bound = result_bound.powmod(&BigNum::new_int(2), &CURVE_ORDER);
-        result_bound = BigNum::modmul(&result_bound, &BigNum::new_int(L as isize), &CURVE_ORDER);
-
-        baby_step_giant_step_g1(&res, &G1::generator(), &result_bound)
+        let range = inner_product_range(&ct.bound, &dk.bound, L);
+        solve_in_range(&range, |bound| baby_step_giant_step_g1(&res, &G1::generator(), bound))
     }
 
 }
 
 
+impl<const L: usize> SipMpk<L> {
+    /// Check that a functional evaluation key was derived from the master secret key of this master public key,
+    /// i.e. that `g^dk = prod_i v_i^(y_i)`.
+    ///
+    /// The check is on the BLS12-381 keys themselves, so no proof about the key is needed. Together with
+    /// `zk::sigma::verify_sip_mpk`, which shows that the key authority knows the master secret key behind the
+    /// published master public key, it certifies that `dk` is the key for the function `y` under that master
+    /// secret key.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// verify_sip_mpk(sip.mpk(), &prove_sip_mpk(&sip)).unwrap();
+    /// let dk = sip.derive_fe_key(&y);
+    /// assert!(sip.mpk().verify_dk(&dk));
+    /// ```
+    pub fn verify_dk(&self, dk: &SipDk<L>) -> bool {
+        let mut res = G1::new();
+        for i in 0..L {
+            res.add(&self.v[i].mul(&dk.y[i]));
+        }
+        res.equals(&G1::generator().mul(&dk.dk))
+    }
+
+    /// Encrypt with the master public key only, like `Sip::try_encrypt_with_bound`.
+    pub fn try_encrypt_with_bound(&self, x: &[BigInt; L], bound: &BigInt) -> Result<SipCipher<L>, DecryptError> {
+        if max_abs(x) > *bound {
+            return Err(DecryptError::OutOfRange { range: bound.clone() });
+        }
+        let mut rng = RandUtilsRand::new();
+
+        let r = rng.sample(&(CURVE_ORDER));
+        Ok(self.encrypt_with_randomness(x, bound, &r))
+    }
+
+    /// Encrypt `x` with the randomness `r`, for proofs about the ciphertext.
+    pub(crate) fn encrypt_with_randomness(&self, x: &[BigInt; L], bound: &BigInt, r: &BigNum) -> SipCipher<L> {
+        let c0 = G1::generator().mul(r);
+        let mut c: [G1; L] = array_init::array_init(|_| G1::generator()); 
+        for i in 0..L {
+            let xi = reduce(&x[i], &MODULUS);
+            let xi = BigNum::fromstring(xi.to_str_radix(16)); 
+
+            c[i] = c[i].mul(&xi);
+            c[i].add(&(self.v[i].mul(r)));
+        }
+        SipCipher {
+            c0,
+            c,
+            bound: bound.clone()
+        }
+    }
+}
+
 impl<const L: usize> Sip<L> {
 
+    /// The master public key.
+    pub fn mpk(&self) -> &SipMpk<L> {
+        &self.mpk
+    }
+
+    pub(crate) fn msk(&self) -> &SipMsk<L> {
+        &self.msk
+    }
+
+    /// The public plaintext bound used by `encrypt`.
+    pub fn bound(&self) -> &BigInt {
+        &self.bound
+    }
+
+    /// Declare the public bound on the absolute value of the plaintext entries encrypted by `encrypt`.
+    pub fn set_bound(&mut self, bound: &BigInt) {
+        self.bound = bound.clone();
+    }
+
     /// Generate a pair of master secret key and master public key.
     pub fn generate_sec_key() -> (SipMsk<L>, SipMpk<L>) {
         let mut rng = RandUtilsRand::new();
@@ -183,6 +256,77 @@ impl<const L: usize> Sip<L> {
         (msk, mpk)
     }
 
+    /// Encrypt a vector of numbers whose entries are bounded by `bound` in absolute value. The bound is carried by
+    /// the ciphertext and determines the range searched during decryption.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let cipher = sip.encrypt_with_bound(&x, &BigInt::from(100));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entry of `x` exceeds `bound`.
+    pub fn encrypt_with_bound(&self, x: &[BigInt; L], bound: &BigInt) -> SipCipher<L> {
+        match self.try_encrypt_with_bound(x, bound) {
+            Ok(cipher) => cipher,
+            Err(_) => panic!("Malformed input: x exceeds the bound ({})", bound),
+        }
+    }
+
+    /// Encrypt like `encrypt_with_bound`, and return `DecryptError::OutOfRange` if an entry of `x` exceeds `bound`.
+    pub fn try_encrypt_with_bound(&self, x: &[BigInt; L], bound: &BigInt) -> Result<SipCipher<L>, DecryptError> {
+        self.mpk.try_encrypt_with_bound(x, bound)
+    }
+
+    /// Encrypt a vector of floating point numbers, encoded with `codec`. `bound` is the public bound on the absolute
+    /// value of the (real) entries.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let codec = FixedPoint::new(100);
+    /// let x: [f64; L] = ...;
+    /// let cipher = sip.encrypt_fixed(&x, 5.0, &codec).unwrap();
+    /// ```
+    pub fn encrypt_fixed<T: Copy + Into<f64>>(&self, x: &[T], bound: f64, codec: &FixedPoint) -> Result<Fixed<SipCipher<L>>, FixedPointError> {
+        let int_x: [BigInt; L] = codec.encode_array(x)?;
+        let int_bound = encode_bound(bound, &codec.scale())?;
+        if max_abs(&int_x) > int_bound {
+            return Err(FixedPointError::OutOfBound { bound: int_bound });
+        }
+        Ok(Fixed {
+            inner: self.encrypt_with_bound(&int_x, &int_bound),
+            scale: codec.scale()
+        })
+    }
+
+    /// Derive functional evaluation key for a vector of floating point numbers, encoded with `codec`.
+    pub fn derive_fe_key_fixed<T: Copy + Into<f64>>(&self, y: &[T], codec: &FixedPoint) -> Result<Fixed<SipDk<L>>, FixedPointError> {
+        let int_y: [BigInt; L] = codec.encode_array(y)?;
+        Ok(Fixed {
+            inner: self.derive_fe_key(&int_y),
+            scale: codec.scale()
+        })
+    }
+
+    /// Decrypt a ciphertext of floating point numbers. The result has the scale of the ciphertext times the scale of
+    /// the key.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Following the examples of `encrypt_fixed`
+    /// let dk = sip.derive_fe_key_fixed(&y, &codec).unwrap();
+    /// let result = sip.decrypt_fixed(&cipher, &dk).unwrap();
+    /// ```
+    pub fn decrypt_fixed(&self, ct: &Fixed<SipCipher<L>>, dk: &Fixed<SipDk<L>>) -> Result<f64, FixedPointError> {
+        let scale = ct.scale.compose(&dk.scale);
+        let result = self.decrypt(&ct.inner, &dk.inner)?;
+        Ok(decode(&result, &scale))
+    }
+
 }
 
 
@@ -1,18 +1,26 @@
 use num_bigint::BigInt;
 
+use crate::bounds::DecryptError;
+
 pub trait FunctionalEncryption {
     type CipherText;
     type PlainData;
     type FEKeyData;
     type EvaluationKey;
     fn new() -> Self;
+    /// Encrypt under the plaintext bound declared for the scheme.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plaintext exceeds the declared bound.
     fn encrypt(&self, plain: &Self::PlainData) -> Self::CipherText;
     fn derive_fe_key(&self, f: &Self::FEKeyData) -> Self::EvaluationKey;
+    /// Decrypt the function value. The range searched for the result follows from the bounds carried by the
+    /// ciphertext and the key.
     fn decrypt(
         &self,
         ciphers: &Self::CipherText,
         //f: &Self::FEKeyData,
         dk: &Self::EvaluationKey,
-        bound: &BigInt,
-    ) -> Option<BigInt>;
+    ) -> Result<BigInt, DecryptError>;
 }
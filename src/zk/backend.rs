@@ -0,0 +1,100 @@
+use fawkes_crypto::{
+    backend::bellman_groth16::{
+        engines::Engine,
+        prover::{self, Proof},
+        setup::setup,
+        verifier::{self, VK},
+        Parameters
+    },
+    circuit::cs::{BuildCS, WitnessCS},
+    core::signal::Signal,
+    ff_uint::{Num, PrimeField},
+};
+use std::marker::PhantomData;
+
+use super::{ParamsError, SnarkInfo};
+
+
+/// A proving system for the circuits of this module. The circuits are written once against the constraint systems
+/// of fawkes-crypto, and a backend turns them into keys and proofs, so that e.g. `ZkSip::prove_on` proves the same
+/// statement on any backend.
+///
+/// `Groth16` has a trusted setup per circuit. `Plonk`, behind the `plonk` feature, derives the keys of every circuit
+/// from one universal reference string.
+pub trait Backend {
+    type Fr: PrimeField;
+    /// Reference string shared by the setups of all circuits, `()` for a circuit-specific setup.
+    type Srs;
+    /// Proving key of one circuit, along with what is needed to derive the verifying key.
+    type Params;
+    type Vk;
+    type Proof;
+
+    /// Generate the parameters of `circuit` from `srs`.
+    fn setup<Pub, Sec, C>(srs: &Self::Srs, circuit: C) -> Result<Self::Params, ParamsError>
+        where Pub: Signal<BuildCS<Self::Fr>>, Sec: Signal<BuildCS<Self::Fr>>, C: Fn(Pub, Sec);
+
+    /// The verifying key, to be pinned by verifiers.
+    fn vk(params: &Self::Params) -> Self::Vk;
+
+    /// Prove `circuit` for the public and secret inputs, and return the public inputs of the proof along with it.
+    fn prove<'a, Pub, Sec, C>(params: &'a Self::Params, public: &Pub::Value, secret: &Sec::Value, circuit: C) -> (Vec<Num<Self::Fr>>, Self::Proof)
+        where Pub: Signal<WitnessCS<'a, Self::Fr>>, Sec: Signal<WitnessCS<'a, Self::Fr>>, C: Fn(Pub, Sec);
+
+    fn verify(vk: &Self::Vk, proof: &Self::Proof, inputs: &[Num<Self::Fr>]) -> bool;
+}
+
+/// Groth16 on the engine `E`, the backend of `ZkParams` and `SnarkInfo`.
+pub struct Groth16<E: Engine>(PhantomData<E>);
+
+impl<E: Engine> Backend for Groth16<E> {
+    type Fr = E::Fr;
+    type Srs = ();
+    type Params = Parameters<E>;
+    type Vk = VK<E>;
+    type Proof = Proof<E>;
+
+    fn setup<Pub, Sec, C>(_: &(), circuit: C) -> Result<Self::Params, ParamsError>
+        where Pub: Signal<BuildCS<Self::Fr>>, Sec: Signal<BuildCS<Self::Fr>>, C: Fn(Pub, Sec) {
+        Ok(setup::<E, _, _, _>(circuit))
+    }
+
+    fn vk(params: &Self::Params) -> Self::Vk {
+        params.get_vk()
+    }
+
+    fn prove<'a, Pub, Sec, C>(params: &'a Self::Params, public: &Pub::Value, secret: &Sec::Value, circuit: C) -> (Vec<Num<Self::Fr>>, Self::Proof)
+        where Pub: Signal<WitnessCS<'a, Self::Fr>>, Sec: Signal<WitnessCS<'a, Self::Fr>>, C: Fn(Pub, Sec) {
+        prover::prove(params, public, secret, circuit)
+    }
+
+    fn verify(vk: &Self::Vk, proof: &Self::Proof, inputs: &[Num<Self::Fr>]) -> bool {
+        verifier::verify(vk, proof, inputs)
+    }
+}
+
+/// A proof on the backend `B` with its public inputs. Unlike `SnarkInfo` it does not carry a verifying key: the
+/// verifier uses the one it pinned from the setup.
+///
+/// # Examples
+///
+/// ```ignore
+/// let params = ZkSip::<3>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+/// let (public, snark) = ZkSip::<3>::prove_on(&params, &g, &h, &s, &y).unwrap();
+/// assert_eq!(ZkSip::<3>::verify_on::<Groth16<Bn256>>(&params.vk(), &public, &snark), Ok(()));
+/// ```
+pub struct Snark<B: Backend> {
+    pub inputs: Vec<Num<B::Fr>>,
+    pub proof: B::Proof,
+}
+
+impl<E: Engine> Snark<Groth16<E>> {
+    /// Attach the verifying key `vk`, e.g. to export the proof as a `SnarkBundle`.
+    pub fn with_vk(self, vk: VK<E>) -> SnarkInfo<E> {
+        SnarkInfo {
+            inputs: self.inputs,
+            proof: self.proof,
+            vk
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use fawkes_crypto::{
+    circuit::cs::CS,
+    circuit::num::CNum,
+    circuit::poseidon::c_poseidon_sponge,
+    native::poseidon::{PoseidonParams, poseidon_sponge},
+    rand::{thread_rng, Rng},
+    ff_uint::{Num, PrimeField},
+};
+
+use std::str::FromStr;
+use num_bigint::{BigInt};
+use crate::math::matrix::{BigIntMatrix};
+use crate::utils::{reduce};
+
+
+/// The opening of a commitment `poseidon(blinding, f_1, ..., f_n)` to a function, i.e. to the vector `y` of an inner
+/// product or to the matrix `F` of a quadratic polynomial, row by row. The data owner publishes the commitments of
+/// the functions they approve, and every key issued for one of them comes with a proof against its commitment, see
+/// `ZkSipFunction` and `ZkQpFunction`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let opening = FunctionOpening::<Bn256Fr>::matrix(&f);
+/// let commitment = opening.commitment();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FunctionOpening<Fr: PrimeField> {
+    pub(crate) blinding: Num<Fr>,
+    pub(crate) function: Vec<Num<Fr>>,
+}
+
+impl<Fr: PrimeField> FunctionOpening<Fr> {
+    /// Commit to the function vector `y` with a random blinding.
+    pub fn vector(y: &[Num<Fr>]) -> Self {
+        Self { blinding: thread_rng().gen(), function: y.to_vec() }
+    }
+
+    /// Commit to the matrix `f` with a random blinding. Negative entries are reduced into the field.
+    pub fn matrix(f: &BigIntMatrix) -> Self {
+        let function: Vec<Num<Fr>> = (0..f.n_rows)
+            .flat_map(|i| (0..f.n_cols).map(move |j| to_field(f.get_element(i, j))))
+            .collect();
+        Self::vector(&function)
+    }
+
+    /// The commitment to publish.
+    pub fn commitment(&self) -> Num<Fr> {
+        let inputs: Vec<Num<Fr>> = std::iter::once(self.blinding).chain(self.function.iter().cloned()).collect();
+        poseidon_sponge(&inputs, &poseidon_params())
+    }
+}
+
+/// The commitment of `FunctionOpening::commitment` in a circuit.
+pub(crate) fn c_commitment<C: CS>(blinding: &CNum<C>, function: &[CNum<C>]) -> CNum<C> {
+    let inputs: Vec<CNum<C>> = std::iter::once(blinding.clone()).chain(function.iter().cloned()).collect();
+    c_poseidon_sponge(&inputs, &poseidon_params())
+}
+
+fn poseidon_params<Fr: PrimeField>() -> PoseidonParams<Fr> {
+    PoseidonParams::<Fr>::new(3, 8, 53)
+}
+
+/// Reduce an integer into the field `Fr`.
+pub(crate) fn to_field<Fr: PrimeField>(x: &BigInt) -> Num<Fr> {
+    // the field modulus, one more than its largest element
+    let modulus = BigInt::from_str(&(-Num::<Fr>::ONE).to_string()).unwrap() + 1;
+    Num::<Fr>::from_str(&reduce(x, &modulus).to_string()).ok().unwrap()
+}
@@ -0,0 +1,124 @@
+use miracl_core::bls12381::pair;
+use num_bigint::{BigInt};
+
+use crate::define::{BigNum, G1, G2, Gt, MODULUS};
+use crate::dmcfe_ip::{DmcfeCipher, DmcfeDecKey, label_points, weighted_sum};
+use crate::math::matrix::{BigIntMatrix, BigNumMatrix, convert};
+use crate::quadratic_sgp::{SgpCipher, SgpDecKey, SgpPubKey, projections};
+use crate::simple_ip::{SipCipher, SipDk, SipMpk};
+use crate::utils::{reduce};
+
+use super::VerifyError;
+use super::sigma::{LinearRelation, PairingProof, PairingRelation, SigmaGroup, SigmaProof, to_bigint};
+
+
+/// Prove that `z` is the decryption of `cipher` with `dk`, i.e. that `sum_i y_i c_i - dk c0 = g^z`, without
+/// revealing `dk`. Together with `g^dk = sum_i y_i v_i`, which anyone can compute from the master public key, this is
+/// an equality of discrete logarithms with respect to `g` and `c0`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let z = sip.decrypt(&cipher, &dk).unwrap();
+/// let proof = prove_sip_decryption(sip.mpk(), &cipher, &dk, &z);
+/// // anyone holding the ciphertext and the function vector
+/// verify_sip_decryption(sip.mpk(), &cipher, &y, &z, &proof).unwrap();
+/// ```
+pub fn prove_sip_decryption<const L: usize>(mpk: &SipMpk<L>, cipher: &SipCipher<L>, dk: &SipDk<L>, z: &BigInt) -> SigmaProof {
+    let y: Vec<BigInt> = dk.y.iter().map(to_bigint).collect();
+    sip_relation(mpk, cipher, &y, z).prove(&[to_bigint(&dk.dk)], "sip decryption")
+}
+
+/// Verify a proof that `z` is the decryption of `cipher` with the functional evaluation key for `y`.
+pub fn verify_sip_decryption<const L: usize>(mpk: &SipMpk<L>, cipher: &SipCipher<L>, y: &[BigInt; L], z: &BigInt, proof: &SigmaProof) -> Result<(), VerifyError> {
+    sip_relation(mpk, cipher, y, z).verify(proof, "sip decryption")
+}
+
+fn sip_relation<const L: usize>(mpk: &SipMpk<L>, cipher: &SipCipher<L>, y: &[BigInt], z: &BigInt) -> LinearRelation<G1> {
+    let g = G1::generator();
+    let (mut gdk, mut c0dk) = (g.mul_scalar(&BigInt::from(0)), g.mul_scalar(&-z));
+    for (i, yi) in y.iter().enumerate() {
+        gdk = gdk.add_point(&mpk.v[i].mul_scalar(yi));
+        c0dk = c0dk.add_point(&cipher.c[i].mul_scalar(yi));
+    }
+    LinearRelation::new(1)
+        .equation(gdk, vec![(0, g)])
+        .equation(c0dk, vec![(0, cipher.c0.clone())])
+}
+
+/// Prove that `z` is the decryption of `cipher` with `dk`, i.e. that `e(key, g1^gamma) = e(g1, g2)^z / P` for the
+/// product `P` of the pairings of the ciphertext weighted by `f`, without revealing the key. The key is tied to the
+/// public key by `e(key, g1) = prod_j e(sum_i f_ij g1^(s_i), g2^(t_j))`.
+///
+/// For a ciphertext projected with `Sgp::project` and a key from `Sgp::derive_fe_key_projected`, the public key
+/// must be projected with the same matrix, see `SgpPubKey::project`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let z = sgp.decrypt(&cipher, &dk).unwrap();
+/// let proof = prove_sgp_decryption(sgp.pk(), &cipher, &dk, &z);
+/// verify_sgp_decryption(sgp.pk(), &cipher, &f, &z, &proof).unwrap();
+/// ```
+pub fn prove_sgp_decryption<const L: usize>(pk: &SgpPubKey, cipher: &SgpCipher<L>, dk: &SgpDecKey, z: &BigInt) -> PairingProof {
+    sgp_relation(pk, cipher, &dk.f, z).prove(std::slice::from_ref(&dk.key), "sgp decryption")
+}
+
+/// Verify a proof that `z` is the decryption of `cipher` with the functional evaluation key for `f`.
+pub fn verify_sgp_decryption<const L: usize>(pk: &SgpPubKey, cipher: &SgpCipher<L>, f: &BigIntMatrix, z: &BigInt, proof: &PairingProof) -> Result<(), VerifyError> {
+    let lengths = [(f.n_rows, cipher.a.len() / 2), (f.n_cols, cipher.b.len() / 2), (f.n_rows, pk.g1s.len()), (f.n_cols, pk.g2t.len())];
+    if let Some(&(expected, actual)) = lengths.iter().find(|(expected, actual)| expected != actual) {
+        return Err(VerifyError::InputLength { expected, actual });
+    }
+    sgp_relation(pk, cipher, &convert(f, &MODULUS), z).verify(proof, "sgp decryption")
+}
+
+fn sgp_relation<const L: usize>(pk: &SgpPubKey, cipher: &SgpCipher<L>, f: &BigNumMatrix, z: &BigInt) -> PairingRelation {
+    let mut key = Gt::new();
+    key.one();
+    for j in 0..f.n_cols {
+        let mut fs = G1::new();
+        fs.inf();
+        for i in 0..f.n_rows {
+            fs.add(&pk.g1s[i].mul(f.get_element(i, j)));
+        }
+        key.mul(&pair::ate(&pk.g2t[j], &fs));
+    }
+    let key = pair::fexp(&key);
+    let masked = Gt::generator().mul_scalar(z).sub_point(&projections(cipher, f));
+    PairingRelation::new(1)
+        .equation(key, vec![(0, G1::generator())])
+        .equation(masked, vec![(0, cipher.g1_mul_gamma.clone())])
+}
+
+/// Prove that `z` is the decryption of `ciphers`, encrypted with `label`, with `dk`, i.e. that
+/// `e(g2, sum_i y_i c_i) / e(g2, g1)^z = e(key_0, H(0 || label)) e(key_1, H(1 || label))`, without revealing the key.
+/// Since the points `H(k || label)` are hashed to the curve, nobody can find a key satisfying the equation for another
+/// result.
+///
+/// # Examples
+///
+/// ```ignore
+/// let z = client.decrypt_with_label(&ciphers, &dk, label).unwrap();
+/// let proof = prove_dmcfe_decryption(&ciphers, &dk, label, &z);
+/// verify_dmcfe_decryption(&ciphers, &y, label, &z, &proof).unwrap();
+/// ```
+pub fn prove_dmcfe_decryption<const L: usize>(ciphers: &[DmcfeCipher], dk: &DmcfeDecKey<L>, label: &str, z: &BigInt) -> PairingProof {
+    dmcfe_relation(ciphers, &dk.y, label, z).prove(&dk.key, "dmcfe decryption")
+}
+
+/// Verify a proof that `z` is the decryption of `ciphers`, encrypted with `label`, with the functional evaluation
+/// key for `y`.
+pub fn verify_dmcfe_decryption<const L: usize>(ciphers: &[DmcfeCipher], y: &[BigInt; L], label: &str, z: &BigInt, proof: &PairingProof) -> Result<(), VerifyError> {
+    if ciphers.len() != L {
+        return Err(VerifyError::InputLength { expected: L, actual: ciphers.len() });
+    }
+    let y: Vec<BigNum> = y.iter().map(|yi| BigNum::fromstring(reduce(yi, &MODULUS).to_str_radix(16))).collect();
+    dmcfe_relation(ciphers, &y, label, z).verify(proof, "dmcfe decryption")
+}
+
+fn dmcfe_relation(ciphers: &[DmcfeCipher], y: &[BigNum], label: &str, z: &BigInt) -> PairingRelation {
+    let sum = pair::fexp(&pair::ate(&G2::generator(), &weighted_sum(ciphers, y)));
+    let [h0, h1] = label_points(label);
+    PairingRelation::new(2).equation(sum.sub_point(&Gt::generator().mul_scalar(z)), vec![(0, h0), (1, h1)])
+}
@@ -1,24 +1,20 @@
 use fawkes_crypto::{
     backend::bellman_groth16::{
-        prover,
         engines::{Bn256},
-        setup::setup
+        verifier::VK
     },
     circuit::cs::{CS},
     circuit::num::CNum,
     circuit::bitify::c_into_bits_le_strict,
     circuit::ecc::*,
     core::signal::Signal,
-    engines::bn256::{JubJubBN256},
     native::ecc::*,
     ff_uint::{Num},
 };
+use std::marker::PhantomData;
 
-use super::SnarkInfo;
-
-type Fr = fawkes_crypto::engines::bn256::Fr;
-type E = Bn256;
-type JjParams = JubJubBN256;
+use super::{CircuitCost, ParamsError, SnarkInfo, VerifyError, Zk, ZkParams, ZkSetup, circuit_cost, verify_points};
+use super::backend::{Backend, Groth16, Snark};
 
 
 pub fn c_dlog<C: CS, J: JubJubParams<Fr = C::Fr>>(g: &CEdwardsPoint<C>, x: &CNum<C>, params: &J) -> CEdwardsPoint<C> {
@@ -27,12 +23,12 @@ pub fn c_dlog<C: CS, J: JubJubParams<Fr = C::Fr>>(g: &CEdwardsPoint<C>, x: &CNum
 }
 
 
-/// Zero knowledge proof for a discrete logarithm.
-pub struct ZkDlog;
+/// Zero knowledge proof for a discrete logarithm, on the engine `Z`.
+pub struct ZkDlog<Z: Zk = Bn256>(PhantomData<Z>);
 
-impl ZkDlog {
-    fn circuit<C: CS<Fr = Fr>>(public: (CEdwardsPoint<C>, CEdwardsPoint<C>), secret: CNum<C>) {
-        let jj_params = JjParams::new(); 
+impl<Z: Zk> ZkDlog<Z> {
+    fn circuit<C: CS<Fr = Z::Fr>>(public: (CEdwardsPoint<C>, CEdwardsPoint<C>), secret: CNum<C>) {
+        let jj_params = Z::jubjub_params();
         let signal_y = c_dlog(&public.0, &secret, &jj_params);
         signal_y.assert_eq(&public.1);
     }
@@ -46,18 +42,69 @@ impl ZkDlog {
     /// let jubjub_params = JubJubBN256::new();
     /// let g = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params).mul(Num::from(8), &jubjub_params);
     /// let x: Num<Bn256Fr> = rng.gen();
-    /// let snark = ZkDlog::generate(&g, &x);
+    /// let snark = ZkDlog::<Bn256>::generate(&g, &x);
     /// ```
-    pub fn generate(g: &EdwardsPoint<Fr>, x: &Num<Fr>) -> SnarkInfo<E> {
-        let jubjub_params = JjParams::new(); 
-        let bellman_params = setup::<E, _, _, _>(ZkDlog::circuit);
+    pub fn generate(g: &EdwardsPoint<Z::Fr>, x: &Num<Z::Fr>) -> SnarkInfo<Z::E> {
+        Self::generate_with(&Self::setup(), g, x).unwrap()
+    }
+
+    /// Run the trusted setup of the circuit, once for all proofs generated with `generate_with`.
+    pub fn setup() -> ZkParams<Z::E> {
+        Self::setup_on(&()).unwrap()
+    }
+
+    /// Run the setup of the circuit on the backend `B` with its reference string `srs`, `&()` for Groth16, once for
+    /// all proofs generated with `generate_on`.
+    pub fn setup_on<B: Backend<Fr = Z::Fr>>(srs: &B::Srs) -> Result<ZkSetup<B>, ParamsError> {
+        Ok(ZkSetup::new(Self::circuit_name(), B::setup(srs, Self::circuit)?))
+    }
+
+    /// The id of the circuit, recorded in its parameters and exported bundles.
+    pub fn circuit_name() -> String {
+        "dlog".to_string()
+    }
+
+    /// The size of the circuit, computed without a setup.
+    pub fn cost() -> CircuitCost {
+        circuit_cost(Self::circuit)
+    }
+
+    /// Generate zero knowledge proof for y=g^x with parameters from `setup`.
+    pub fn generate_with(params: &ZkParams<Z::E>, g: &EdwardsPoint<Z::Fr>, x: &Num<Z::Fr>) -> Result<SnarkInfo<Z::E>, ParamsError> {
+        Ok(Self::generate_on(params, g, x)?.with_vk(params.vk()))
+    }
+
+    /// Generate zero knowledge proof for y=g^x on the backend `B`, with parameters from `setup_on`.
+    pub fn generate_on<B: Backend<Fr = Z::Fr>>(params: &ZkSetup<B>, g: &EdwardsPoint<Z::Fr>, x: &Num<Z::Fr>) -> Result<Snark<B>, ParamsError> {
+        params.check(&Self::circuit_name())?;
+        let jubjub_params = Z::jubjub_params();
         let y = g.mul(x.to_other_reduced(), &jubjub_params);
-        let (inputs, snark_proof) = prover::prove(&bellman_params, &(*g, y), x, ZkDlog::circuit);
-        SnarkInfo::<E> {
-            inputs,
-            proof: snark_proof,
-            vk: bellman_params.get_vk()
-        }
+        let (inputs, proof) = B::prove(&params.params, &(*g, y), x, Self::circuit);
+        Ok(Snark { inputs, proof })
+    }
+
+    /// Verify a proof for the statement `y=g^x` with the verifying key `vk` of the parameters the proof was
+    /// generated with. The key shipped with the proof is not trusted: a prover controls it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let params = ZkDlog::<Bn256>::setup();
+    /// let snark = ZkDlog::<Bn256>::generate_with(&params, &g, &x).unwrap();
+    /// let y = g.mul(x.to_other_reduced(), &jubjub_params);
+    /// assert_eq!(ZkDlog::<Bn256>::verify_with(&params.vk(), &g, &y, &snark), Ok(()));
+    /// ```
+    pub fn verify_with(vk: &VK<Z::E>, g: &EdwardsPoint<Z::Fr>, y: &EdwardsPoint<Z::Fr>, snark: &SnarkInfo<Z::E>) -> Result<(), VerifyError> {
+        verify_points::<Groth16<Z::E>>(vk, &snark.inputs, &snark.proof, &Self::points(g, y))
+    }
+
+    /// Verify a proof generated by `generate_on` like `verify_with`, with the verifying key `vk` of the backend `B`.
+    pub fn verify_on<B: Backend<Fr = Z::Fr>>(vk: &B::Vk, g: &EdwardsPoint<Z::Fr>, y: &EdwardsPoint<Z::Fr>, snark: &Snark<B>) -> Result<(), VerifyError> {
+        verify_points::<B>(vk, &snark.inputs, &snark.proof, &Self::points(g, y))
+    }
+
+    fn points(g: &EdwardsPoint<Z::Fr>, y: &EdwardsPoint<Z::Fr>) -> Vec<(String, EdwardsPoint<Z::Fr>)> {
+        vec![("g".to_string(), *g), ("y".to_string(), *y)]
     }
 
 }
@@ -0,0 +1,337 @@
+use bellman::pairing::{CurveAffine, RawEncodable};
+use fawkes_crypto::{
+    backend::bellman_groth16::{
+        engines::{Bn256, Engine},
+        group::{G1Point, G2Point},
+        prover::Proof,
+        verifier::VK
+    },
+    ff_uint::Num,
+    BorshDeserialize,
+    BorshSerialize,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::io::Cursor;
+
+use super::SnarkInfo;
+
+
+/// Version of the bundle format written by `SnarkBundle::to_json`, bumped on any incompatible change.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// How the Borsh serialized verifying key, proof and public inputs are written in a bundle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Base64,
+    /// `0x` prefixed lowercase hex, as expected by EVM tooling.
+    Hex,
+}
+
+/// The reason a bundle cannot be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportError {
+    Json(String),
+    /// The bundle was written with another version of the format.
+    Version { expected: u32, actual: u32 },
+    /// The named field is not the encoding of a value on this engine, e.g. `proof` or `vk`.
+    Decoding { field: String },
+    /// A point of the named field is not on the curve.
+    InvalidPoint { field: String },
+}
+
+/// A proof together with everything needed to check it on-chain: the verifying key, the public inputs, and the id of
+/// the circuit it is for, e.g. `sip/3` as returned by `ZkSip::<3>::circuit_name`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let (public, snark) = ZkSip::<3>::prove(&g, &h, &s, &y);
+/// let json = SnarkBundle::new(ZkSip::<3>::circuit_name(), snark).to_json(Encoding::Hex);
+/// // on the verifier side
+/// let bundle = SnarkBundle::<Bn256>::from_json(&json).unwrap();
+/// assert_eq!(bundle.circuit, "sip/3");
+/// assert_eq!(ZkSip::<3>::verify_with(&pinned_vk, &public, &bundle.snark), Ok(()));
+/// ```
+pub struct SnarkBundle<E: Engine> {
+    pub circuit: String,
+    pub snark: SnarkInfo<E>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleJson {
+    version: u32,
+    circuit: String,
+    encoding: String,
+    vk: String,
+    proof: String,
+    inputs: String,
+}
+
+impl<E: Engine> SnarkBundle<E> {
+    pub fn new(circuit: String, snark: SnarkInfo<E>) -> Self {
+        Self { circuit, snark }
+    }
+
+    pub fn to_json(&self, encoding: Encoding) -> String {
+        let encode = |bytes: Vec<u8>| match encoding {
+            Encoding::Base64 => base64::encode(bytes),
+            Encoding::Hex => to_hex(&bytes),
+        };
+        let json = BundleJson {
+            version: BUNDLE_VERSION,
+            circuit: self.circuit.clone(),
+            encoding: match encoding {
+                Encoding::Base64 => "base64".to_string(),
+                Encoding::Hex => "hex".to_string(),
+            },
+            vk: encode(self.snark.vk.try_to_vec().unwrap()),
+            proof: encode(self.snark.proof.try_to_vec().unwrap()),
+            inputs: encode(self.snark.inputs.try_to_vec().unwrap()),
+        };
+        serde_json::to_string(&json).unwrap()
+    }
+
+    /// Import a bundle written by `to_json` in either encoding. The points of the verifying key and the proof are
+    /// checked to be on the curve, the public inputs to be in the field.
+    pub fn from_json(json: &str) -> Result<Self, ExportError> {
+        let bundle: BundleJson = serde_json::from_str(json).map_err(|e| ExportError::Json(e.to_string()))?;
+        if bundle.version != BUNDLE_VERSION {
+            return Err(ExportError::Version { expected: BUNDLE_VERSION, actual: bundle.version });
+        }
+        let decode = |field: &str, value: &str| {
+            let bytes = match bundle.encoding.as_str() {
+                "base64" => base64::decode(value).ok(),
+                "hex" => from_hex(value),
+                _ => None,
+            };
+            bytes.ok_or_else(|| ExportError::Decoding { field: field.to_string() })
+        };
+        let vk: VK<E> = from_borsh("vk", &decode("vk", &bundle.vk)?)?;
+        let proof: Proof<E> = from_borsh("proof", &decode("proof", &bundle.proof)?)?;
+        let inputs: Vec<Num<E::Fr>> = from_borsh("inputs", &decode("inputs", &bundle.inputs)?)?;
+
+        let vk_valid = g1_on_curve(&vk.alpha) && [&vk.beta, &vk.gamma, &vk.delta].iter().all(|p| g2_on_curve(p))
+            && vk.ic.iter().all(g1_on_curve);
+        if !vk_valid {
+            return Err(ExportError::InvalidPoint { field: "vk".to_string() });
+        }
+        if !(g1_on_curve(&proof.a) && g2_on_curve(&proof.b) && g1_on_curve(&proof.c)) {
+            return Err(ExportError::InvalidPoint { field: "proof".to_string() });
+        }
+        Ok(Self { circuit: bundle.circuit, snark: SnarkInfo { inputs, proof, vk } })
+    }
+}
+
+fn from_borsh<T: BorshDeserialize>(field: &str, bytes: &[u8]) -> Result<T, ExportError> {
+    T::try_from_slice(bytes).map_err(|_| ExportError::Decoding { field: field.to_string() })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = "0x".to_string();
+    for b in bytes.iter() {
+        write!(hex, "{:02x}", b).unwrap();
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x")?;
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+/// Whether `p` is the point at infinity, encoded as zeros, or on the curve.
+fn g1_on_curve<E: Engine>(p: &G1Point<E>) -> bool {
+    type G1Affine<E> = <<E as Engine>::BE as bellman::pairing::Engine>::G1Affine;
+    if p.0 == Num::ZERO && p.1 == Num::ZERO {
+        return true;
+    }
+    let mut buf = G1Affine::<E>::zero().into_raw_uncompressed_le();
+    {
+        let mut cur = Cursor::new(buf.as_mut());
+        for c in [&p.0, &p.1].iter() {
+            BorshSerialize::serialize(&c.to_mont_uint(), &mut cur).unwrap();
+        }
+    }
+    G1Affine::<E>::from_raw_uncompressed_le(&buf, false).is_ok()
+}
+
+/// Whether `p` is the point at infinity, encoded as zeros, or on the twisted curve.
+fn g2_on_curve<E: Engine>(p: &G2Point<E>) -> bool {
+    type G2Affine<E> = <<E as Engine>::BE as bellman::pairing::Engine>::G2Affine;
+    let coordinates = [&p.0 .0, &p.0 .1, &p.1 .0, &p.1 .1];
+    if coordinates.iter().all(|c| **c == Num::ZERO) {
+        return true;
+    }
+    let mut buf = G2Affine::<E>::zero().into_raw_uncompressed_le();
+    {
+        let mut cur = Cursor::new(buf.as_mut());
+        for c in coordinates.iter() {
+            BorshSerialize::serialize(&c.to_mont_uint(), &mut cur).unwrap();
+        }
+    }
+    G2Affine::<E>::from_raw_uncompressed_le(&buf, false).is_ok()
+}
+
+impl SnarkInfo<Bn256> {
+    /// The arguments `a, b, c, input` of `verifyProof` in the contract of `solidity_verifier`, as a comma separated
+    /// list of JSON arrays of decimal strings.
+    /// Experimental, like `solidity_verifier`.
+    pub fn to_solidity_calldata(&self) -> String {
+        let inputs: Vec<String> = self.inputs.iter().map(|x| format!("\"{}\"", x)).collect();
+        format!(
+            "{},{},{},[{}]",
+            g1_array(&self.proof.a),
+            g2_array(&self.proof.b),
+            g1_array(&self.proof.c),
+            inputs.join(",")
+        )
+    }
+}
+
+fn g1_array(p: &G1Point<Bn256>) -> String {
+    format!("[\"{}\",\"{}\"]", p.0, p.1)
+}
+
+/// The EVM precompiles take the coordinates of G2 points with the imaginary part first.
+fn g2_array(p: &G2Point<Bn256>) -> String {
+    format!("[[\"{}\",\"{}\"],[\"{}\",\"{}\"]]", p.0 .1, p.0 .0, p.1 .1, p.1 .0)
+}
+
+fn g1_solidity(p: &G1Point<Bn256>) -> String {
+    format!("G1Point({}, {})", p.0, p.1)
+}
+
+fn g2_solidity(p: &G2Point<Bn256>) -> String {
+    format!("G2Point([{}, {}], [{}, {}])", p.0 .1, p.0 .0, p.1 .1, p.1 .0)
+}
+
+/// Generate a Solidity contract `contract` that verifies Groth16 proofs for the verifying key `vk` with the BN256
+/// precompiles of the EVM. Its `verifyProof` takes the arguments produced by `SnarkInfo::to_solidity_calldata`.
+///
+/// Experimental: the contract has not been compiled and run against an EVM here, only its contents are checked.
+/// Test it with the calldata of a known proof before deploying it.
+///
+/// # Examples
+///
+/// ```ignore
+/// let params = ZkSip::<3>::setup();
+/// std::fs::write("SipVerifier.sol", solidity_verifier(&params.vk(), "SipVerifier")).unwrap();
+/// ```
+pub fn solidity_verifier(vk: &VK<Bn256>, contract: &str) -> String {
+    let mut ic = String::new();
+    for (i, p) in vk.ic.iter().enumerate() {
+        writeln!(ic, "        vk.ic[{}] = {};", i, g1_solidity(p)).unwrap();
+    }
+    SOLIDITY_TEMPLATE
+        .replace("{contract}", contract)
+        .replace("{inputs}", &(vk.ic.len() - 1).to_string())
+        .replace("{ic_len}", &vk.ic.len().to_string())
+        .replace("{alpha}", &g1_solidity(&vk.alpha))
+        .replace("{beta}", &g2_solidity(&vk.beta))
+        .replace("{gamma}", &g2_solidity(&vk.gamma))
+        .replace("{delta}", &g2_solidity(&vk.delta))
+        .replace("{ic}\n", &ic)
+}
+
+const SOLIDITY_TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+/// Groth16 verifier on BN256, generated by ruby.
+contract {contract} {
+    uint256 constant SCALAR_FIELD = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    uint256 constant BASE_FIELD = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+    struct G1Point {
+        uint256 x;
+        uint256 y;
+    }
+
+    // coordinates are [imaginary, real]
+    struct G2Point {
+        uint256[2] x;
+        uint256[2] y;
+    }
+
+    struct VerifyingKey {
+        G1Point alpha;
+        G2Point beta;
+        G2Point gamma;
+        G2Point delta;
+        G1Point[] ic;
+    }
+
+    function verifyingKey() internal pure returns (VerifyingKey memory vk) {
+        vk.alpha = {alpha};
+        vk.beta = {beta};
+        vk.gamma = {gamma};
+        vk.delta = {delta};
+        vk.ic = new G1Point[]({ic_len});
+{ic}
+    }
+
+    function negate(G1Point memory p) internal pure returns (G1Point memory) {
+        if (p.x == 0 && p.y == 0) {
+            return p;
+        }
+        return G1Point(p.x, BASE_FIELD - (p.y % BASE_FIELD));
+    }
+
+    function add(G1Point memory p, G1Point memory q) internal view returns (G1Point memory r) {
+        uint256[4] memory input = [p.x, p.y, q.x, q.y];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 6, input, 0x80, r, 0x40)
+        }
+        require(success, "point addition failed");
+    }
+
+    function mul(G1Point memory p, uint256 s) internal view returns (G1Point memory r) {
+        uint256[3] memory input = [p.x, p.y, s];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 7, input, 0x60, r, 0x40)
+        }
+        require(success, "scalar multiplication failed");
+    }
+
+    function pairing(G1Point[4] memory a, G2Point[4] memory b) internal view returns (bool) {
+        uint256[24] memory input;
+        for (uint256 i = 0; i < 4; i++) {
+            input[i * 6] = a[i].x;
+            input[i * 6 + 1] = a[i].y;
+            input[i * 6 + 2] = b[i].x[0];
+            input[i * 6 + 3] = b[i].x[1];
+            input[i * 6 + 4] = b[i].y[0];
+            input[i * 6 + 5] = b[i].y[1];
+        }
+        uint256[1] memory out;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 8, input, 0x300, out, 0x20)
+        }
+        require(success, "pairing failed");
+        return out[0] == 1;
+    }
+
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[{inputs}] calldata input
+    ) external view returns (bool) {
+        VerifyingKey memory vk = verifyingKey();
+        G1Point memory x = vk.ic[0];
+        for (uint256 i = 0; i < input.length; i++) {
+            require(input[i] < SCALAR_FIELD, "input not in the scalar field");
+            x = add(x, mul(vk.ic[i + 1], input[i]));
+        }
+        G1Point[4] memory p = [negate(G1Point(a[0], a[1])), vk.alpha, x, G1Point(c[0], c[1])];
+        G2Point[4] memory q = [G2Point(b[0], b[1]), vk.beta, vk.gamma, vk.delta];
+        return pairing(p, q);
+    }
+}
+"#;
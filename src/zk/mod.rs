@@ -1,21 +1,66 @@
 use fawkes_crypto::{
     backend::bellman_groth16::{
-        engines::{Engine},
+        engines::{Bls12_381, Bn256, Engine},
         prover::Proof,
-        verifier::VK
+        verifier::VK,
+        Parameters
     },
+    circuit::cs::DebugCS,
+    core::signal::Signal,
+    engines::bls12_381::{JubJubBLS12_381},
+    engines::bn256::{JubJubBN256},
     ff_uint::{Num, PrimeField},
+    native::ecc::{EdwardsPoint, JubJubParams},
+    BorshDeserialize,
     BorshSerialize,
 };
+use std::fs;
+use std::path::Path;
 
+use backend::{Backend, Groth16};
 
+
+pub mod backend;
+pub mod commitment;
+pub mod decryption;
 pub mod dlog;
+pub mod export;
+#[cfg(feature = "plonk")]
+pub mod plonk;
 pub mod qp;
+pub mod sigma;
 pub mod sip;
+pub mod sip_range;
 
+/// A fawkes engine together with the JubJub curve embedded in its scalar field, on which the circuits of `ZkDlog`,
+/// `ZkSip` and `ZkQp` are proven. It is implemented by the engines themselves, so that e.g. `ZkSip::<3, Bls12_381>`
+/// proves on BLS12-381; the default is `Bn256`.
 pub trait Zk {
     type Fr: PrimeField;
-    type E: Engine;
+    type E: Engine<Fr = Self::Fr>;
+    type J: JubJubParams<Fr = Self::Fr>;
+
+    fn jubjub_params() -> Self::J;
+}
+
+impl Zk for Bn256 {
+    type Fr = fawkes_crypto::engines::bn256::Fr;
+    type E = Bn256;
+    type J = JubJubBN256;
+
+    fn jubjub_params() -> Self::J {
+        JubJubBN256::new()
+    }
+}
+
+impl Zk for Bls12_381 {
+    type Fr = fawkes_crypto::engines::bls12_381::Fr;
+    type E = Bls12_381;
+    type J = JubJubBLS12_381;
+
+    fn jubjub_params() -> Self::J {
+        JubJubBLS12_381::new()
+    }
 }
 
 pub struct SnarkInfo<E: Engine> {
@@ -24,6 +69,160 @@ pub struct SnarkInfo<E: Engine> {
     pub vk: VK<E> 
 }
 
+/// Proving and verifying keys of one circuit on the backend `B`, generated once by a setup and reused for many
+/// proofs, so that all of them are verified against the same pinned verifying key. `circuit` names the statement
+/// and its dimension, e.g. `sip/3`, and is checked whenever the parameters are used.
+pub struct ZkSetup<B: Backend> {
+    pub circuit: String,
+    pub(crate) params: B::Params,
+}
+
+/// Groth16 parameters of one circuit, generated by a trusted setup, which can be saved and loaded.
+///
+/// # Examples
+///
+/// ```ignore
+/// ZkSip::<3>::setup().save("sip3.params").unwrap();
+/// // later, or on another machine
+/// let params = ZkParams::<Bn256>::load("sip3.params").unwrap();
+/// let (public, snark) = ZkSip::<3>::prove_with(&params, &g, &h, &s, &y).unwrap();
+/// assert_eq!(ZkSip::<3>::verify_with(&params.vk(), &public, &snark), Ok(()));
+/// ```
+pub type ZkParams<E> = ZkSetup<Groth16<E>>;
+
+/// The reason parameters cannot be loaded or used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamsError {
+    Io(String),
+    /// The parameters were generated for another circuit.
+    WrongCircuit { expected: String, actual: String },
+    /// The circuit needs polynomials of degree `degree`, above the `max_degree` of the reference string.
+    SrsTooSmall { degree: usize, max_degree: usize },
+}
+
+/// The reason a proof about a committed function cannot be generated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProveError {
+    Params(ParamsError),
+    /// The opening commits to a function with another number of entries than the circuit takes.
+    FunctionLength { expected: usize, actual: usize },
+}
+
+impl From<ParamsError> for ProveError {
+    fn from(e: ParamsError) -> Self {
+        ProveError::Params(e)
+    }
+}
+
+impl<B: Backend> ZkSetup<B> {
+    pub(crate) fn new(circuit: String, params: B::Params) -> Self {
+        Self { circuit, params }
+    }
+
+    /// The verifying key, to be pinned by verifiers.
+    pub fn vk(&self) -> B::Vk {
+        B::vk(&self.params)
+    }
+
+    /// Check that the parameters are for `circuit`.
+    pub(crate) fn check(&self, circuit: &str) -> Result<(), ParamsError> {
+        if self.circuit != circuit {
+            return Err(ParamsError::WrongCircuit { expected: circuit.to_string(), actual: self.circuit.clone() });
+        }
+        Ok(())
+    }
+}
+
+impl<E: Engine> ZkParams<E> {
+    /// Load parameters written by `save`. Points are checked to be on the curve and in the right subgroup.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParamsError> {
+        let bytes = fs::read(path).map_err(|e| ParamsError::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ParamsError> {
+        fs::write(path, self.to_bytes()).map_err(|e| ParamsError::Io(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParamsError> {
+        let mut reader = bytes;
+        let circuit = String::deserialize(&mut reader).map_err(|e| ParamsError::Io(e.to_string()))?;
+        let params = Parameters::read(&mut reader, true, true).map_err(|e| ParamsError::Io(e.to_string()))?;
+        Ok(Self { circuit, params })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.circuit.try_to_vec().unwrap();
+        self.params.write(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+/// The size of a circuit, which drives the time and memory of setup and proving: the number of R1CS constraints,
+/// of public inputs, and of private witness variables.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitCost {
+    pub constraints: usize,
+    pub public_inputs: usize,
+    pub witness: usize,
+}
+
+/// Synthesize `circuit` in a counting constraint system, the same way `setup` does but without generating keys.
+pub(crate) fn circuit_cost<Fr: PrimeField, Pub: Signal<DebugCS<Fr>>, Sec: Signal<DebugCS<Fr>>, C: Fn(Pub, Sec)>(circuit: C) -> CircuitCost {
+    let rcs = DebugCS::rc_new();
+    let public = Pub::alloc(&rcs, None);
+    public.inputize();
+    let secret = Sec::alloc(&rcs, None);
+    circuit(public, secret);
+
+    let cs = rcs.borrow();
+    CircuitCost {
+        constraints: cs.num_gates,
+        // without the constant one
+        public_inputs: cs.num_input - 1,
+        witness: cs.num_aux,
+    }
+}
+
+/// The reason a proof is rejected by a `verify_with` function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// The proof has a different number of public inputs than the statement, i.e. it is for another statement type
+    /// or dimension.
+    InputLength { expected: usize, actual: usize },
+    /// The public inputs of the proof do not encode the named value of the statement, e.g. `c1` or `v[2]`.
+    InputMismatch { input: String },
+    /// The public inputs match the statement, but the Groth16 verification fails.
+    InvalidProof,
+}
+
+/// Check that the public `inputs` of a proof are the coordinates `(x, y)` of the `points` of a statement, in order,
+/// and verify the proof with `vk`.
+pub(crate) fn verify_points<B: Backend>(vk: &B::Vk, inputs: &[Num<B::Fr>], proof: &B::Proof, points: &[(String, EdwardsPoint<B::Fr>)]) -> Result<(), VerifyError> {
+    let expected: Vec<(String, Vec<Num<B::Fr>>)> = points.iter().map(|(name, p)| (name.clone(), vec![p.x, p.y])).collect();
+    verify_inputs::<B>(vk, inputs, proof, &expected)
+}
+
+/// Check that the public `inputs` of a proof are the named values of a statement, in order, and verify the proof
+/// with `vk`.
+pub(crate) fn verify_inputs<B: Backend>(vk: &B::Vk, inputs: &[Num<B::Fr>], proof: &B::Proof, expected: &[(String, Vec<Num<B::Fr>>)]) -> Result<(), VerifyError> {
+    let length: usize = expected.iter().map(|(_, v)| v.len()).sum();
+    if inputs.len() != length {
+        return Err(VerifyError::InputLength { expected: length, actual: inputs.len() });
+    }
+    let mut offset = 0;
+    for (name, v) in expected.iter() {
+        if inputs[offset..offset + v.len()] != v[..] {
+            return Err(VerifyError::InputMismatch { input: name.clone() });
+        }
+        offset += v.len();
+    }
+    if !B::verify(vk, proof, inputs) {
+        return Err(VerifyError::InvalidProof);
+    }
+    Ok(())
+}
+
 pub trait ToEncoding {
     fn encode(&self) -> String;
 }
@@ -0,0 +1,318 @@
+use dusk_plonk::prelude::{BlsScalar, Circuit, Compiler, Composer, Constraint, Error, Proof, Prover, PublicParameters, Verifier, Witness};
+use fawkes_crypto::{
+    circuit::cs::{BuildCS, Gate, WitnessCS},
+    circuit::lc::Index,
+    core::signal::Signal,
+    engines::bls12_381::Fr,
+    ff_uint::{Num, Uint},
+};
+use rand::rngs::OsRng;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::ParamsError;
+use super::backend::Backend;
+
+
+/// Extra degree of the SRS and of the circuit polynomials over the number of gates, for the blinding factors.
+const BLINDING_DEGREE: usize = 6;
+
+/// Label of the transcripts of the proofs, binding them to this crate.
+const LABEL: &[u8] = b"ruby";
+
+/// Universal reference string of `Plonk`: one SRS of degree `max_degree` serves the setup of every circuit with at
+/// most `max_degree` gates, rounded up to a power of two.
+///
+/// # Examples
+///
+/// ```ignore
+/// let srs = PlonkSrs::generate(1 << 16);
+/// srs.save("plonk.srs").unwrap();
+/// // later, or on another machine
+/// let srs = PlonkSrs::load("plonk.srs").unwrap();
+/// let params = ZkSip::<3, Bls12_381>::setup_on::<Plonk>(&srs).unwrap();
+/// ```
+pub struct PlonkSrs(PublicParameters);
+
+impl PlonkSrs {
+    /// Generate an SRS from local randomness. Whoever runs it knows the trapdoor and can forge proofs, so in
+    /// production the SRS comes from a ceremony and is loaded with `load`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_degree` is zero.
+    pub fn generate(max_degree: usize) -> Self {
+        PlonkSrs(PublicParameters::setup(max_degree, &mut OsRng).expect("Malformed input: the degree must be positive"))
+    }
+
+    /// The largest number of gates, rounded up to a power of two, of a circuit set up from this SRS.
+    pub fn max_degree(&self) -> usize {
+        self.0.max_degree() - BLINDING_DEGREE
+    }
+
+    /// Load an SRS written by `save`. Points are checked to be on the curve and in the right subgroup.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParamsError> {
+        let bytes = fs::read(path).map_err(|e| ParamsError::Io(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ParamsError> {
+        fs::write(path, self.to_bytes()).map_err(|e| ParamsError::Io(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParamsError> {
+        PublicParameters::from_slice(bytes).map(PlonkSrs).map_err(|e| ParamsError::Io(e.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_var_bytes()
+    }
+}
+
+/// PLONK on BLS12-381 with a universal SRS, from dusk-plonk. The R1CS constraints of the circuits are translated
+/// gate by gate, so that the same circuits prove on `Groth16<Bls12_381>` and `Plonk`.
+///
+/// The keys of a circuit are derived from the SRS without randomness, so a verifier holding the SRS can derive the
+/// verifying key itself instead of trusting the prover's setup.
+pub struct Plonk;
+
+/// Proving and verifying keys of one circuit, along with its constraints, from which the prover computes the witness.
+pub struct PlonkParams {
+    cs: BuildCS<Fr>,
+    prover: Prover,
+    verifier: Verifier,
+}
+
+impl Backend for Plonk {
+    type Fr = Fr;
+    type Srs = PlonkSrs;
+    type Params = PlonkParams;
+    type Vk = Verifier;
+    type Proof = Proof;
+
+    fn setup<Pub, Sec, C>(srs: &PlonkSrs, circuit: C) -> Result<Self::Params, ParamsError>
+        where Pub: Signal<BuildCS<Self::Fr>>, Sec: Signal<BuildCS<Self::Fr>>, C: Fn(Pub, Sec) {
+        let rcs = &BuildCS::rc_new();
+        let signal_pub = Pub::alloc(rcs, None);
+        signal_pub.inputize();
+        let signal_sec = Sec::alloc(rcs, None);
+        circuit(signal_pub, signal_sec);
+        let cs = rcs.borrow().clone();
+
+        let r1cs = R1cs { gates: &cs.gates, num_input: cs.num_input, num_aux: cs.num_aux, values: None };
+        let degree = (r1cs.size() + BLINDING_DEGREE).next_power_of_two();
+        if degree > srs.max_degree() {
+            return Err(ParamsError::SrsTooSmall { degree, max_degree: srs.max_degree() });
+        }
+        let (prover, verifier) = Compiler::compile_with_circuit(&srs.0, LABEL, &r1cs).map_err(|e| ParamsError::Io(e.to_string()))?;
+        Ok(PlonkParams { cs, prover, verifier })
+    }
+
+    fn vk(params: &Self::Params) -> Self::Vk {
+        Verifier::try_from_bytes(params.verifier.to_bytes()).unwrap()
+    }
+
+    fn prove<'a, Pub, Sec, C>(params: &'a Self::Params, public: &Pub::Value, secret: &Sec::Value, circuit: C) -> (Vec<Num<Self::Fr>>, Self::Proof)
+        where Pub: Signal<WitnessCS<'a, Self::Fr>>, Sec: Signal<WitnessCS<'a, Self::Fr>>, C: Fn(Pub, Sec) {
+        // the witness is computed from the values alone, the gates are taken from the parameters
+        let rcs = &WitnessCS::rc_new(params.cs.gates.len(), &[], &params.cs.const_tracker);
+        let signal_pub = Pub::alloc(rcs, Some(public));
+        signal_pub.inputize();
+        let signal_sec = Sec::alloc(rcs, Some(secret));
+        circuit(signal_pub, signal_sec);
+
+        let cs = rcs.borrow();
+        assert!(cs.const_tracker_index == cs.const_tracker.len(), "not all cached data used");
+        let r1cs = R1cs {
+            gates: &params.cs.gates,
+            num_input: params.cs.num_input,
+            num_aux: params.cs.num_aux,
+            values: Some((&cs.values_input, &cs.values_aux)),
+        };
+        let (proof, _) = params.prover.prove(&mut OsRng, &r1cs).expect("the circuit of the parameters");
+        (cs.values_input[1..].to_vec(), proof)
+    }
+
+    fn verify(vk: &Self::Vk, proof: &Self::Proof, inputs: &[Num<Self::Fr>]) -> bool {
+        let inputs: Vec<BlsScalar> = inputs.iter().map(to_scalar).collect();
+        vk.verify(proof, &inputs).is_ok()
+    }
+}
+
+fn to_scalar(n: &Num<Fr>) -> BlsScalar {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&n.to_uint().0.to_little_endian());
+    // a canonical element of the same field
+    Option::from(BlsScalar::from_bytes(&bytes)).unwrap()
+}
+
+type Values<'a> = (&'a [Num<Fr>], &'a [Num<Fr>]);
+
+/// The R1CS constraints `a * b = c` of a fawkes circuit as a PLONK circuit. The public inputs are appended first, in
+/// order, then every linear combination is reduced to a single wire with addition gates and the product is checked
+/// by a multiplication gate. The gates depend on the coefficients only, so the circuit compiled without `values`
+/// is the one proven with them.
+#[derive(Default)]
+struct R1cs<'a> {
+    gates: &'a [Gate<Fr>],
+    num_input: usize,
+    num_aux: usize,
+    /// Values of the input and auxiliary variables, `None` for the setup.
+    values: Option<Values<'a>>,
+}
+
+impl<'a> R1cs<'a> {
+    fn value(&self, index: Index) -> BlsScalar {
+        match (self.values, index) {
+            (Some((input, _)), Index::Input(i)) => to_scalar(&input[i as usize]),
+            (Some((_, aux)), Index::Aux(i)) => to_scalar(&aux[i as usize]),
+            (None, _) => BlsScalar::zero(),
+        }
+    }
+}
+
+impl<'a> Circuit for R1cs<'a> {
+    fn circuit(&self, composer: &mut Composer) -> Result<(), Error> {
+        let mut inputs = vec![Composer::ONE];
+        for i in 1..self.num_input {
+            inputs.push(composer.append_public(self.value(Index::Input(i as u32))));
+        }
+        let aux = (0..self.num_aux).map(|i| composer.append_witness(self.value(Index::Aux(i as u32)))).collect();
+        let mut wires = Wires { inputs, aux, sums: HashMap::new(), rows: HashMap::new() };
+
+        for Gate(a, b, c) in self.gates.iter() {
+            let a = wires.reduce(composer, a);
+            let b = wires.reduce(composer, b);
+            let c = wires.reduce(composer, c);
+            composer.append_gate(Constraint::new().mult(1).output(-BlsScalar::one()).a(a).b(b).c(c));
+        }
+        Ok(())
+    }
+}
+
+/// A variable of a linear combination, `(0, i)` for `Input(i)` and `(1, i)` for `Aux(i)`, in allocation order.
+type Variable = (u8, u32);
+
+/// A linear combination reduced to a wire.
+struct Row {
+    constant: BlsScalar,
+    terms: BTreeMap<Variable, BlsScalar>,
+    wire: Witness,
+}
+
+/// The wires of the variables of a circuit, and of the linear combinations reduced so far.
+///
+/// fawkes does not allocate the sums of its circuits, so a chain of point doublings carries combinations of hundreds
+/// of variables, each one made of the previous ones and of a few new variables. A combination is rewritten with the
+/// wires of the previous ones, by eliminating its variables from the last allocated one, and every addition gate is
+/// only appended once.
+struct Wires {
+    inputs: Vec<Witness>,
+    aux: Vec<Witness>,
+    /// Output of the addition gate of the terms, the constant being the term of `Composer::ONE`.
+    sums: HashMap<Vec<([u8; 32], Witness)>, Witness>,
+    /// The combinations reduced so far, by their last variable.
+    rows: HashMap<Variable, Vec<Row>>,
+}
+
+impl Wires {
+    fn wire(&self, (kind, i): Variable) -> Witness {
+        match kind {
+            0 => self.inputs[i as usize],
+            _ => self.aux[i as usize],
+        }
+    }
+
+    /// A wire holding the value of the linear combination `lc`. The constant terms go into the constant selector,
+    /// the first addition gate sums up to three terms and every next one adds up to two terms to the sum so far.
+    fn reduce(&mut self, composer: &mut Composer, lc: &[(Num<Fr>, Index)]) -> Witness {
+        let mut constant = BlsScalar::zero();
+        let mut terms: BTreeMap<Variable, BlsScalar> = BTreeMap::new();
+        for (k, i) in lc.iter() {
+            match *i {
+                Index::Input(0) => constant += to_scalar(k),
+                Index::Input(i) => *terms.entry((0, i)).or_insert_with(BlsScalar::zero) += to_scalar(k),
+                Index::Aux(i) => *terms.entry((1, i)).or_insert_with(BlsScalar::zero) += to_scalar(k),
+            }
+        }
+        terms.retain(|_, k| *k != BlsScalar::zero());
+
+        let (sum_constant, sum_terms) = self.eliminate(constant, terms.clone());
+        let wire = match (sum_terms.as_slice(), sum_constant == BlsScalar::zero()) {
+            ([], true) => return Composer::ZERO,
+            ([(k, w)], true) if *k == BlsScalar::one() => *w,
+            _ => {
+                let (first, rest) = sum_terms.split_at(sum_terms.len().min(3));
+                let mut sum = self.add(composer, sum_constant, first);
+                for chunk in rest.chunks(2) {
+                    let mut next = vec![(BlsScalar::one(), sum)];
+                    next.extend_from_slice(chunk);
+                    sum = self.add(composer, BlsScalar::zero(), &next);
+                }
+                sum
+            }
+        };
+
+        if terms.len() > 1 {
+            let last = *terms.keys().next_back().unwrap();
+            self.rows.entry(last).or_default().push(Row { constant, terms, wire });
+        }
+        wire
+    }
+
+    /// The constant and terms of a sum equal to `constant` plus `terms`, with the wires of previous combinations.
+    /// From the last variable down, a variable is eliminated with the previous combination ending with it whenever
+    /// this does not make the sum longer.
+    fn eliminate(&self, mut constant: BlsScalar, mut terms: BTreeMap<Variable, BlsScalar>) -> (BlsScalar, Vec<(BlsScalar, Witness)>) {
+        let mut sum = vec![];
+        let mut next = terms.keys().next_back().copied();
+        while let Some(v) = next {
+            let best = self.rows.get(&v).into_iter().flatten().map(|row| {
+                let alpha = terms[&v] * row.terms[&v].invert().unwrap();
+                // the change in the length of the sum: a variable of the row is cancelled, added, or kept
+                let change: isize = row.terms.iter().map(|(u, k)| match terms.get(u) {
+                    Some(l) if *l == alpha * k => -1,
+                    Some(_) => 0,
+                    None => 1,
+                }).sum();
+                (change + 1, alpha, row)
+            }).min_by_key(|(change, _, _)| *change);
+            if let Some((change, alpha, row)) = best {
+                if change <= 0 {
+                    for (u, k) in row.terms.iter() {
+                        let l = terms.remove(u).unwrap_or_else(BlsScalar::zero) - alpha * k;
+                        if l != BlsScalar::zero() {
+                            terms.insert(*u, l);
+                        }
+                    }
+                    constant -= alpha * row.constant;
+                    sum.push((alpha, row.wire));
+                }
+            }
+            next = terms.range(..v).next_back().map(|(u, _)| *u);
+        }
+        sum.extend(terms.iter().map(|(v, k)| (*k, self.wire(*v))));
+        (constant, sum)
+    }
+
+    /// The output of an addition gate of `constant` and up to three `terms`.
+    fn add(&mut self, composer: &mut Composer, constant: BlsScalar, terms: &[(BlsScalar, Witness)]) -> Witness {
+        let mut key: Vec<([u8; 32], Witness)> = terms.iter().map(|(k, w)| (k.to_bytes(), *w)).collect();
+        key.push((constant.to_bytes(), Composer::ONE));
+        if let Some(sum) = self.sums.get(&key) {
+            return *sum;
+        }
+        let mut s = Constraint::new().constant(constant);
+        for (j, (k, w)) in terms.iter().enumerate() {
+            s = match j {
+                0 => s.left(*k).a(*w),
+                1 => s.right(*k).b(*w),
+                _ => s.fourth(*k).d(*w),
+            };
+        }
+        let sum = composer.gate_add(s);
+        self.sums.insert(key, sum);
+        sum
+    }
+}
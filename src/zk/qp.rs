@@ -1,8 +1,7 @@
 use fawkes_crypto::{
     backend::bellman_groth16::{
-        prover,
         engines::{Bn256},
-        setup::setup
+        verifier::VK
     },
     circuit::bool::CBool,
     circuit::cs::{CS, RCS},
@@ -11,22 +10,21 @@ use fawkes_crypto::{
     circuit::ecc::*,
     core::signal::Signal,
     core::sizedvec::SizedVec,
-    engines::bn256::{JubJubBN256},
     native::ecc::*,
     rand::{thread_rng, Rng},
-    ff_uint::{Num, PrimeFieldParams, PrimeField},
+    ff_uint::{Num, PrimeField},
 };
 
+use std::marker::PhantomData;
 use std::str::FromStr;
 use num_bigint::{BigInt}; 
 use crate::math::matrix::{BigIntMatrix};
 use crate::utils::{quadratic_result, reduce};
 
-use super::SnarkInfo;
+use super::{CircuitCost, ParamsError, ProveError, SnarkInfo, VerifyError, Zk, ZkParams, ZkSetup, circuit_cost, verify_inputs, verify_points};
+use super::backend::{Backend, Groth16, Snark};
+use super::commitment::{FunctionOpening, c_commitment};
 
-type Fr = fawkes_crypto::engines::bn256::Fr;
-type E = Bn256;
-type JjParams = JubJubBN256;
 
 
 #[derive(Clone, Debug)]
@@ -39,6 +37,21 @@ pub struct QpProofPublic<Fr: PrimeField, const L: usize> {
     pub c4: SizedVec<EdwardsPoint<Fr>, L>
 }
 
+impl<Fr: PrimeField, const L: usize> QpProofPublic<Fr, L> {
+    /// The named points of the statement, in the order of the public inputs of the proof.
+    fn points(&self) -> Vec<(String, EdwardsPoint<Fr>)> {
+        let mut points = vec![
+            ("g1".to_string(), self.g1),
+            ("h1".to_string(), self.h1),
+            ("c1".to_string(), self.c1),
+            ("c2".to_string(), self.c2),
+        ];
+        points.extend(self.c3.iter().enumerate().map(|(i, c)| (format!("c3[{}]", i), *c)));
+        points.extend(self.c4.iter().enumerate().map(|(i, c)| (format!("c4[{}]", i), *c)));
+        points
+    }
+}
+
 #[derive(Clone, Signal)]
 #[Value = "QpProofPublic<C::Fr, L>"]
 pub struct CqpProofPublic<C: CS, const L: usize> {
@@ -67,13 +80,16 @@ pub struct CqpProofSecret<C: CS, const L: usize> {
     pub t: SizedVec<CNum<C>, L>
 }
 
+/// A public statement together with its proof on the engine `Z`.
+type QpProof<const L: usize, Z> = (QpProofPublic<<Z as Zk>::Fr, L>, SnarkInfo<<Z as Zk>::E>);
+
 /// Zero knowledge proof for quadractic polynomial functional encryption.
-pub struct ZkQp<const L: usize>;
+pub struct ZkQp<const L: usize, Z: Zk = Bn256>(PhantomData<Z>);
 
-impl<const L: usize> ZkQp<L> {
+impl<const L: usize, Z: Zk> ZkQp<L, Z> {
 
-    fn circuit<C: CS<Fr = Fr>>(public: CqpProofPublic<C, L>, secret: CqpProofSecret<C, L>) {
-        let jubjub_params = JjParams::new();
+    fn circuit<C: CS<Fr = Z::Fr>>(public: CqpProofPublic<C, L>, secret: CqpProofSecret<C, L>) {
+        let jubjub_params = Z::jubjub_params();
 
         let f_st_bits = c_into_bits_le_strict(&secret.f_st);
         let r_bits = c_into_bits_le_strict(&secret.r);
@@ -115,19 +131,64 @@ impl<const L: usize> ZkQp<L> {
     /// let bigint_f = BigIntMatrix::new_random(N, N, &low, &high);
     /// let snark = ZkQp::<N>::generate(&g1, &h1, &s, &t, &bigint_f);
     /// ```
-    pub fn generate(g1: &EdwardsPoint<Fr>, h1: &EdwardsPoint<Fr>, s: &SizedVec<Num<Fr>, L>, t: &SizedVec<Num<Fr>, L>, f: &BigIntMatrix) -> SnarkInfo<E> {
-        let jubjub_params = JjParams::new();
-        let mut rng = thread_rng();
+    pub fn generate(g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, f: &BigIntMatrix) -> SnarkInfo<Z::E> {
+        Self::prove(g1, h1, s, t, f).1
+    }
 
-        let r: Num<Fr> = rng.gen();
+    /// Generate zero knowledge proof like `generate`, and return it along with the public statement that
+    /// `verify_with` checks it against.
+    pub fn prove(g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, f: &BigIntMatrix) -> (QpProofPublic<Z::Fr, L>, SnarkInfo<Z::E>) {
+        Self::prove_with(&Self::setup(), g1, h1, s, t, f).unwrap()
+    }
+
+    /// Run the trusted setup of the circuit for dimension `L`, once for all proofs generated with `prove_with`.
+    pub fn setup() -> ZkParams<Z::E> {
+        Self::setup_on(&()).unwrap()
+    }
+
+    /// Run the setup of the circuit for dimension `L` on the backend `B` with its reference string `srs`, `&()` for
+    /// Groth16, once for all proofs generated with `prove_on`.
+    pub fn setup_on<B: Backend<Fr = Z::Fr>>(srs: &B::Srs) -> Result<ZkSetup<B>, ParamsError> {
+        Ok(ZkSetup::new(Self::circuit_name(), B::setup(srs, Self::circuit)?))
+    }
 
-        let bigint_mod = BigInt::from_str(&Fr::MODULUS.to_string()).unwrap();
+    /// The id of the circuit for dimension `L`, e.g. `qp/3`, recorded in its parameters and exported bundles.
+    pub fn circuit_name() -> String {
+        format!("qp/{}", L)
+    }
+
+    /// The size of the circuit for dimension `L`, computed without a setup.
+    pub fn cost() -> CircuitCost {
+        circuit_cost(Self::circuit)
+    }
+
+    /// Generate zero knowledge proof like `prove`, with parameters from `setup`.
+    pub fn prove_with(params: &ZkParams<Z::E>, g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, f: &BigIntMatrix) -> Result<QpProof<L, Z>, ParamsError> {
+        let (public, snark) = Self::prove_on(params, g1, h1, s, t, f)?;
+        Ok((public, snark.with_vk(params.vk())))
+    }
+
+    /// Generate zero knowledge proof like `prove`, on the backend `B` with parameters from `setup_on`.
+    pub fn prove_on<B: Backend<Fr = Z::Fr>>(params: &ZkSetup<B>, g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, f: &BigIntMatrix) -> Result<(QpProofPublic<Z::Fr, L>, Snark<B>), ParamsError> {
+        params.check(&Self::circuit_name())?;
+
+        // the field modulus, one more than its largest element
+        let bigint_mod = BigInt::from_str(&(-Num::<Z::Fr>::ONE).to_string()).unwrap() + 1;
         let bigint_s: Vec<BigInt> = s.iter().map(|x| BigInt::from_str(&x.to_string()).unwrap()).collect(); 
         let bigint_t: Vec<BigInt> = t.iter().map(|x| BigInt::from_str(&x.to_string()).unwrap()).collect();
         let bigint_result = reduce(&quadratic_result(&bigint_s, &bigint_t, &f), &bigint_mod);
-        let f_st = Num::<Fr>::from_str(&bigint_result.to_string()).unwrap();
-        println!("bigint_f(s, t): {}", bigint_result);
-        println!("f(s, t): {}", f_st);
+        let f_st = Num::<Z::Fr>::from_str(&bigint_result.to_string()).ok().unwrap();
+
+        let (qp_proof_public, qp_proof_secret) = Self::statement(g1, h1, s, t, f_st);
+        let (inputs, proof) = B::prove(&params.params, &qp_proof_public, &qp_proof_secret, Self::circuit);
+        Ok((qp_proof_public, Snark { inputs, proof }))
+    }
+
+    /// The public statement and the witness of a proof for the evaluation `f_st` of the function, with a fresh
+    /// randomness `r`.
+    fn statement(g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, f_st: Num<Z::Fr>) -> (QpProofPublic<Z::Fr, L>, QpProofSecret<Z::Fr, L>) {
+        let jubjub_params = Z::jubjub_params();
+        let r: Num<Z::Fr> = thread_rng().gen();
 
         let c1 = g1.mul(f_st.to_other_reduced(), &jubjub_params)
             .add(&h1.mul(r.to_other_reduced(), &jubjub_params), &jubjub_params);
@@ -153,13 +214,174 @@ impl<const L: usize> ZkQp<L> {
             s: s.clone(),
             t: t.clone()
         };
+        (qp_proof_public, qp_proof_secret)
+    }
+
+    /// Verify a proof for the public statement `public` with the verifying key `vk` of the parameters the proof was
+    /// generated with: the public inputs of the proof must be the points `g1`, `h1`, `c1`, `c2`, `c3` and `c4` of
+    /// the statement. The key shipped with the proof is not trusted: a prover controls it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let params = ZkQp::<N>::setup();
+    /// let (public, snark) = ZkQp::<N>::prove_with(&params, &g1, &h1, &s, &t, &bigint_f).unwrap();
+    /// assert_eq!(ZkQp::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+    /// ```
+    pub fn verify_with(vk: &VK<Z::E>, public: &QpProofPublic<Z::Fr, L>, snark: &SnarkInfo<Z::E>) -> Result<(), VerifyError> {
+        verify_points::<Groth16<Z::E>>(vk, &snark.inputs, &snark.proof, &public.points())
+    }
+
+    /// Verify a proof generated by `prove_on` like `verify_with`, with the verifying key `vk` of the backend `B`.
+    pub fn verify_on<B: Backend<Fr = Z::Fr>>(vk: &B::Vk, public: &QpProofPublic<Z::Fr, L>, snark: &Snark<B>) -> Result<(), VerifyError> {
+        verify_points::<B>(vk, &snark.inputs, &snark.proof, &public.points())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QpFunctionPublic<Fr: PrimeField, const L: usize> {
+    pub statement: QpProofPublic<Fr, L>,
+    pub commitment: Num<Fr>
+}
+
+#[derive(Clone, Signal)]
+#[Value = "QpFunctionPublic<C::Fr, L>"]
+pub struct CqpFunctionPublic<C: CS, const L: usize> {
+    pub statement: CqpProofPublic<C, L>,
+    pub commitment: CNum<C>
+}
 
-        let bellman_params = setup::<E, _, _, _>(ZkQp::<L>::circuit);
-        let (inputs, snark_proof) = prover::prove(&bellman_params, &qp_proof_public, &qp_proof_secret, ZkQp::<L>::circuit);
-        SnarkInfo::<E> {
-            inputs,
-            proof: snark_proof,
-            vk: bellman_params.get_vk()
+#[derive(Clone, Debug)]
+pub struct QpFunctionSecret<Fr: PrimeField, const L: usize> {
+    pub statement: QpProofSecret<Fr, L>,
+    pub blinding: Num<Fr>,
+    pub f: SizedVec<SizedVec<Num<Fr>, L>, L>
+}
+
+#[derive(Clone, Signal)]
+#[Value = "QpFunctionSecret<C::Fr, L>"]
+pub struct CqpFunctionSecret<C: CS, const L: usize> {
+    pub statement: CqpProofSecret<C, L>,
+    pub blinding: CNum<C>,
+    pub f: SizedVec<SizedVec<CNum<C>, L>, L>
+}
+
+/// A public statement of `ZkQpFunction` together with its proof on the engine `Z`.
+type QpFunctionProof<const L: usize, Z> = (QpFunctionPublic<<Z as Zk>::Fr, L>, SnarkInfo<<Z as Zk>::E>);
+
+/// Zero knowledge proof for quadratic polynomial functional encryption like `ZkQp`, where in addition `f(s, t)` is
+/// proven to be the evaluation of the `L x L` matrix `F` committed in a public function commitment, so that every
+/// issued key can be audited against the functions the data owner approved.
+///
+/// # Examples
+///
+/// ```ignore
+/// let opening = FunctionOpening::matrix(&bigint_f);
+/// let params = ZkQpFunction::<N>::setup();
+/// let (public, snark) = ZkQpFunction::<N>::prove_with(&params, &g1, &h1, &s, &t, &opening).unwrap();
+/// assert_eq!(public.commitment, opening.commitment());
+/// assert_eq!(ZkQpFunction::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+/// ```
+pub struct ZkQpFunction<const L: usize, Z: Zk = Bn256>(PhantomData<Z>);
+
+impl<const L: usize, Z: Zk> ZkQpFunction<L, Z> {
+
+    fn circuit<C: CS<Fr = Z::Fr>>(public: CqpFunctionPublic<C, L>, secret: CqpFunctionSecret<C, L>) {
+        let cs = secret.get_cs();
+
+        let f: Vec<CNum<C>> = secret.f.iter().flat_map(|row| row.iter().cloned()).collect();
+        let commitment = c_commitment(&secret.blinding, &f);
+        commitment.assert_eq(&public.commitment);
+
+        let mut f_st = CNum::<C>::from_const(cs, &Num::<Z::Fr>::ZERO);
+        for i in 0..L {
+            let mut row = CNum::<C>::from_const(cs, &Num::<Z::Fr>::ZERO);
+            for j in 0..L {
+                row += &secret.f[i][j] * &secret.statement.t[j];
+            }
+            f_st += &secret.statement.s[i] * &row;
+        }
+        f_st.assert_eq(&secret.statement.f_st);
+
+        ZkQp::<L, Z>::circuit(public.statement, secret.statement);
+    }
+
+    /// The id of the circuit for dimension `L`, e.g. `qp-function/3`, recorded in its parameters and exported
+    /// bundles.
+    pub fn circuit_name() -> String {
+        format!("qp-function/{}", L)
+    }
+
+    /// The size of the circuit for dimension `L`, computed without a setup.
+    pub fn cost() -> CircuitCost {
+        circuit_cost(Self::circuit)
+    }
+
+    /// Run the trusted setup of the circuit for dimension `L`, once for all proofs generated with `prove_with`.
+    pub fn setup() -> ZkParams<Z::E> {
+        Self::setup_on(&()).unwrap()
+    }
+
+    /// Run the setup of the circuit for dimension `L` on the backend `B` with its reference string `srs`, `&()` for
+    /// Groth16, once for all proofs generated with `prove_on`.
+    pub fn setup_on<B: Backend<Fr = Z::Fr>>(srs: &B::Srs) -> Result<ZkSetup<B>, ParamsError> {
+        Ok(ZkSetup::new(Self::circuit_name(), B::setup(srs, Self::circuit)?))
+    }
+
+    /// Generate zero knowledge proof for the matrix `F` committed in `opening`, with a fresh setup.
+    pub fn prove(g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> QpFunctionProof<L, Z> {
+        Self::prove_with(&Self::setup(), g1, h1, s, t, opening).unwrap()
+    }
+
+    /// Generate zero knowledge proof like `prove`, with parameters from `setup`. The opening must commit to `L * L`
+    /// entries.
+    pub fn prove_with(params: &ZkParams<Z::E>, g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> Result<QpFunctionProof<L, Z>, ProveError> {
+        let (public, snark) = Self::prove_on(params, g1, h1, s, t, opening)?;
+        Ok((public, snark.with_vk(params.vk())))
+    }
+
+    /// Generate zero knowledge proof like `prove`, on the backend `B` with parameters from `setup_on`.
+    pub fn prove_on<B: Backend<Fr = Z::Fr>>(params: &ZkSetup<B>, g1: &EdwardsPoint<Z::Fr>, h1: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, t: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> Result<(QpFunctionPublic<Z::Fr, L>, Snark<B>), ProveError> {
+        params.check(&Self::circuit_name())?;
+        if opening.function.len() != L * L {
+            return Err(ProveError::FunctionLength { expected: L * L, actual: opening.function.len() });
         }
+        let f: SizedVec<SizedVec<Num<Z::Fr>, L>, L> = opening.function.chunks(L)
+            .map(|row| row.iter().cloned().collect())
+            .collect();
+        let mut f_st = Num::<Z::Fr>::ZERO;
+        for i in 0..L {
+            for j in 0..L {
+                f_st += f[i][j] * s[i] * t[j];
+            }
+        }
+
+        let (statement, secret) = ZkQp::<L, Z>::statement(g1, h1, s, t, f_st);
+        let public = QpFunctionPublic { statement, commitment: opening.commitment() };
+        let secret = QpFunctionSecret { statement: secret, blinding: opening.blinding, f };
+
+        let (inputs, proof) = B::prove(&params.params, &public, &secret, Self::circuit);
+        Ok((public, Snark { inputs, proof }))
+    }
+
+    /// Verify a proof for the public statement `public` with the verifying key `vk` of the parameters the proof was
+    /// generated with: the public inputs of the proof must be the points of `public.statement` as for
+    /// `ZkQp::verify_with`, followed by the function commitment.
+    pub fn verify_with(vk: &VK<Z::E>, public: &QpFunctionPublic<Z::Fr, L>, snark: &SnarkInfo<Z::E>) -> Result<(), VerifyError> {
+        verify_inputs::<Groth16<Z::E>>(vk, &snark.inputs, &snark.proof, &Self::inputs(public))
+    }
+
+    /// Verify a proof generated by `prove_on` like `verify_with`, with the verifying key `vk` of the backend `B`.
+    pub fn verify_on<B: Backend<Fr = Z::Fr>>(vk: &B::Vk, public: &QpFunctionPublic<Z::Fr, L>, snark: &Snark<B>) -> Result<(), VerifyError> {
+        verify_inputs::<B>(vk, &snark.inputs, &snark.proof, &Self::inputs(public))
+    }
+
+    /// The named public inputs of a proof for `public`.
+    fn inputs(public: &QpFunctionPublic<Z::Fr, L>) -> Vec<(String, Vec<Num<Z::Fr>>)> {
+        let mut inputs: Vec<(String, Vec<Num<Z::Fr>>)> = public.statement.points().into_iter()
+            .map(|(name, p)| (name, vec![p.x, p.y]))
+            .collect();
+        inputs.push(("commitment".to_string(), vec![public.commitment]));
+        inputs
     }
 }
@@ -0,0 +1,418 @@
+use miracl_core::bls12381::pair;
+use miracl_core::hash512::HASH512;
+use num_bigint::{BigInt, Sign};
+use num_traits::{Num, Zero};
+
+use crate::define::{BigNum, G1, G2, Gt, MB, MODULUS};
+use crate::dmcfe_ip::{Dmcfe};
+use crate::simple_ip::{Sip, SipMpk};
+use crate::utils::{reduce};
+use crate::utils::rand_utils::{RandUtilsRng, Sample};
+
+use super::VerifyError;
+
+
+/// A group of BLS12-381, `G1` or `G2`, in which the sigma protocols are run. Scalars are integers modulo the group
+/// order `MODULUS`.
+pub trait SigmaGroup: Clone {
+    fn generator() -> Self;
+    fn mul_scalar(&self, k: &BigInt) -> Self;
+    fn add_point(&self, other: &Self) -> Self;
+    fn sub_point(&self, other: &Self) -> Self;
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+impl SigmaGroup for G1 {
+    fn generator() -> Self {
+        G1::generator()
+    }
+
+    fn mul_scalar(&self, k: &BigInt) -> Self {
+        self.mul(&to_bignum(k))
+    }
+
+    fn add_point(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        res.add(other);
+        res
+    }
+
+    fn sub_point(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        res.sub(other);
+        res
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut b = vec![0u8; MB + 1];
+        self.tobytes(&mut b, true);
+        b
+    }
+}
+
+impl SigmaGroup for G2 {
+    fn generator() -> Self {
+        G2::generator()
+    }
+
+    fn mul_scalar(&self, k: &BigInt) -> Self {
+        self.mul(&to_bignum(k))
+    }
+
+    fn add_point(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        res.add(other);
+        res
+    }
+
+    fn sub_point(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        res.sub(other);
+        res
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut b = vec![0u8; 2 * MB + 1];
+        self.tobytes(&mut b, true);
+        b
+    }
+}
+
+/// The target group, written additively like the source groups: `add_point` multiplies and `mul_scalar`
+/// exponentiates. Its generator is `e(g2, g1)`.
+impl SigmaGroup for Gt {
+    fn generator() -> Self {
+        pair::fexp(&pair::ate(&G2::generator(), &G1::generator()))
+    }
+
+    fn mul_scalar(&self, k: &BigInt) -> Self {
+        self.pow(&to_bignum(k))
+    }
+
+    fn add_point(&self, other: &Self) -> Self {
+        let mut res = *self;
+        res.mul(other);
+        res
+    }
+
+    fn sub_point(&self, other: &Self) -> Self {
+        let mut inv = *other;
+        inv.inverse();
+        self.add_point(&inv)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut b = vec![0u8; 12 * MB];
+        let mut p = *self;
+        p.tobytes(&mut b);
+        b
+    }
+}
+
+/// One equation `lhs = sum_j x_(index_j) * base_j` of a linear relation, written additively.
+#[derive(Clone)]
+pub struct Equation<G: SigmaGroup> {
+    pub lhs: G,
+    pub terms: Vec<(usize, G)>,
+}
+
+/// A statement that the prover knows `witnesses` exponents `x_0, x_1, ...` satisfying every equation. Knowledge of
+/// a discrete logarithm and equality of discrete logarithms are the relations with one witness and one or two
+/// equations; see `prove_dlog` and `prove_dlog_equality`.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Pedersen commitment c = x * g + r * h
+/// let relation = LinearRelation::new(2).equation(c, vec![(0, g), (1, h)]);
+/// let proof = relation.prove(&[x, r], "commitment");
+/// assert_eq!(relation.verify(&proof, "commitment"), Ok(()));
+/// ```
+#[derive(Clone)]
+pub struct LinearRelation<G: SigmaGroup> {
+    pub witnesses: usize,
+    pub equations: Vec<Equation<G>>,
+}
+
+/// A Fiat-Shamir-transformed Schnorr proof: the challenge `e` and one response per witness. Serialized, it takes
+/// 32 bytes per scalar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigmaProof {
+    pub e: BigInt,
+    pub z: Vec<BigInt>,
+}
+
+impl<G: SigmaGroup> LinearRelation<G> {
+    pub fn new(witnesses: usize) -> Self {
+        Self { witnesses, equations: Vec::new() }
+    }
+
+    /// Add the equation `lhs = sum_j x_(index_j) * base_j`.
+    pub fn equation(mut self, lhs: G, terms: Vec<(usize, G)>) -> Self {
+        if let Some((j, _)) = terms.iter().find(|(j, _)| *j >= self.witnesses) {
+            panic!("Malformed input: witness index ({}), witnesses ({})", j, self.witnesses);
+        }
+        self.equations.push(Equation { lhs, terms });
+        self
+    }
+
+    /// Prove knowledge of `witness`. `label` separates the proofs of different protocols and contexts: a proof only
+    /// verifies with the label it was generated with.
+    pub fn prove(&self, witness: &[BigInt], label: &str) -> SigmaProof {
+        if witness.len() != self.witnesses {
+            panic!("Malformed input: witness.len ({}), witnesses ({})", witness.len(), self.witnesses);
+        }
+        if self.equations.iter().any(|eq| eq.lhs.to_bytes() != self.evaluate(eq, witness).to_bytes()) {
+            panic!("Malformed input: the witness does not satisfy the relation");
+        }
+        let mut rng = RandUtilsRng::new();
+        let k: Vec<BigInt> = (0..self.witnesses).map(|_| rng.sample(&MODULUS)).collect();
+        let mut transcript = self.transcript(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&self.evaluate(eq, &k));
+        }
+        let e = transcript.challenge();
+        SigmaProof {
+            z: k.iter().zip(witness.iter()).map(|(kj, xj)| reduce(&(kj + &e * xj), &MODULUS)).collect(),
+            e,
+        }
+    }
+
+    pub fn verify(&self, proof: &SigmaProof, label: &str) -> Result<(), VerifyError> {
+        if proof.z.len() != self.witnesses {
+            return Err(VerifyError::InputLength { expected: self.witnesses, actual: proof.z.len() });
+        }
+        let mut transcript = self.transcript(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&self.evaluate(eq, &proof.z).sub_point(&eq.lhs.mul_scalar(&proof.e)));
+        }
+        if transcript.challenge() != proof.e {
+            return Err(VerifyError::InvalidProof);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, eq: &Equation<G>, x: &[BigInt]) -> G {
+        eq.terms
+            .iter()
+            .fold(G::generator().mul_scalar(&BigInt::zero()), |acc, (j, base)| acc.add_point(&base.mul_scalar(&x[*j])))
+    }
+
+    /// The transcript of the statement: the label, and the left-hand side and bases of every equation.
+    fn transcript(&self, label: &str) -> Transcript {
+        let mut transcript = Transcript::new(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&eq.lhs);
+            transcript.append_bytes(&(eq.terms.len() as u64).to_be_bytes());
+            for (j, base) in eq.terms.iter() {
+                transcript.append_bytes(&(*j as u64).to_be_bytes());
+                transcript.append_point(base);
+            }
+        }
+        transcript
+    }
+}
+
+impl SigmaProof {
+    /// Serialize the proof as fixed-width 32-byte big-endian scalars, the challenge first.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        std::iter::once(&self.e).chain(self.z.iter()).flat_map(|k| {
+            let b = k.to_bytes_be().1;
+            let mut padded = vec![0u8; 32 - b.len()];
+            padded.extend_from_slice(&b);
+            padded
+        }).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(32) {
+            return None;
+        }
+        let mut scalars: Vec<BigInt> = bytes.chunks(32).map(|c| BigInt::from_bytes_be(Sign::Plus, c)).collect();
+        if scalars.iter().any(|k| k >= &*MODULUS) {
+            return None;
+        }
+        let e = scalars.remove(0);
+        Some(Self { e, z: scalars })
+    }
+}
+
+/// One equation `lhs = prod_j e(k_(index_j), base_j)` of a pairing relation.
+#[derive(Clone)]
+pub struct PairingEquation {
+    pub lhs: Gt,
+    pub terms: Vec<(usize, G1)>,
+}
+
+/// A statement that the prover knows `witnesses` points `k_0, k_1, ...` of `G2` satisfying every equation. Since
+/// the pairing is linear in its first argument, this is a sigma protocol like `LinearRelation`, with points of `G2`
+/// instead of exponents as the witness; it proves knowledge of a functional evaluation key of a pairing-based
+/// scheme without revealing it.
+#[derive(Clone)]
+pub struct PairingRelation {
+    pub witnesses: usize,
+    pub equations: Vec<PairingEquation>,
+}
+
+/// A Fiat-Shamir-transformed proof for a `PairingRelation`: the challenge `e` and one response in `G2` per witness.
+#[derive(Debug, Clone)]
+pub struct PairingProof {
+    pub e: BigInt,
+    pub s: Vec<G2>,
+}
+
+impl PairingRelation {
+    pub fn new(witnesses: usize) -> Self {
+        Self { witnesses, equations: Vec::new() }
+    }
+
+    /// Add the equation `lhs = prod_j e(k_(index_j), base_j)`.
+    pub fn equation(mut self, lhs: Gt, terms: Vec<(usize, G1)>) -> Self {
+        if let Some((j, _)) = terms.iter().find(|(j, _)| *j >= self.witnesses) {
+            panic!("Malformed input: witness index ({}), witnesses ({})", j, self.witnesses);
+        }
+        self.equations.push(PairingEquation { lhs, terms });
+        self
+    }
+
+    /// Prove knowledge of `witness`, see `LinearRelation::prove`.
+    pub fn prove(&self, witness: &[G2], label: &str) -> PairingProof {
+        if witness.len() != self.witnesses {
+            panic!("Malformed input: witness.len ({}), witnesses ({})", witness.len(), self.witnesses);
+        }
+        if self.equations.iter().any(|eq| !eq.lhs.equals(&self.evaluate(eq, witness))) {
+            panic!("Malformed input: the witness does not satisfy the relation");
+        }
+        let mut rng = RandUtilsRng::new();
+        let k: Vec<G2> = (0..self.witnesses).map(|_| G2::generator().mul_scalar(&rng.sample(&MODULUS))).collect();
+        let mut transcript = self.transcript(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&self.evaluate(eq, &k));
+        }
+        let e = transcript.challenge();
+        PairingProof {
+            s: k.iter().zip(witness.iter()).map(|(kj, wj)| kj.add_point(&wj.mul_scalar(&e))).collect(),
+            e,
+        }
+    }
+
+    pub fn verify(&self, proof: &PairingProof, label: &str) -> Result<(), VerifyError> {
+        if proof.s.len() != self.witnesses {
+            return Err(VerifyError::InputLength { expected: self.witnesses, actual: proof.s.len() });
+        }
+        let mut transcript = self.transcript(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&self.evaluate(eq, &proof.s).sub_point(&eq.lhs.mul_scalar(&proof.e)));
+        }
+        if transcript.challenge() != proof.e {
+            return Err(VerifyError::InvalidProof);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, eq: &PairingEquation, k: &[G2]) -> Gt {
+        let mut out = Gt::new();
+        out.one();
+        for (j, base) in eq.terms.iter() {
+            out.mul(&pair::ate(&k[*j], base));
+        }
+        pair::fexp(&out)
+    }
+
+    fn transcript(&self, label: &str) -> Transcript {
+        let mut transcript = Transcript::new(label);
+        for eq in self.equations.iter() {
+            transcript.append_point(&eq.lhs);
+            transcript.append_bytes(&(eq.terms.len() as u64).to_be_bytes());
+            for (j, base) in eq.terms.iter() {
+                transcript.append_bytes(&(*j as u64).to_be_bytes());
+                transcript.append_point(base);
+            }
+        }
+        transcript
+    }
+}
+
+/// Prove knowledge of `x` with `y = x * base`.
+pub fn prove_dlog<G: SigmaGroup>(base: &G, x: &BigInt, label: &str) -> SigmaProof {
+    LinearRelation::new(1).equation(base.mul_scalar(x), vec![(0, base.clone())]).prove(std::slice::from_ref(x), label)
+}
+
+pub fn verify_dlog<G: SigmaGroup>(base: &G, y: &G, proof: &SigmaProof, label: &str) -> Result<(), VerifyError> {
+    LinearRelation::new(1).equation(y.clone(), vec![(0, base.clone())]).verify(proof, label)
+}
+
+/// Prove knowledge of `x` with `y1 = x * base1` and `y2 = x * base2`.
+pub fn prove_dlog_equality<G: SigmaGroup>(base1: &G, base2: &G, x: &BigInt, label: &str) -> SigmaProof {
+    dlog_equality(base1, &base1.mul_scalar(x), base2, &base2.mul_scalar(x)).prove(std::slice::from_ref(x), label)
+}
+
+pub fn verify_dlog_equality<G: SigmaGroup>(base1: &G, y1: &G, base2: &G, y2: &G, proof: &SigmaProof, label: &str) -> Result<(), VerifyError> {
+    dlog_equality(base1, y1, base2, y2).verify(proof, label)
+}
+
+fn dlog_equality<G: SigmaGroup>(base1: &G, y1: &G, base2: &G, y2: &G) -> LinearRelation<G> {
+    LinearRelation::new(1)
+        .equation(y1.clone(), vec![(0, base1.clone())])
+        .equation(y2.clone(), vec![(0, base2.clone())])
+}
+
+/// Prove that the master public key of `sip` was formed correctly, i.e. that the key authority knows every `s_i`
+/// with `v_i = g^(s_i)`.
+pub fn prove_sip_mpk<const L: usize>(sip: &Sip<L>) -> SigmaProof {
+    let s: Vec<BigInt> = sip.msk().s.iter().map(to_bigint).collect();
+    sip_mpk_relation(sip.mpk()).prove(&s, "sip mpk")
+}
+
+pub fn verify_sip_mpk<const L: usize>(mpk: &SipMpk<L>, proof: &SigmaProof) -> Result<(), VerifyError> {
+    sip_mpk_relation(mpk).verify(proof, "sip mpk")
+}
+
+fn sip_mpk_relation<const L: usize>(mpk: &SipMpk<L>) -> LinearRelation<G1> {
+    (0..L).fold(LinearRelation::new(L), |relation, i| relation.equation(mpk.v[i].clone(), vec![(i, G1::generator())]))
+}
+
+/// Prove that the public key of `client` was formed correctly, i.e. that the client knows its secret key. The index
+/// of the client is part of the label, so that the proof of one client cannot be replayed for another one.
+pub fn prove_dmcfe_pub_key<const L: usize>(client: &Dmcfe<L>) -> SigmaProof {
+    prove_dlog(&G1::generator(), &to_bigint(&client.client_sec_key), &format!("dmcfe client public key {}", client.index))
+}
+
+pub fn verify_dmcfe_pub_key(index: usize, pub_key: &G1, proof: &SigmaProof) -> Result<(), VerifyError> {
+    verify_dlog(&G1::generator(), pub_key, proof, &format!("dmcfe client public key {}", index))
+}
+
+fn to_bignum(k: &BigInt) -> BigNum {
+    BigNum::fromstring(reduce(k, &MODULUS).to_str_radix(16))
+}
+
+pub(crate) fn to_bigint(k: &BigNum) -> BigInt {
+    BigInt::from_str_radix(&k.tostring(), 16).unwrap()
+}
+
+/// Fiat-Shamir transcript of a sigma protocol.
+pub(crate) struct Transcript(HASH512);
+
+impl Transcript {
+    pub(crate) fn new(label: &str) -> Self {
+        let mut t = Transcript(HASH512::new());
+        t.append_bytes(label.as_bytes());
+        t
+    }
+
+    /// Append length-prefixed bytes.
+    pub(crate) fn append_bytes(&mut self, b: &[u8]) {
+        self.0.process_array(&(b.len() as u64).to_be_bytes());
+        self.0.process_array(b);
+    }
+
+    pub(crate) fn append_point<G: SigmaGroup>(&mut self, p: &G) {
+        self.0.process_array(&p.to_bytes());
+    }
+
+    /// The challenge, a 512-bit hash reduced modulo the group order.
+    pub(crate) fn challenge(self) -> BigInt {
+        let mut hash = self.0;
+        BigInt::from_bytes_be(Sign::Plus, &hash.hash()) % &*MODULUS
+    }
+}
@@ -1,8 +1,7 @@
 use fawkes_crypto::{
     backend::bellman_groth16::{
-        prover,
         engines::{Bn256},
-        setup::setup
+        verifier::VK
     },
     circuit::bool::CBool,
     circuit::cs::{CS, RCS},
@@ -11,17 +10,17 @@ use fawkes_crypto::{
     circuit::ecc::*,
     core::signal::Signal,
     core::sizedvec::SizedVec,
-    engines::bn256::{JubJubBN256},
     native::ecc::*,
     rand::{thread_rng, Rng},
     ff_uint::{Num, PrimeField},
 };
 
-use super::SnarkInfo;
+use std::marker::PhantomData;
+
+use super::{CircuitCost, ParamsError, ProveError, SnarkInfo, VerifyError, Zk, ZkParams, ZkSetup, circuit_cost, verify_inputs, verify_points};
+use super::backend::{Backend, Groth16, Snark};
+use super::commitment::{FunctionOpening, c_commitment};
 
-type Fr = fawkes_crypto::engines::bn256::Fr;
-type E = Bn256;
-type JjParams = JubJubBN256;
 
 
 #[derive(Clone, Debug)]
@@ -33,6 +32,20 @@ pub struct SipProofPublic<Fr: PrimeField, const L: usize> {
     pub v: SizedVec<EdwardsPoint<Fr>, L>
 }
 
+impl<Fr: PrimeField, const L: usize> SipProofPublic<Fr, L> {
+    /// The named points of the statement, in the order of the public inputs of the proof.
+    fn points(&self) -> Vec<(String, EdwardsPoint<Fr>)> {
+        let mut points = vec![
+            ("g".to_string(), self.g),
+            ("h".to_string(), self.h),
+            ("c1".to_string(), self.c1),
+            ("c2".to_string(), self.c2),
+        ];
+        points.extend(self.v.iter().enumerate().map(|(i, vi)| (format!("v[{}]", i), *vi)));
+        points
+    }
+}
+
 #[derive(Clone, Signal)]
 #[Value = "SipProofPublic<C::Fr, L>"]
 pub struct CSipProofPublic<C: CS, const L: usize> {
@@ -58,16 +71,19 @@ pub struct CSipProofSecret<C: CS, const L: usize> {
     pub y: SizedVec<CNum<C>, L>
 }
 
+/// A public statement together with its proof on the engine `Z`.
+type SipProof<const L: usize, Z> = (SipProofPublic<<Z as Zk>::Fr, L>, SnarkInfo<<Z as Zk>::E>);
+
 /// Zero knowledge proof for the simple inner product functional encryption.
-pub struct ZkSip<const L: usize>;
+pub struct ZkSip<const L: usize, Z: Zk = Bn256>(PhantomData<Z>);
 
-impl<const L: usize> ZkSip<L> {
+impl<const L: usize, Z: Zk> ZkSip<L, Z> {
 
-    fn circuit<C: CS<Fr = Fr>>(public: CSipProofPublic<C, L>, secret: CSipProofSecret<C, L>) {
-        let jubjub_params = JjParams::new();
+    fn circuit<C: CS<Fr = Z::Fr>>(public: CSipProofPublic<C, L>, secret: CSipProofSecret<C, L>) {
+        let jubjub_params = Z::jubjub_params();
         let cs = secret.get_cs();
 
-        let mut ys = CNum::<C>::from_const(cs, &Num::<Fr>::ZERO); 
+        let mut ys = CNum::<C>::from_const(cs, &Num::<Z::Fr>::ZERO); 
         for i in 0..L {
             let yi = secret.y[i].clone();
             ys += &yi * &secret.s[i];
@@ -104,11 +120,57 @@ impl<const L: usize> ZkSip<L> {
     /// let y: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
     /// let snark = ZkSip::<N>::generate(&g, &h, &s, &y);
     /// ```
-    pub fn generate(g: &EdwardsPoint<Fr>, h: &EdwardsPoint<Fr>, s: &SizedVec<Num<Fr>, L>, y: &SizedVec<Num<Fr>, L>) -> SnarkInfo<E> {
-        let jubjub_params = JjParams::new();
+    pub fn generate(g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, y: &SizedVec<Num<Z::Fr>, L>) -> SnarkInfo<Z::E> {
+        Self::prove(g, h, s, y).1
+    }
+
+    /// Generate zero knowledge proof like `generate`, and return it along with the public statement that
+    /// `verify_with` checks it against.
+    pub fn prove(g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, y: &SizedVec<Num<Z::Fr>, L>) -> (SipProofPublic<Z::Fr, L>, SnarkInfo<Z::E>) {
+        Self::prove_with(&Self::setup(), g, h, s, y).unwrap()
+    }
+
+    /// Run the trusted setup of the circuit for dimension `L`, once for all proofs generated with `prove_with`.
+    pub fn setup() -> ZkParams<Z::E> {
+        Self::setup_on(&()).unwrap()
+    }
+
+    /// Run the setup of the circuit for dimension `L` on the backend `B` with its reference string `srs`, `&()` for
+    /// Groth16, once for all proofs generated with `prove_on`.
+    pub fn setup_on<B: Backend<Fr = Z::Fr>>(srs: &B::Srs) -> Result<ZkSetup<B>, ParamsError> {
+        Ok(ZkSetup::new(Self::circuit_name(), B::setup(srs, Self::circuit)?))
+    }
+
+    /// The id of the circuit for dimension `L`, e.g. `sip/3`, recorded in its parameters and exported bundles.
+    pub fn circuit_name() -> String {
+        format!("sip/{}", L)
+    }
+
+    /// The size of the circuit for dimension `L`, computed without a setup.
+    pub fn cost() -> CircuitCost {
+        circuit_cost(Self::circuit)
+    }
+
+    /// Generate zero knowledge proof like `prove`, with parameters from `setup`.
+    pub fn prove_with(params: &ZkParams<Z::E>, g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, y: &SizedVec<Num<Z::Fr>, L>) -> Result<SipProof<L, Z>, ParamsError> {
+        let (public, snark) = Self::prove_on(params, g, h, s, y)?;
+        Ok((public, snark.with_vk(params.vk())))
+    }
+
+    /// Generate zero knowledge proof like `prove`, on the backend `B` with parameters from `setup_on`.
+    pub fn prove_on<B: Backend<Fr = Z::Fr>>(params: &ZkSetup<B>, g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, y: &SizedVec<Num<Z::Fr>, L>) -> Result<(SipProofPublic<Z::Fr, L>, Snark<B>), ParamsError> {
+        params.check(&Self::circuit_name())?;
+        let (sip_proof_public, sip_proof_secret) = Self::statement(g, h, s, y);
+        let (inputs, proof) = B::prove(&params.params, &sip_proof_public, &sip_proof_secret, Self::circuit);
+        Ok((sip_proof_public, Snark { inputs, proof }))
+    }
+
+    /// The public statement and the witness of a proof, with a fresh randomness `r`.
+    fn statement(g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, y: &SizedVec<Num<Z::Fr>, L>) -> (SipProofPublic<Z::Fr, L>, SipProofSecret<Z::Fr, L>) {
+        let jubjub_params = Z::jubjub_params();
         let mut rng = thread_rng();
 
-        let r: Num<Fr> = rng.gen();
+        let r: Num<Z::Fr> = rng.gen();
 
         //let bigint_mod = BigInt::from_str(&Fr::MODULUS.to_string()).unwrap();
         //let bigint_s: Vec<BigInt> = s.iter().map(|x| BigInt::from_str(&x.to_string()).unwrap()).collect(); 
@@ -118,7 +180,7 @@ impl<const L: usize> ZkSip<L> {
         //println!("bigint_f(s, t): {}", bigint_result);
         //println!("f(s, t): {}", f_st);
 
-        let mut ys = Num::<Fr>::ZERO; 
+        let mut ys = Num::<Z::Fr>::ZERO; 
         for i in 0..L {
             ys += y[i] * s[i];
         }
@@ -142,13 +204,152 @@ impl<const L: usize> ZkSip<L> {
             s: s.clone(),
             y: y.clone()
         };
+        (sip_proof_public, sip_proof_secret)
+    }
+
+    /// Verify a proof for the public statement `public` with the verifying key `vk` of the parameters the proof was
+    /// generated with: the public inputs of the proof must be the points `g`, `h`, `c1`, `c2` and `v` of the
+    /// statement. The key shipped with the proof is not trusted: a prover controls it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let params = ZkSip::<N>::setup();
+    /// let (public, snark) = ZkSip::<N>::prove_with(&params, &g, &h, &s, &y).unwrap();
+    /// assert_eq!(ZkSip::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+    /// ```
+    pub fn verify_with(vk: &VK<Z::E>, public: &SipProofPublic<Z::Fr, L>, snark: &SnarkInfo<Z::E>) -> Result<(), VerifyError> {
+        verify_points::<Groth16<Z::E>>(vk, &snark.inputs, &snark.proof, &public.points())
+    }
+
+    /// Verify a proof generated by `prove_on` like `verify_with`, with the verifying key `vk` of the backend `B`.
+    pub fn verify_on<B: Backend<Fr = Z::Fr>>(vk: &B::Vk, public: &SipProofPublic<Z::Fr, L>, snark: &Snark<B>) -> Result<(), VerifyError> {
+        verify_points::<B>(vk, &snark.inputs, &snark.proof, &public.points())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SipFunctionPublic<Fr: PrimeField, const L: usize> {
+    pub statement: SipProofPublic<Fr, L>,
+    pub commitment: Num<Fr>
+}
 
-        let bellman_params = setup::<E, _, _, _>(ZkSip::<L>::circuit);
-        let (inputs, snark_proof) = prover::prove(&bellman_params, &sip_proof_public, &sip_proof_secret, ZkSip::<L>::circuit);
-        SnarkInfo::<E> {
-            inputs,
-            proof: snark_proof,
-            vk: bellman_params.get_vk()
+#[derive(Clone, Signal)]
+#[Value = "SipFunctionPublic<C::Fr, L>"]
+pub struct CSipFunctionPublic<C: CS, const L: usize> {
+    pub statement: CSipProofPublic<C, L>,
+    pub commitment: CNum<C>
+}
+
+#[derive(Clone, Debug)]
+pub struct SipFunctionSecret<Fr: PrimeField, const L: usize> {
+    pub statement: SipProofSecret<Fr, L>,
+    pub blinding: Num<Fr>
+}
+
+#[derive(Clone, Signal)]
+#[Value = "SipFunctionSecret<C::Fr, L>"]
+pub struct CSipFunctionSecret<C: CS, const L: usize> {
+    pub statement: CSipProofSecret<C, L>,
+    pub blinding: CNum<C>
+}
+
+/// A public statement of `ZkSipFunction` together with its proof on the engine `Z`.
+type SipFunctionProof<const L: usize, Z> = (SipFunctionPublic<<Z as Zk>::Fr, L>, SnarkInfo<<Z as Zk>::E>);
+
+/// Zero knowledge proof for the simple inner product functional encryption like `ZkSip`, where the function vector
+/// `y` is in addition the one committed in a public function commitment, so that every issued key can be audited
+/// against the functions the data owner approved.
+///
+/// # Examples
+///
+/// ```ignore
+/// let opening = FunctionOpening::vector(&y);
+/// let commitment = opening.commitment();
+/// let params = ZkSipFunction::<N>::setup();
+/// let (public, snark) = ZkSipFunction::<N>::prove_with(&params, &g, &h, &s, &opening).unwrap();
+/// assert_eq!(public.commitment, commitment);
+/// assert_eq!(ZkSipFunction::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+/// ```
+pub struct ZkSipFunction<const L: usize, Z: Zk = Bn256>(PhantomData<Z>);
+
+impl<const L: usize, Z: Zk> ZkSipFunction<L, Z> {
+
+    fn circuit<C: CS<Fr = Z::Fr>>(public: CSipFunctionPublic<C, L>, secret: CSipFunctionSecret<C, L>) {
+        let y: Vec<CNum<C>> = secret.statement.y.iter().cloned().collect();
+        let commitment = c_commitment(&secret.blinding, &y);
+        commitment.assert_eq(&public.commitment);
+
+        ZkSip::<L, Z>::circuit(public.statement, secret.statement);
+    }
+
+    /// The id of the circuit for dimension `L`, e.g. `sip-function/3`, recorded in its parameters and exported
+    /// bundles.
+    pub fn circuit_name() -> String {
+        format!("sip-function/{}", L)
+    }
+
+    /// The size of the circuit for dimension `L`, computed without a setup.
+    pub fn cost() -> CircuitCost {
+        circuit_cost(Self::circuit)
+    }
+
+    /// Run the trusted setup of the circuit for dimension `L`, once for all proofs generated with `prove_with`.
+    pub fn setup() -> ZkParams<Z::E> {
+        Self::setup_on(&()).unwrap()
+    }
+
+    /// Run the setup of the circuit for dimension `L` on the backend `B` with its reference string `srs`, `&()` for
+    /// Groth16, once for all proofs generated with `prove_on`.
+    pub fn setup_on<B: Backend<Fr = Z::Fr>>(srs: &B::Srs) -> Result<ZkSetup<B>, ParamsError> {
+        Ok(ZkSetup::new(Self::circuit_name(), B::setup(srs, Self::circuit)?))
+    }
+
+    /// Generate zero knowledge proof for the function vector `y` committed in `opening`, with a fresh setup.
+    pub fn prove(g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> SipFunctionProof<L, Z> {
+        Self::prove_with(&Self::setup(), g, h, s, opening).unwrap()
+    }
+
+    /// Generate zero knowledge proof like `prove`, with parameters from `setup`. The opening must commit to `L`
+    /// entries.
+    pub fn prove_with(params: &ZkParams<Z::E>, g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> Result<SipFunctionProof<L, Z>, ProveError> {
+        let (public, snark) = Self::prove_on(params, g, h, s, opening)?;
+        Ok((public, snark.with_vk(params.vk())))
+    }
+
+    /// Generate zero knowledge proof like `prove`, on the backend `B` with parameters from `setup_on`.
+    pub fn prove_on<B: Backend<Fr = Z::Fr>>(params: &ZkSetup<B>, g: &EdwardsPoint<Z::Fr>, h: &EdwardsPoint<Z::Fr>, s: &SizedVec<Num<Z::Fr>, L>, opening: &FunctionOpening<Z::Fr>) -> Result<(SipFunctionPublic<Z::Fr, L>, Snark<B>), ProveError> {
+        params.check(&Self::circuit_name())?;
+        if opening.function.len() != L {
+            return Err(ProveError::FunctionLength { expected: L, actual: opening.function.len() });
         }
+        let y: SizedVec<Num<Z::Fr>, L> = opening.function.iter().cloned().collect();
+        let (statement, secret) = ZkSip::<L, Z>::statement(g, h, s, &y);
+        let public = SipFunctionPublic { statement, commitment: opening.commitment() };
+        let secret = SipFunctionSecret { statement: secret, blinding: opening.blinding };
+
+        let (inputs, proof) = B::prove(&params.params, &public, &secret, Self::circuit);
+        Ok((public, Snark { inputs, proof }))
+    }
+
+    /// Verify a proof for the public statement `public` with the verifying key `vk` of the parameters the proof was
+    /// generated with: the public inputs of the proof must be the points of `public.statement` as for
+    /// `ZkSip::verify_with`, followed by the function commitment.
+    pub fn verify_with(vk: &VK<Z::E>, public: &SipFunctionPublic<Z::Fr, L>, snark: &SnarkInfo<Z::E>) -> Result<(), VerifyError> {
+        verify_inputs::<Groth16<Z::E>>(vk, &snark.inputs, &snark.proof, &Self::inputs(public))
+    }
+
+    /// Verify a proof generated by `prove_on` like `verify_with`, with the verifying key `vk` of the backend `B`.
+    pub fn verify_on<B: Backend<Fr = Z::Fr>>(vk: &B::Vk, public: &SipFunctionPublic<Z::Fr, L>, snark: &Snark<B>) -> Result<(), VerifyError> {
+        verify_inputs::<B>(vk, &snark.inputs, &snark.proof, &Self::inputs(public))
+    }
+
+    /// The named public inputs of a proof for `public`.
+    fn inputs(public: &SipFunctionPublic<Z::Fr, L>) -> Vec<(String, Vec<Num<Z::Fr>>)> {
+        let mut inputs: Vec<(String, Vec<Num<Z::Fr>>)> = public.statement.points().into_iter()
+            .map(|(name, p)| (name, vec![p.x, p.y]))
+            .collect();
+        inputs.push(("commitment".to_string(), vec![public.commitment]));
+        inputs
     }
 }
@@ -0,0 +1,203 @@
+use num_bigint::{BigInt};
+use num_traits::{One, Zero};
+
+use crate::bounds::{max_abs};
+use crate::define::{BigNum, G1, MODULUS};
+use crate::simple_ip::{SipCipher, SipMpk};
+use crate::utils::{hash_to_g1, reduce};
+use crate::utils::rand_utils::{RandUtilsRng, Sample};
+
+use super::VerifyError;
+use super::sigma::{SigmaGroup, Transcript};
+
+
+/// OR proof that the Pedersen commitment `c = g^b h^rho` commits to a bit `b`, i.e. that `c` or `c - g` is a
+/// multiple of `h`. The challenge of the second branch is the proof challenge minus `e0`.
+#[derive(Debug, Clone)]
+struct BitProof {
+    c: G1,
+    e0: BigInt,
+    z0: BigInt,
+    z1: BigInt,
+}
+
+/// Non-interactive zero knowledge proof that a `SipCipher` is well-formed, i.e. that its encryptor knows `r` and
+/// `x` with `c0 = g^r` and `c_i = g^(x_i) v_i^r`, and that every `x_i` lies in `[-B, B]` for the bound `B` carried
+/// by the ciphertext.
+///
+/// Every `x_i + B` is decomposed into bits with weights `1, 2, ..., 2^(m-2)` and `2B - 2^(m-1) + 1`, so that any sum
+/// of weights lies in `[0, 2B]`. The bits are committed with Pedersen commitments over `g` and a second generator
+/// `h` of unknown discrete logarithm, each with an OR proof that it commits to 0 or 1, and a Schnorr proof for the
+/// linear relations ties the weighted sum of the commitments to `c_i`. All proofs share one Fiat-Shamir challenge.
+/// The proof has `L * m` bit proofs, with `m` the bit length of `2B`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let (cipher, proof) = SipEncryptionProof::prove(sip.mpk(), &x, &BigInt::from(100));
+/// // the server
+/// proof.verify(sip.mpk(), &cipher).unwrap();
+/// let result = sip.decrypt(&cipher, &dk);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SipEncryptionProof<const L: usize> {
+    bits: Vec<Vec<BitProof>>,
+    e: BigInt,
+    z_r: BigInt,
+    z_x: Vec<BigInt>,
+    z_rho: Vec<BigInt>,
+}
+
+impl<const L: usize> SipEncryptionProof<L> {
+    /// Encrypt `x`, whose entries are bounded by `bound` in absolute value, under `mpk`, and prove that the
+    /// ciphertext is well-formed.
+    pub fn prove(mpk: &SipMpk<L>, x: &[BigInt; L], bound: &BigInt) -> (SipCipher<L>, Self) {
+        if max_abs(x) > *bound {
+            panic!("Malformed input: x exceeds the bound ({})", bound);
+        }
+        let mut rng = RandUtilsRng::new();
+        let (g, h) = (G1::generator(), generator_h());
+        let r = rng.sample(&MODULUS);
+        let cipher = mpk.encrypt_with_randomness(x, bound, &to_bignum(&r));
+        let weights = weights(bound);
+
+        let mut transcript = transcript(mpk, &cipher);
+        let mut bit_secrets = Vec::with_capacity(L);
+        let mut rho = Vec::with_capacity(L);
+        for xi in x.iter() {
+            let mut secrets = Vec::with_capacity(weights.len());
+            for b in decompose(&(xi + bound), &weights) {
+                let rho_j = rng.sample(&MODULUS);
+                let c = g.mul_scalar(&BigInt::from(b as u8)).add_point(&h.mul_scalar(&rho_j));
+                // the real branch b starts with h^k, the other one is simulated
+                let (k, e_sim, z_sim) = (rng.sample(&MODULUS), rng.sample(&MODULUS), rng.sample(&MODULUS));
+                let y_sim = if b { c.clone() } else { c.sub_point(&g) };
+                let a_real = h.mul_scalar(&k);
+                let a_sim = h.mul_scalar(&z_sim).sub_point(&y_sim.mul_scalar(&e_sim));
+                let (a0, a1) = if b { (a_sim, a_real) } else { (a_real, a_sim) };
+                for p in [&c, &a0, &a1].iter() {
+                    transcript.append_point(*p);
+                }
+                secrets.push((b, c, rho_j, k, e_sim, z_sim));
+            }
+            rho.push(secrets.iter().zip(weights.iter()).fold(BigInt::zero(), |acc, (s, w)| acc + w * &s.2) % &*MODULUS);
+            bit_secrets.push(secrets);
+        }
+
+        let k_r = rng.sample(&MODULUS);
+        let k_x: Vec<BigInt> = (0..L).map(|_| rng.sample(&MODULUS)).collect();
+        let k_rho: Vec<BigInt> = (0..L).map(|_| rng.sample(&MODULUS)).collect();
+        transcript.append_point(&g.mul_scalar(&k_r));
+        for i in 0..L {
+            let gx = g.mul_scalar(&k_x[i]);
+            transcript.append_point(&gx.add_point(&mpk.v[i].mul_scalar(&k_r)));
+            transcript.append_point(&gx.add_point(&h.mul_scalar(&k_rho[i])));
+        }
+        let e = transcript.challenge();
+
+        let bits = bit_secrets
+            .into_iter()
+            .map(|secrets| {
+                secrets
+                    .into_iter()
+                    .map(|(b, c, rho_j, k, e_sim, z_sim)| {
+                        let e_real = reduce(&(&e - &e_sim), &MODULUS);
+                        let z_real = (k + &e_real * rho_j) % &*MODULUS;
+                        if b {
+                            BitProof { c, e0: e_sim, z0: z_sim, z1: z_real }
+                        } else {
+                            BitProof { c, e0: e_real, z0: z_real, z1: z_sim }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let proof = Self {
+            bits,
+            z_r: (k_r + &e * &r) % &*MODULUS,
+            z_x: (0..L).map(|i| reduce(&(&k_x[i] + &e * &x[i]), &MODULUS)).collect(),
+            z_rho: (0..L).map(|i| (&k_rho[i] + &e * &rho[i]) % &*MODULUS).collect(),
+            e,
+        };
+        (cipher, proof)
+    }
+
+    /// Verify the proof for `cipher`, encrypted under `mpk`, and the bound carried by `cipher`. A server should
+    /// verify the proof before decrypting the ciphertext.
+    pub fn verify(&self, mpk: &SipMpk<L>, cipher: &SipCipher<L>) -> Result<(), VerifyError> {
+        let (g, h) = (G1::generator(), generator_h());
+        let weights = weights(&cipher.bound);
+        for v in [self.bits.len(), self.z_x.len(), self.z_rho.len()].iter() {
+            if *v != L {
+                return Err(VerifyError::InputLength { expected: L, actual: *v });
+            }
+        }
+        if let Some(bits) = self.bits.iter().find(|bits| bits.len() != weights.len()) {
+            return Err(VerifyError::InputLength { expected: weights.len(), actual: bits.len() });
+        }
+
+        let mut transcript = transcript(mpk, cipher);
+        let mut d = Vec::with_capacity(L);
+        for bits in self.bits.iter() {
+            let mut di = g.mul_scalar(&-&cipher.bound);
+            for (bit, w) in bits.iter().zip(weights.iter()) {
+                let e1 = &self.e - &bit.e0;
+                let a0 = h.mul_scalar(&bit.z0).sub_point(&bit.c.mul_scalar(&bit.e0));
+                let a1 = h.mul_scalar(&bit.z1).sub_point(&bit.c.sub_point(&g).mul_scalar(&e1));
+                for p in [&bit.c, &a0, &a1].iter() {
+                    transcript.append_point(*p);
+                }
+                di = di.add_point(&bit.c.mul_scalar(w));
+            }
+            d.push(di);
+        }
+
+        transcript.append_point(&g.mul_scalar(&self.z_r).sub_point(&cipher.c0.mul_scalar(&self.e)));
+        for (i, di) in d.iter().enumerate() {
+            let gx = g.mul_scalar(&self.z_x[i]);
+            transcript.append_point(&gx.add_point(&mpk.v[i].mul_scalar(&self.z_r)).sub_point(&cipher.c[i].mul_scalar(&self.e)));
+            transcript.append_point(&gx.add_point(&h.mul_scalar(&self.z_rho[i])).sub_point(&di.mul_scalar(&self.e)));
+        }
+        if transcript.challenge() != self.e {
+            return Err(VerifyError::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+/// The weights of the bit decomposition of numbers in `[0, 2 * bound]`.
+fn weights(bound: &BigInt) -> Vec<BigInt> {
+    let n: BigInt = bound * 2;
+    let m = (n.bits() as usize).max(1);
+    let mut w: Vec<BigInt> = (0..m - 1).map(|j| BigInt::one() << j).collect();
+    w.push(&n - (BigInt::one() << (m - 1)) + 1);
+    w
+}
+
+/// Bits of `u` in `[0, 2 * bound]` for `weights`.
+fn decompose(u: &BigInt, weights: &[BigInt]) -> Vec<bool> {
+    let m = weights.len();
+    let top = *u >= BigInt::one() << (m - 1);
+    let rest = if top { u - &weights[m - 1] } else { u.clone() };
+    (0..m - 1).map(|j| rest.bit(j as u64)).chain(std::iter::once(top)).collect()
+}
+
+/// The second generator for the Pedersen commitments, hashed to the curve so that nobody knows its discrete
+/// logarithm with respect to `g`.
+fn generator_h() -> G1 {
+    hash_to_g1("ruby sip range proof h")
+}
+
+fn to_bignum(k: &BigInt) -> BigNum {
+    BigNum::fromstring(reduce(k, &MODULUS).to_str_radix(16))
+}
+
+/// Fiat-Shamir transcript, starting with the statement.
+fn transcript<const L: usize>(mpk: &SipMpk<L>, cipher: &SipCipher<L>) -> Transcript {
+    let mut t = Transcript::new("ruby sip encryption proof");
+    t.append_bytes(&cipher.bound.to_signed_bytes_be());
+    for p in mpk.v.iter().chain(std::iter::once(&cipher.c0)).chain(cipher.c.iter()) {
+        t.append_point(p);
+    }
+    t
+}
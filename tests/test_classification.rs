@@ -0,0 +1,92 @@
+use ruby::ml::classification::{Classifier, Decision, decide, sigmoid, softmax};
+use ruby::ml::linear_model::{LinearModel, LinearModelService, LinearOutput, ModelError, Score};
+
+fn output(name: &str, weights: &[f64], bias: f64) -> LinearOutput {
+    LinearOutput { name: name.to_string(), weights: weights.to_vec(), bias }
+}
+
+fn assert_prediction_close(classifier: &Classifier<4>, x: &[f64]) {
+    let cipher = classifier.encrypt(x).unwrap();
+    let encrypted = classifier.predict(&cipher).unwrap();
+    let plain = classifier.predict_plain(x).unwrap();
+    assert_eq!(encrypted.class, plain.class);
+    for (e, p) in encrypted.probabilities.iter().zip(plain.probabilities.iter()) {
+        assert_eq!(e.name, p.name);
+        assert!((e.value - p.value).abs() < 1e-2, "{}: {} vs {}", e.name, e.value, p.value);
+    }
+}
+
+#[test]
+fn test_classification_functions() {
+    assert_eq!(sigmoid(0.0), 0.5);
+    assert!((sigmoid(2.0) + sigmoid(-2.0) - 1.0).abs() < 1e-12);
+    assert!(sigmoid(-800.0) >= 0.0);
+
+    let p = softmax(&[1000.0, 1000.0, 999.0]);
+    assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    assert!((p[0] - p[1]).abs() < 1e-12 && p[2] < p[0]);
+
+    let scores = vec![Score { name: "a".to_string(), value: 0.3 }, Score { name: "b".to_string(), value: 1.2 }];
+    assert_eq!(decide(&Decision::Softmax, &scores).unwrap().class, "b");
+    assert_eq!(decide(&Decision::Softmax, &[]).err(), Some(ModelError::UnsupportedOutputs(0)));
+    let logistic = Decision::Logistic { threshold: 0.5, positive: "p".to_string(), negative: "n".to_string() };
+    assert_eq!(decide(&logistic, &scores).err(), Some(ModelError::UnsupportedOutputs(2)));
+}
+
+#[test]
+fn test_classification_bias_feature() {
+    let model = LinearModel::new(&["a", "b"], vec![output("o", &[0.5, -1.25], 2.75)]);
+    let service = LinearModelService::<3>::with_bias_feature(model.clone(), "bias-feature").unwrap();
+    let x = [1.5f64, 0.5];
+    let scores = service.compute(&service.encrypt(&x).unwrap()).unwrap();
+    assert!((scores[0].value - model.evaluate(&x).unwrap()[0].value).abs() < 1e-9);
+
+    // the constant feature needs one more slot
+    assert_eq!(
+        LinearModelService::<2>::with_bias_feature(model, "bias-feature").err(),
+        Some(ModelError::TooManyFeatures { max: 2, actual: 3 })
+    );
+}
+
+#[test]
+fn test_classification_logistic() {
+    let model = LinearModel::new(&["glucose", "bmi", "age"], vec![output("diabetes", &[1.8, 0.9, 0.04], -4.2)]);
+    let mut classifier = Classifier::<4>::logistic(model.clone(), "logistic", 0.5).unwrap();
+
+    for x in [[1.2, 0.8, 30.0], [0.5, 1.1, 52.0], [2.3, 1.6, 61.0]].iter() {
+        assert_prediction_close(&classifier, x);
+    }
+
+    // p(diabetes) is about 0.57 here: a stricter threshold flips the decision
+    let x = [1.2, 0.8, 40.0];
+    let cipher = classifier.encrypt(&x).unwrap();
+    assert_eq!(classifier.predict(&cipher).unwrap().class, "diabetes");
+    classifier.set_threshold(0.9);
+    assert_eq!(classifier.predict(&cipher).unwrap().class, "not diabetes");
+
+    assert_eq!(
+        Classifier::<4>::softmax(model, "logistic").err(),
+        Some(ModelError::UnsupportedOutputs(1))
+    );
+}
+
+#[test]
+fn test_classification_softmax() {
+    let model = LinearModel::new(&["x", "y"], vec![
+        output("left", &[-2.0, 0.1], 0.5),
+        output("middle", &[0.2, -0.3], 1.0),
+        output("right", &[2.1, 0.4], -0.25),
+    ]);
+    let classifier = Classifier::<4>::softmax(model.clone(), "softmax").unwrap();
+
+    for x in [[-1.5, 0.2], [0.1, 0.0], [1.4, -0.6]].iter() {
+        assert_prediction_close(&classifier, x);
+    }
+    let cipher = classifier.encrypt(&[-1.5, 0.2]).unwrap();
+    assert_eq!(classifier.predict(&cipher).unwrap().class, "left");
+
+    assert_eq!(
+        Classifier::<4>::logistic(model, "softmax", 0.5).err(),
+        Some(ModelError::UnsupportedOutputs(3))
+    );
+}
@@ -0,0 +1,108 @@
+use num_bigint::{BigInt};
+
+use ruby::math::fixed_point::{FixedPoint};
+use ruby::ml::ModelError;
+use ruby::ml::convolution::{Conv2d};
+use ruby::ml::neural_network::NeuralNetwork;
+
+/// Naive zero-padded convolution, indexing the padded image directly.
+fn reference(x: &[f64], input: [usize; 3], kernel_shape: [usize; 4], kernels: &[f64], stride: usize, padding: usize) -> Vec<f64> {
+    let [channels, height, width] = input;
+    let [out_channels, _, kh, kw] = kernel_shape;
+    let (ph, pw) = (height + 2 * padding, width + 2 * padding);
+    let mut padded = vec![0.0; channels * ph * pw];
+    for c in 0..channels {
+        for h in 0..height {
+            for w in 0..width {
+                padded[(c * ph + h + padding) * pw + w + padding] = x[(c * height + h) * width + w];
+            }
+        }
+    }
+    let (oh, ow) = ((ph - kh) / stride + 1, (pw - kw) / stride + 1);
+    let mut y = Vec::new();
+    for k in 0..out_channels {
+        for i in 0..oh {
+            for j in 0..ow {
+                let mut s = 0.0;
+                for c in 0..channels {
+                    for u in 0..kh {
+                        for v in 0..kw {
+                            s += kernels[((k * channels + c) * kh + u) * kw + v] * padded[(c * ph + i * stride + u) * pw + j * stride + v];
+                        }
+                    }
+                }
+                y.push(s);
+            }
+        }
+    }
+    y
+}
+
+#[test]
+fn test_convolution_projection() {
+    let (input, kernel_shape, stride, padding) = ([2, 4, 5], [3, 2, 3, 2], 2, 1);
+    let kernels: Vec<f64> = (0..36).map(|i| ((i * 7) % 11) as f64 - 5.0).collect();
+    let conv = Conv2d::new(input, kernel_shape, kernels.clone(), stride, padding).unwrap();
+    assert_eq!(conv.output(), [3, 2, 3]);
+    assert_eq!((conv.n(), conv.d()), (40, 18));
+
+    let x: Vec<f64> = (0..40).map(|i| ((i * 5) % 9) as f64 - 4.0).collect();
+    let expected = reference(&x, input, kernel_shape, &kernels, stride, padding);
+    assert_eq!(conv.forward(&x).unwrap(), expected);
+
+    let p = conv.projection();
+    let projected: Vec<f64> = (0..conv.d()).map(|j| (0..conv.n()).map(|i| p[i][j] * x[i]).sum()).collect();
+    assert_eq!(projected, expected);
+
+    let matrix = conv.projection_matrix(&FixedPoint::new(10)).unwrap();
+    assert_eq!((matrix.n_rows, matrix.n_cols), (40, 18));
+    assert_eq!(matrix.get_element(0, 0), &BigInt::from(p[0][0] as i64 * 10));
+}
+
+#[test]
+fn test_convolution_network() {
+    const L: usize = 9;
+    let kernels = vec![1.0, -1.0, 0.0, 2.0, 0.0, 1.0, 1.0, 0.0];
+    let conv = Conv2d::new([1, 3, 3], [2, 1, 2, 2], kernels, 1, 0).unwrap();
+    let linear = vec![
+        vec![1.0, 0.0, -1.0, 0.0, 2.0, 0.0, 0.0, -1.0],
+        vec![0.0, 1.0, 0.0, 1.0, -1.0, 1.0, 0.0, 0.0],
+    ];
+    let weights = conv.network(&linear).unwrap();
+    let (network, report) = weights.quantize(1, 1).unwrap();
+    assert_eq!(report.max_abs(), 0.0);
+    let service = NeuralNetwork::<L>::from_quantized(&network, &BigInt::from(3)).unwrap();
+
+    for x in [[1, -2, 3, 0, 1, -1, 2, 2, -3], [0, 0, 1, -1, 3, 2, -2, 1, 0]].iter() {
+        let xf: Vec<f64> = x.iter().map(|&v| v as f64).collect();
+        let z = conv.forward(&xf).unwrap();
+        let plain: Vec<f64> = linear.iter().map(|row| row.iter().zip(z.iter()).map(|(w, zj)| w * zj * zj).sum()).collect();
+        assert_eq!(weights.evaluate(&xf).unwrap(), plain);
+
+        let encoded: [BigInt; L] = array_init::array_init(|i| BigInt::from(x[i]));
+        let scores = service.compute(&service.encrypt(&encoded));
+        assert_eq!(scores, plain.iter().map(|&s| BigInt::from(s as i64)).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_convolution_errors() {
+    assert!(matches!(Conv2d::new([1, 3, 3], [1, 1, 2, 2], vec![1.0; 4], 0, 0), Err(ModelError::Empty)));
+    assert_eq!(
+        Conv2d::new([2, 3, 3], [1, 1, 2, 2], vec![1.0; 4], 1, 0).err(),
+        Some(ModelError::Shape { expected: vec![2], actual: vec![1] })
+    );
+    assert_eq!(
+        Conv2d::new([1, 3, 3], [1, 1, 2, 2], vec![1.0; 3], 1, 0).err(),
+        Some(ModelError::Shape { expected: vec![1, 1, 2, 2], actual: vec![3] })
+    );
+    assert_eq!(
+        Conv2d::new([1, 3, 3], [1, 1, 4, 2], vec![1.0; 8], 1, 0).err(),
+        Some(ModelError::Shape { expected: vec![3, 3], actual: vec![4, 2] })
+    );
+
+    let conv = Conv2d::new([1, 3, 3], [1, 1, 2, 2], vec![1.0; 4], 1, 1).unwrap();
+    assert_eq!(conv.output(), [1, 4, 4]);
+    assert_eq!(conv.forward(&[1.0; 4]).err(), Some(ModelError::Shape { expected: vec![1, 3, 3], actual: vec![4] }));
+    assert_eq!(conv.network(&[vec![1.0; 3]]).err(), Some(ModelError::Shape { expected: vec![16], actual: vec![3] }));
+}
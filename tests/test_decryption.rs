@@ -0,0 +1,151 @@
+use num_bigint::{BigInt};
+
+use ruby::define::{G1, G2Vector};
+use ruby::dmcfe_ip::{Dmcfe, DmcfeCipher};
+use ruby::math::matrix::{BigIntMatrix};
+use ruby::ml::disease_prediction::DiseasePrediction;
+use ruby::ml::linear_model::{LinearModel, LinearModelService, LinearOutput};
+use ruby::ml::neural_network::NeuralNetwork;
+use ruby::quadratic_sgp::{Sgp, SgpPlain};
+use ruby::simple_ip::Sip;
+use ruby::traits::FunctionalEncryption;
+use ruby::utils::rand_utils::{RandUtilsRng, Sample};
+use ruby::zk::VerifyError;
+use ruby::zk::decryption::{prove_dmcfe_decryption, prove_sgp_decryption, prove_sip_decryption, verify_dmcfe_decryption,
+                           verify_sgp_decryption, verify_sip_decryption};
+
+#[test]
+fn test_sip_decryption_proof() {
+    const L: usize = 3;
+    let sip = Sip::<L>::new();
+    let x = [BigInt::from(3), BigInt::from(-7), BigInt::from(12)];
+    let y = [BigInt::from(-2), BigInt::from(5), BigInt::from(1)];
+    let cipher = sip.encrypt(&x);
+    let dk = sip.derive_fe_key(&y);
+    let z = sip.decrypt(&cipher, &dk).unwrap();
+    assert_eq!(z, BigInt::from(-29));
+
+    let proof = prove_sip_decryption(sip.mpk(), &cipher, &dk, &z);
+    assert_eq!(verify_sip_decryption(sip.mpk(), &cipher, &y, &z, &proof), Ok(()));
+    assert_eq!(verify_sip_decryption(sip.mpk(), &cipher, &y, &(&z + 1), &proof), Err(VerifyError::InvalidProof));
+    let other_y = [BigInt::from(-2), BigInt::from(5), BigInt::from(2)];
+    assert_eq!(verify_sip_decryption(sip.mpk(), &cipher, &other_y, &z, &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_sip_decryption(sip.mpk(), &sip.encrypt(&x), &y, &z, &proof), Err(VerifyError::InvalidProof));
+}
+
+#[test]
+#[should_panic(expected = "Malformed input")]
+fn test_sip_decryption_proof_wrong_result() {
+    let sip = Sip::<2>::new();
+    let y = [BigInt::from(1), BigInt::from(1)];
+    let cipher = sip.encrypt(&[BigInt::from(1), BigInt::from(2)]);
+    prove_sip_decryption(sip.mpk(), &cipher, &sip.derive_fe_key(&y), &BigInt::from(4));
+}
+
+#[test]
+fn test_sgp_decryption_proof() {
+    const L: usize = 2;
+    let sgp = Sgp::<L>::new();
+    let plain = SgpPlain { x: [BigInt::from(1), BigInt::from(-2)], y: [BigInt::from(3), BigInt::from(4)] };
+    let f = BigIntMatrix::new_ints(&[1, 0, -1, 2][..], 2, 2);
+    let cipher = sgp.encrypt(&plain);
+    let dk = sgp.derive_fe_key(&f);
+    let z = sgp.decrypt(&cipher, &dk).unwrap();
+
+    let proof = prove_sgp_decryption(sgp.pk(), &cipher, &dk, &z);
+    assert_eq!(verify_sgp_decryption(sgp.pk(), &cipher, &f, &z, &proof), Ok(()));
+    assert_eq!(verify_sgp_decryption(sgp.pk(), &cipher, &f, &(&z - 1), &proof), Err(VerifyError::InvalidProof));
+    let other_f = BigIntMatrix::new_ints(&[1, 0, -1, 3][..], 2, 2);
+    assert_eq!(verify_sgp_decryption(sgp.pk(), &cipher, &other_f, &z, &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_sgp_decryption(Sgp::<L>::new().pk(), &cipher, &f, &z, &proof), Err(VerifyError::InvalidProof));
+    let wide_f = BigIntMatrix::new_ints(&[1, 0, -1][..], 1, 3);
+    assert_eq!(
+        verify_sgp_decryption(sgp.pk(), &cipher, &wide_f, &z, &proof),
+        Err(VerifyError::InputLength { expected: 1, actual: 2 })
+    );
+}
+
+#[test]
+fn test_dmcfe_decryption_proof() {
+    const L: usize = 3;
+    let bound = BigInt::from(100);
+    let label = "dmcfe-label";
+    let mut clients: Vec<Dmcfe<L>> = (0..L).map(Dmcfe::<L>::new_single).collect();
+    let pub_keys: Vec<G1> = clients.iter().map(|c| c.client_pub_key.clone()).collect();
+    for client in clients.iter_mut() {
+        client.set_share(&pub_keys);
+    }
+    let x = [BigInt::from(7), BigInt::from(-20), BigInt::from(55)];
+    let y = [BigInt::from(3), BigInt::from(2), BigInt::from(-1)];
+    let ciphers: Vec<DmcfeCipher> = clients.iter().enumerate().map(|(i, c)| c.encrypt_single(&x[i], &bound, label)).collect();
+    let fe_key: Vec<G2Vector> = clients.iter().map(|c| c.derive_fe_key_share(&y)).collect();
+    let dk = clients[0].key_comb(&fe_key, &y);
+    let z = clients[0].decrypt_with_label(&ciphers, &dk, label).unwrap();
+    assert_eq!(z, BigInt::from(-74));
+
+    let proof = prove_dmcfe_decryption(&ciphers, &dk, label, &z);
+    assert_eq!(verify_dmcfe_decryption(&ciphers, &y, label, &z, &proof), Ok(()));
+    assert_eq!(verify_dmcfe_decryption(&ciphers, &y, label, &(&z + 1), &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_dmcfe_decryption(&ciphers, &y, "other-label", &z, &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(
+        verify_dmcfe_decryption(&ciphers[..2], &y, label, &z, &proof),
+        Err(VerifyError::InputLength { expected: 3, actual: 2 })
+    );
+}
+
+#[test]
+fn test_linear_model_verifiable() {
+    let output = |name: &str, weights: Vec<f64>, bias: f64| LinearOutput { name: name.to_string(), weights, bias };
+    let model = LinearModel::new(&["a", "b"], vec![output("risk", vec![0.5, -1.25], 2.0), output("score", vec![1.0, 0.75], 0.0)]);
+    let service = LinearModelService::<3>::new(model.clone(), "linear-verifiable").unwrap();
+    let cipher = service.encrypt(&[1.5f64, 0.25]).unwrap();
+    let mut scores = service.compute_verifiable(&cipher).unwrap();
+    assert_eq!(scores.iter().map(|s| s.score.clone()).collect::<Vec<_>>(), service.compute(&cipher).unwrap());
+    assert_eq!(model.verify_scores::<3>("linear-verifiable", &cipher, &scores), Ok(()));
+    assert_eq!(model.verify_scores::<3>("other-label", &cipher, &scores), Err(VerifyError::InvalidProof));
+
+    scores[0].score.value += 1.0;
+    assert_eq!(
+        model.verify_scores::<3>("linear-verifiable", &cipher, &scores),
+        Err(VerifyError::InputMismatch { input: "risk".to_string() })
+    );
+    assert_eq!(
+        model.verify_scores::<3>("linear-verifiable", &cipher, &scores[..1]),
+        Err(VerifyError::InputLength { expected: 2, actual: 1 })
+    );
+}
+
+#[test]
+fn test_disease_prediction_verifiable() {
+    let service = DiseasePrediction::new();
+    let x: [f32; 8] = [0.1, -0.23, 1.1, 0.98, 5.6, -0.9, -5.0, 2.4];
+    let ciphers = service.encrypt(&x);
+    let scores = service.compute_verifiable(&ciphers);
+    assert_eq!(scores.len(), 2);
+    assert_eq!(service.verify(&ciphers, &scores), Ok(()));
+    let other: [f32; 8] = [0.2, -0.23, 1.1, 0.98, 5.6, -0.9, -5.0, 2.4];
+    assert_eq!(service.verify(&service.encrypt(&other), &scores), Err(VerifyError::InvalidProof));
+}
+
+#[test]
+fn test_neural_network_verifiable() {
+    const L: usize = 4;
+    let mut rng = RandUtilsRng::new();
+    let p = BigIntMatrix::new_random(L, 2, &BigInt::from(-2), &BigInt::from(2));
+    let q: Vec<BigIntMatrix> = (0..2).map(|_| BigIntMatrix::new_random(2, 2, &BigInt::from(-3), &BigInt::from(3))).collect();
+    let service = NeuralNetwork::<L>::new(&p, &q);
+    let x: [BigInt; L] = rng.sample_range_array::<L>(&BigInt::from(-10), &BigInt::from(10));
+    let cipher = service.encrypt(&x);
+
+    let results = service.compute_verifiable(&cipher);
+    assert_eq!(results.iter().map(|(z, _)| z.clone()).collect::<Vec<_>>(), service.compute(&cipher));
+    assert_eq!(NeuralNetwork::verify(service.pk(), &p, &q, &cipher, &results), Ok(()));
+
+    let mut forged = results.clone();
+    forged[1].0 += 1;
+    assert_eq!(NeuralNetwork::verify(service.pk(), &p, &q, &cipher, &forged), Err(VerifyError::InvalidProof));
+    assert_eq!(
+        NeuralNetwork::verify(service.pk(), &p, &q[..1], &cipher, &results),
+        Err(VerifyError::InputLength { expected: 1, actual: 2 })
+    );
+}
@@ -1,9 +1,10 @@
 use num_bigint::{BigInt};
 
-use ruby::define::{G1, G1Vector, G2Vector};
+use ruby::define::{G1, G2Vector};
 use ruby::utils::{inner_product_result};
 use ruby::utils::rand_utils::{RandUtilsRng, Sample};
-use ruby::dmcfe_ip::Dmcfe;
+use ruby::dmcfe_ip::{Dmcfe, DmcfeCipher};
+use ruby::bounds::DecryptError;
 use ruby::traits::FunctionalEncryption;
 
 #[cfg(test)]
@@ -19,7 +20,7 @@ mod tests {
         let high = bound.clone();
         let mut clients: Vec<Dmcfe<L>> = Vec::with_capacity(L);
         let mut pub_keys: Vec<G1> = Vec::with_capacity(L);
-        let mut ciphers: Vec<G1> = Vec::with_capacity(L);
+        let mut ciphers: Vec<DmcfeCipher> = Vec::with_capacity(L);
         let mut fe_key: Vec<G2Vector> = Vec::with_capacity(L);
         let mut temp: G1;
 
@@ -43,18 +44,18 @@ mod tests {
         println!("Groud truth: {:?}", plain_result);
 
         for i in 0..L {
-            ciphers.push(clients[i].encrypt_single(&x[i], label));
+            ciphers.push(clients[i].encrypt_single(&x[i], &bound, label));
             fe_key.push(clients[i].derive_fe_key_share(&y));
         }
         println!("decrypt starts");
         use std::time::Instant;
         let now = Instant::now();
         let dk = clients[0].key_comb(&fe_key, &y);
-        let xy = clients[0].decrypt_with_label(&ciphers, &dk, &bound, label);
+        let xy = clients[0].decrypt_with_label(&ciphers, &dk, label);
         let elapsed = now.elapsed();
         println!("Elapsed: {:.2?}", elapsed);
 
-        assert!(xy.is_some());
+        assert!(xy.is_ok());
         assert_eq!(xy.unwrap(), plain_result);
     }
 
@@ -76,7 +77,7 @@ mod tests {
         let client = Dmcfe::<L>::new();
 
         let now = Instant::now();
-        let ciphers: G1Vector = client.encrypt(&x);
+        let ciphers: Vec<DmcfeCipher> = client.encrypt(&x);
         let elapsed = now.elapsed();
         println!("[DMCFE Encrypt]: {:.2?}", elapsed);
 
@@ -86,12 +87,37 @@ mod tests {
         println!("[DMCFE Derive]: {:.2?}", elapsed);
         
         let now = Instant::now();
-        let xy = client.decrypt(&ciphers, &dk, &bound);
+        let xy = client.decrypt(&ciphers, &dk);
         let elapsed = now.elapsed();
         println!("[DMCFE Decrypt]: {:.2?}", elapsed);
 
-        assert!(xy.is_some());
+        assert!(xy.is_ok());
         assert_eq!(xy.unwrap(), plain_result);
     }
-}
 
+    #[test]
+    fn test_dmcfe_out_of_range() {
+        const L: usize = 2;
+        let client = Dmcfe::<L>::new();
+        let label = "dmcfe-label";
+        let x = [BigInt::from(7), BigInt::from(3)];
+        let y = [BigInt::from(2), BigInt::from(5)];
+
+        let mut ciphers = vec![
+            client.encrypt_single(&x[0], &BigInt::from(10), label),
+            client.encrypt_single(&x[1], &BigInt::from(4), label),
+        ];
+        let dk = client.derive_fe_key(&y);
+        assert_eq!(dk.bound, BigInt::from(5));
+        assert_eq!(client.decrypt_with_label(&ciphers, &dk, label).unwrap(), BigInt::from(29));
+
+        let res = client.decrypt_with_label(&ciphers[..1], &dk, label);
+        assert_eq!(res, Err(DecryptError::DimensionMismatch { expected: L, actual: 1 }));
+
+        // understated bounds: the result 29 lies outside [-10, 10]
+        ciphers[0].bound = BigInt::from(1);
+        ciphers[1].bound = BigInt::from(1);
+        let res = client.decrypt_with_label(&ciphers, &dk, label);
+        assert_eq!(res, Err(DecryptError::OutOfRange { range: BigInt::from(10) }));
+    }
+}
@@ -0,0 +1,124 @@
+use num_bigint::{BigInt};
+use num_traits::{Signed, ToPrimitive};
+
+use ruby::define::{G1, G2Vector};
+use ruby::utils::{inner_product_result};
+use ruby::utils::rand_utils::{RandUtilsRng};
+use ruby::simple_ip::Sip;
+use ruby::dmcfe_ip::{Dmcfe, DmcfeCipher};
+use ruby::dp::{DpConfig, DpError, NoiseMechanism, PrivacyAccountant};
+use ruby::dp::sampler::{DiscreteNoise, Ratio};
+use ruby::traits::FunctionalEncryption;
+
+fn moments(samples: &[BigInt]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let values: Vec<f64> = samples.iter().map(|s| s.to_f64().unwrap()).collect();
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+    (mean, var)
+}
+
+#[test]
+fn test_dp_samplers() {
+    let mut rng = RandUtilsRng::new();
+    let n = 4000;
+
+    // Variance of the discrete Gaussian is slightly below sigma2
+    let sigma2 = Ratio::from_int(9);
+    let samples: Vec<BigInt> = (0..n).map(|_| rng.sample_discrete_gaussian(&sigma2)).collect();
+    let (mean, var) = moments(&samples);
+    println!("discrete gaussian: mean {}, var {}", mean, var);
+    assert!(mean.abs() < 0.5);
+    assert!((var - 9.0).abs() < 1.5);
+
+    // Variance of the discrete Laplace is 2 p / (1 - p)^2 with p = exp(-1 / scale)
+    let scale = Ratio::new(&BigInt::from(5), &BigInt::from(2));
+    let samples: Vec<BigInt> = (0..n).map(|_| rng.sample_discrete_laplace(&scale)).collect();
+    let (mean, var) = moments(&samples);
+    let p = (-1.0 / 2.5f64).exp();
+    let expected = 2.0 * p / ((1.0 - p) * (1.0 - p));
+    println!("discrete laplace: mean {}, var {} (expected {})", mean, var, expected);
+    assert!(mean.abs() < 0.5);
+    assert!((var - expected).abs() < 0.15 * expected);
+
+    let hits = (0..n).filter(|_| rng.sample_bernoulli_exp(&Ratio::new(&BigInt::from(3), &BigInt::from(2)))).count();
+    let freq = hits as f64 / n as f64;
+    assert!((freq - (-1.5f64).exp()).abs() < 0.04);
+}
+
+#[test]
+fn test_dp_sip() {
+    const L: usize = 4;
+    let sip = Sip::<L>::new();
+    let config = DpConfig {
+        mechanism: NoiseMechanism::DiscreteLaplace { scale: Ratio::from_int(4) },
+        sensitivity: BigInt::from(2),
+    };
+    let x: [BigInt; L] = array_init::array_init(|i| BigInt::from(i as i64 * 3 - 4));
+    let y: [BigInt; L] = array_init::array_init(|i| BigInt::from(i as i64 % 2));
+    let cipher = sip.encrypt(&config.perturb(&x));
+    let dk = sip.derive_fe_key(&y);
+
+    let exact = inner_product_result(&x, &y);
+
+    let mut accountant = PrivacyAccountant::new(config.clone(), 0.5, 0.0);
+    let res = accountant.decrypt_sip(&sip, &cipher, &dk, &y, "sip-label").unwrap();
+    println!("noisy {} vs exact {}", res.value, exact);
+    assert_eq!(res.guarantee.epsilon, 0.5);
+    assert_eq!(res.guarantee.delta, 0.0);
+    // y selects two coordinates, so the error is the sum of two noise samples
+    assert!((&res.value - &exact).abs() <= config.noise_bound() * 2);
+
+    // The error of each trial has mean 0 and variance 2 * 2p / (1 - p)^2 with p = exp(-1 / 4), about 64
+    let trials = 100;
+    let errors: Vec<BigInt> = (0..trials)
+        .map(|_| sip.decrypt(&sip.encrypt(&config.perturb(&x)), &dk).unwrap() - &exact)
+        .collect();
+    let (mean, var) = moments(&errors);
+    println!("noise of the inner product: mean {}, var {}", mean, var);
+    assert!(mean.abs() < 4.0);
+    assert!(var > 24.0 && var < 160.0);
+}
+
+#[test]
+fn test_dp_dmcfe_budget() {
+    const L: usize = 3;
+    let mut clients: Vec<Dmcfe<L>> = (0..L).map(Dmcfe::<L>::new_single).collect();
+    let pub_keys: Vec<G1> = clients.iter().map(|c| c.client_pub_key.clone()).collect();
+    for client in clients.iter_mut() {
+        client.set_share(&pub_keys);
+    }
+
+    // The noise of the sum has variance 300, split among the clients
+    let sensitivity = BigInt::from(1);
+    let config = DpConfig::distributed_gaussian(&Ratio::from_int(300), L, &sensitivity);
+    let x: [BigInt; L] = [BigInt::from(7), BigInt::from(-2), BigInt::from(5)];
+    let noisy_x = config.perturb(&x);
+
+    let label = "round-1";
+    let bound = BigInt::from(10) + config.noise_bound();
+    let ciphers: Vec<DmcfeCipher> = (0..L).map(|i| clients[i].encrypt_single(&noisy_x[i], &bound, label)).collect();
+    let ones: [BigInt; L] = array_init::array_init(|_| BigInt::from(1));
+    let shares: Vec<G2Vector> = clients.iter().map(|c| c.derive_fe_key_share(&ones)).collect();
+    let dk = clients[0].key_comb(&shares, &ones);
+
+    let mut accountant = PrivacyAccountant::new(config, 0.4, 1e-5);
+    let res = accountant.decrypt_dmcfe(&clients[0], &ciphers, &dk, &ones, label).unwrap();
+    println!("noisy sum {} vs exact {}, epsilon {}", res.value, inner_product_result(&x, &ones), res.guarantee.epsilon);
+    assert!(res.guarantee.epsilon < 0.4);
+
+    // Repeating the same query releases nothing new
+    let spent = accountant.charge(label, &ones).unwrap();
+    assert!((spent.epsilon - res.guarantee.epsilon).abs() < 1e-9);
+
+    // Together with the sum, a second query would isolate the first client's noise
+    let first: [BigInt; L] = [BigInt::from(1), BigInt::from(0), BigInt::from(0)];
+    match accountant.charge(label, &first) {
+        Err(DpError::BudgetExceeded { epsilon, .. }) => assert!(epsilon > 0.4),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!((accountant.spent(label).epsilon - res.guarantee.epsilon).abs() < 1e-9);
+
+    // Labels are accounted separately
+    assert_eq!(accountant.spent("round-2").epsilon, 0.0);
+}
@@ -0,0 +1,103 @@
+use num_bigint::{BigInt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::path::PathBuf;
+
+use ruby::ml::ModelError;
+use ruby::ml::evaluation::{EncryptedEvaluator};
+use ruby::ml::idx::{Dataset, IdxArray, IdxType};
+use ruby::ml::quadratic_trainer::{Optimizer, QuadraticTrainer};
+
+const L: usize = 4;
+const CLASSES: usize = 3;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ruby_evaluation_{}_{}", std::process::id(), name))
+}
+
+/// 2x2 grayscale images: the image of class `c` has a bright pixel `c` on a dark, noisy background.
+fn images(count: usize, seed: u64) -> (IdxArray, IdxArray) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pixels = Vec::with_capacity(count * L);
+    let mut labels = Vec::with_capacity(count);
+    for s in 0..count {
+        let c = s % CLASSES;
+        for i in 0..L {
+            pixels.push(if i == c { rng.gen_range(160..=255) } else { rng.gen_range(0..40) } as f64);
+        }
+        labels.push(c as f64);
+    }
+    (IdxArray::new(IdxType::U8, vec![count, 2, 2], pixels), IdxArray::new(IdxType::U8, vec![count], labels))
+}
+
+#[test]
+fn test_idx() {
+    for &dtype in [IdxType::U8, IdxType::I8, IdxType::I16, IdxType::I32, IdxType::F32, IdxType::F64].iter() {
+        let array = IdxArray::new(dtype, vec![2, 3], vec![0.0, 1.0, 7.0, 100.0, 3.0, 12.0]);
+        assert_eq!(IdxArray::from_bytes(&array.to_bytes()).unwrap(), array);
+    }
+    let signed = IdxArray::new(IdxType::I16, vec![3], vec![-300.0, 0.0, 300.0]);
+    assert_eq!(IdxArray::from_bytes(&signed.to_bytes()).unwrap(), signed);
+
+    // header of MNIST's label files
+    let bytes = [0, 0, 0x08, 1, 0, 0, 0, 2, 7, 3];
+    assert_eq!(IdxArray::from_bytes(&bytes).unwrap(), IdxArray::new(IdxType::U8, vec![2], vec![7.0, 3.0]));
+    assert!(matches!(IdxArray::from_bytes(&bytes[..9]), Err(ModelError::Idx(_))));
+    assert_eq!(IdxArray::from_bytes(&[0, 0, 0x0A, 0]).err(), Some(ModelError::Idx("unsupported type 0x0a".to_string())));
+    assert_eq!(IdxArray::from_bytes(&[1, 0, 0x08, 0]).err(), Some(ModelError::Idx("missing magic number".to_string())));
+    let mut huge = vec![0, 0, 0x08, 3];
+    huge.extend_from_slice(&[0xff; 12]);
+    assert_eq!(IdxArray::from_bytes(&huge).err(), Some(ModelError::Idx("dimensions overflow".to_string())));
+
+    let (images, _) = images(5, 0);
+    let labels = IdxArray::new(IdxType::U8, vec![4], vec![0.0; 4]);
+    assert_eq!(Dataset::from_arrays(&images, &labels).err(), Some(ModelError::FeatureMismatch { expected: 5, actual: 4 }));
+    let labels = IdxArray::new(IdxType::U8, vec![5], vec![0.0; 5]);
+    let mut truncated = images.clone();
+    truncated.data.pop();
+    assert_eq!(Dataset::from_arrays(&truncated, &labels).err(), Some(ModelError::Idx("expected 20 entries, found 19".to_string())));
+    let mut huge = images;
+    huge.shape = vec![5, usize::MAX, 2];
+    assert_eq!(Dataset::from_arrays(&huge, &labels).err(), Some(ModelError::Idx("dimensions overflow".to_string())));
+}
+
+#[test]
+fn test_encrypted_evaluation() {
+    let (images_path, labels_path) = (temp_path("images-idx3-ubyte"), temp_path("labels-idx1-ubyte"));
+    let (images_idx, labels_idx) = images(6, 1);
+    images_idx.save(&images_path).unwrap();
+    labels_idx.save(&labels_path).unwrap();
+    let dataset = Dataset::load_idx(&images_path, &labels_path).unwrap();
+    fs::remove_file(&images_path).unwrap();
+    fs::remove_file(&labels_path).unwrap();
+    assert_eq!(dataset.len(), 6);
+    assert_eq!(dataset.samples[0].len(), L);
+
+    // train on normalized pixels; the scores are homogeneous in the input, so the classes of raw pixels agree
+    let (train_idx, train_labels) = images(90, 2);
+    let train = Dataset::from_arrays(&train_idx, &train_labels).unwrap();
+    let x: Vec<Vec<f64>> = train.samples.iter().map(|s| s.iter().map(|p| p / 64.0).collect()).collect();
+    let weights = QuadraticTrainer::new(3, Optimizer::adam(0.05)).train(&x, &train.labels, CLASSES).unwrap();
+
+    // pixels are divided by 16 before encryption, so they are bounded by 16
+    let x_bound = BigInt::from(16);
+    let (network, _) = weights.quantize_bounded(&x_bound, &BigInt::from(1u64 << 24)).unwrap();
+    let mut evaluator = EncryptedEvaluator::<L>::new(weights, network, 1.0 / 16.0, &x_bound).unwrap();
+
+    let report = evaluator.evaluate(&dataset, Some(4)).unwrap();
+    assert_eq!(report.samples, 4);
+    assert_eq!(report.encrypt_times.len(), 4);
+    assert_eq!(report.decrypt_times.len(), 4);
+    assert!(report.mean_decrypt_time() > report.mean_encrypt_time());
+    assert!(report.accuracy() >= 0.75);
+    assert_eq!(report.plain_accuracy(), 1.0);
+    for m in report.mismatches.iter() {
+        assert_ne!(m.encrypted, m.plain);
+        assert_eq!(m.plain, dataset.labels[m.index]);
+    }
+    assert_eq!(report.agreement() == 1.0, report.accuracy() == 1.0);
+
+    evaluator.input_scale = 1.0;
+    assert_eq!(evaluator.evaluate(&dataset, None).err(), Some(ModelError::InputOutOfBound { bound: BigInt::from(16) }));
+}
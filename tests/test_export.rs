@@ -0,0 +1,103 @@
+use fawkes_crypto::{
+    backend::bellman_groth16::engines::{Bls12_381, Bn256},
+    native::ecc::*,
+    rand::{thread_rng, Rng},
+    ff_uint::Num,
+};
+
+use ruby::zk::dlog::ZkDlog;
+use ruby::zk::export::{solidity_verifier, Encoding, ExportError, SnarkBundle, BUNDLE_VERSION};
+use ruby::zk::{ToEncoding, VerifyError, Zk};
+
+/// Export a dlog proof on the engine `Z` in both encodings, and check that it verifies after re-import.
+fn check_round_trip<Z: Zk>() {
+    let mut rng = thread_rng();
+    let jubjub_params = Z::jubjub_params();
+    let g = EdwardsPoint::<Z::Fr>::rand(&mut rng, &jubjub_params).mul(Num::from(8), &jubjub_params);
+    let x: Num<Z::Fr> = rng.gen();
+    let y = g.mul(x.to_other_reduced(), &jubjub_params);
+    let params = ZkDlog::<Z>::setup();
+    let bundle = SnarkBundle::new(ZkDlog::<Z>::circuit_name(), ZkDlog::<Z>::generate_with(&params, &g, &x).unwrap());
+
+    for encoding in [Encoding::Base64, Encoding::Hex].iter() {
+        let json = bundle.to_json(*encoding);
+        let imported = SnarkBundle::<Z::E>::from_json(&json).unwrap();
+        assert_eq!(imported.circuit, "dlog");
+        assert_eq!(imported.snark.vk.encode(), params.vk().encode());
+        assert_eq!(imported.snark.proof.encode(), bundle.snark.proof.encode());
+        assert_eq!(ZkDlog::<Z>::verify_with(&params.vk(), &g, &y, &imported.snark), Ok(()));
+        assert_eq!(ZkDlog::<Z>::verify_with(&params.vk(), &g, &g, &imported.snark), Err(VerifyError::InputMismatch { input: "y".to_string() }));
+        assert_eq!(imported.to_json(*encoding), json);
+    }
+}
+
+#[test]
+fn test_bundle_round_trip_bn256() {
+    check_round_trip::<Bn256>();
+}
+
+#[test]
+fn test_bundle_round_trip_bls12381() {
+    check_round_trip::<Bls12_381>();
+}
+
+#[test]
+fn test_bundle_import_errors() {
+    let mut rng = thread_rng();
+    let jubjub_params = Bn256::jubjub_params();
+    let g = EdwardsPoint::<<Bn256 as Zk>::Fr>::rand(&mut rng, &jubjub_params).mul(Num::from(8), &jubjub_params);
+    let x: Num<<Bn256 as Zk>::Fr> = rng.gen();
+    let mut bundle = SnarkBundle::new(ZkDlog::<Bn256>::circuit_name(), ZkDlog::<Bn256>::generate(&g, &x));
+    let json = bundle.to_json(Encoding::Hex);
+
+    let version = format!("\"version\":{}", BUNDLE_VERSION);
+    assert_eq!(
+        SnarkBundle::<Bn256>::from_json(&json.replace(&version, "\"version\":0")).err(),
+        Some(ExportError::Version { expected: BUNDLE_VERSION, actual: 0 })
+    );
+    assert!(matches!(SnarkBundle::<Bn256>::from_json(&json[1..]), Err(ExportError::Json(_))));
+    assert_eq!(
+        SnarkBundle::<Bn256>::from_json(&json.replace("\"proof\":\"0x", "\"proof\":\"0xzz")).err(),
+        Some(ExportError::Decoding { field: "proof".to_string() })
+    );
+    assert_eq!(
+        SnarkBundle::<Bn256>::from_json(&json.replace("\"inputs\":\"0x", "\"inputs\":\"0x00")).err(),
+        Some(ExportError::Decoding { field: "inputs".to_string() })
+    );
+    // a proof of another engine does not decode
+    assert_eq!(
+        SnarkBundle::<Bls12_381>::from_json(&json).err(),
+        Some(ExportError::Decoding { field: "vk".to_string() })
+    );
+
+    bundle.snark.proof.a.1 += Num::ONE;
+    assert_eq!(
+        SnarkBundle::<Bn256>::from_json(&bundle.to_json(Encoding::Base64)).err(),
+        Some(ExportError::InvalidPoint { field: "proof".to_string() })
+    );
+}
+
+#[test]
+fn test_solidity_verifier() {
+    let mut rng = thread_rng();
+    let jubjub_params = Bn256::jubjub_params();
+    let g = EdwardsPoint::<<Bn256 as Zk>::Fr>::rand(&mut rng, &jubjub_params).mul(Num::from(8), &jubjub_params);
+    let x: Num<<Bn256 as Zk>::Fr> = rng.gen();
+    let snark = ZkDlog::<Bn256>::generate(&g, &x);
+
+    let contract = solidity_verifier(&snark.vk, "DlogVerifier");
+    assert!(contract.contains("contract DlogVerifier {"));
+    // the points g and y are the 4 public inputs
+    assert!(contract.contains("uint256[4] calldata input"));
+    assert!(contract.contains("vk.ic = new G1Point[](5);"));
+    assert!(contract.contains(&format!("vk.ic[4] = G1Point({}, {});", snark.vk.ic[4].0, snark.vk.ic[4].1)));
+    assert!(contract.contains(&format!("vk.alpha = G1Point({}, {});", snark.vk.alpha.0, snark.vk.alpha.1)));
+    for placeholder in ["{contract}", "{inputs}", "{ic_len}", "{alpha}", "{beta}", "{gamma}", "{delta}", "{ic}"].iter() {
+        assert!(!contract.contains(placeholder));
+    }
+
+    let calldata = snark.to_solidity_calldata();
+    let b = &snark.proof.b;
+    assert!(calldata.starts_with(&format!("[\"{}\",\"{}\"],[[\"{}\",\"{}\"]", snark.proof.a.0, snark.proof.a.1, (b.0).1, (b.0).0)));
+    assert!(calldata.ends_with(&format!("[\"{}\",\"{}\",\"{}\",\"{}\"]", g.x, g.y, snark.inputs[2], snark.inputs[3])));
+}
@@ -0,0 +1,108 @@
+use num_bigint::{BigInt};
+
+use ruby::define::{G2Vector};
+use ruby::ml::federated::{FederatedClient, FederatedError, FederatedServer, Weighting};
+
+const L: usize = 3;
+const SESSION: &str = "federated-test";
+
+fn aggregate(clients: &[FederatedClient<L>], server: &mut FederatedServer<L>) -> Result<Vec<f64>, FederatedError> {
+    let weights = server.weights()?;
+    let shares: Vec<G2Vector> = clients.iter().map(|c| c.key_share(&weights)).collect::<Result<_, _>>()?;
+    server.aggregate(&shares)
+}
+
+fn assert_close(result: &[f64], truth: &[f64]) {
+    for (r, t) in result.iter().zip(truth.iter()) {
+        assert!((r - t).abs() < 1e-3, "{} vs {}", r, t);
+    }
+}
+
+#[test]
+fn test_federated_averaging() {
+    let clients = FederatedClient::<L>::setup(SESSION, 1000, 1.0);
+    let updates = [[0.25, -0.5, 0.125], [0.75, 0.0, -0.375], [-0.1, 0.9, 0.001]];
+
+    let mut server = FederatedServer::<L>::new(SESSION, 3, Weighting::Uniform);
+    for c in clients.iter() {
+        server.submit(c.encrypt_update(server.round(), &updates[c.index]).unwrap()).unwrap();
+    }
+    assert_eq!(server.weights().unwrap(), vec![BigInt::from(1); L]);
+    let average = aggregate(&clients, &mut server).unwrap();
+    assert_close(&average, &[0.3, 0.4 / 3.0, -0.249 / 3.0]);
+    assert_eq!(server.round(), 1);
+
+    // a session weighting the updates by the sample counts agreed at setup, over two rounds
+    let samples = [10u64, 30, 60];
+    let weighting = Weighting::SampleCount(samples.to_vec());
+    let clients = FederatedClient::<L>::setup_weighted(SESSION, 1000, 1.0, &weighting).unwrap();
+    let mut server = FederatedServer::<L>::new(SESSION, 3, weighting);
+    let truth: Vec<f64> = (0..3)
+        .map(|j| (0..L).map(|i| samples[i] as f64 * updates[i][j]).sum::<f64>() / 100.0)
+        .collect();
+    for round in 0..2 {
+        for c in clients.iter() {
+            server.submit(c.encrypt_update(round, &updates[c.index]).unwrap()).unwrap();
+        }
+        assert_close(&aggregate(&clients, &mut server).unwrap(), &truth);
+    }
+    assert_eq!(server.round(), 2);
+}
+
+#[test]
+fn test_federated_fixed_weights() {
+    let weighting = Weighting::SampleCount(vec![1, 1, 1]);
+    let clients = FederatedClient::<L>::setup_weighted(SESSION, 100, 1.0, &weighting).unwrap();
+    let ones = vec![BigInt::from(1); L];
+    assert!(clients[2].key_share(&ones).is_ok());
+
+    // keys for (1, 1, 1) and (1, 1, 2) would reveal the update of the last client: the second vector is refused
+    let other = vec![BigInt::from(1), BigInt::from(1), BigInt::from(2)];
+    for c in clients.iter() {
+        assert_eq!(c.key_share(&other).err(), Some(FederatedError::InvalidWeights));
+    }
+    assert_eq!(
+        FederatedClient::<L>::setup_weighted(SESSION, 100, 1.0, &Weighting::SampleCount(vec![5, 0, 3])).err(),
+        Some(FederatedError::InvalidWeights)
+    );
+    assert_eq!(
+        FederatedClient::<L>::setup_weighted(SESSION, 100, 1.0, &Weighting::SampleCount(vec![5, 3])).err(),
+        Some(FederatedError::DimensionMismatch { expected: L, actual: 2 })
+    );
+}
+
+#[test]
+fn test_federated_rounds() {
+    let clients = FederatedClient::<L>::setup(SESSION, 100, 1.0);
+    let mut server = FederatedServer::<L>::new(SESSION, 2, Weighting::Uniform);
+
+    let stale = clients[0].encrypt_update(5, &[0.5, 0.5]).unwrap();
+    assert_eq!(server.submit(stale).err(), Some(FederatedError::WrongRound { expected: 0, actual: 5 }));
+    assert_eq!(
+        clients[1].encrypt_update(0, &[0.5, 1.5]).err(),
+        Some(FederatedError::UpdateOutOfBound { coordinate: 1, bound: 1.0 })
+    );
+    let short = clients[1].encrypt_update(0, &[0.5]).unwrap();
+    assert_eq!(server.submit(short).err(), Some(FederatedError::DimensionMismatch { expected: 2, actual: 1 }));
+
+    server.submit(clients[0].encrypt_update(0, &[0.5, -0.5]).unwrap()).unwrap();
+    let again = clients[0].encrypt_update(0, &[0.5, -0.5]).unwrap();
+    assert_eq!(server.submit(again).err(), Some(FederatedError::DuplicateUpdate(0)));
+    assert_eq!(server.missing(), vec![1, 2]);
+    assert_eq!(aggregate(&clients, &mut server).err(), Some(FederatedError::MissingUpdates(vec![1, 2])));
+
+    // a key isolating the update of a single client is refused
+    let weights = vec![BigInt::from(1), BigInt::from(0), BigInt::from(0)];
+    assert_eq!(clients[0].key_share(&weights).err(), Some(FederatedError::InvalidWeights));
+
+    // an update encrypted for the previous round does not decrypt in the next one
+    server.submit(clients[1].encrypt_update(0, &[0.25, 0.0]).unwrap()).unwrap();
+    server.submit(clients[2].encrypt_update(0, &[-0.25, 0.5]).unwrap()).unwrap();
+    assert_close(&aggregate(&clients, &mut server).unwrap(), &[0.5 / 3.0, 0.0]);
+    let mut replay = clients[2].encrypt_update(0, &[-0.25, 0.5]).unwrap();
+    replay.round = 1;
+    server.submit(clients[0].encrypt_update(1, &[0.0, 0.0]).unwrap()).unwrap();
+    server.submit(clients[1].encrypt_update(1, &[0.0, 0.0]).unwrap()).unwrap();
+    server.submit(replay).unwrap();
+    assert!(matches!(aggregate(&clients, &mut server), Err(FederatedError::Decrypt(_))));
+}
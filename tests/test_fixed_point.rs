@@ -0,0 +1,106 @@
+use num_bigint::{BigInt};
+
+use ruby::define::{G1};
+use ruby::math::fixed_point::{FixedPoint, FixedPointError, Fixed, Scale, decode};
+use ruby::simple_ip::Sip;
+use ruby::quadratic_sgp::Sgp;
+use ruby::dmcfe_ip::{Dmcfe, DmcfeCipher};
+use ruby::traits::FunctionalEncryption;
+
+fn assert_close(a: f64, b: f64, tol: f64) {
+    assert!((a - b).abs() < tol, "{} != {}", a, b);
+}
+
+#[test]
+fn test_fixed_point_codec() {
+    let codec = FixedPoint::new(1000);
+    assert_eq!(codec.encode(1.2345f64).unwrap(), BigInt::from(1235));
+    assert_eq!(codec.encode(-0.5f32).unwrap(), BigInt::from(-500));
+    assert_close(codec.decode(&BigInt::from(-1500)), -1.5, 1e-12);
+
+    let x: [BigInt; 3] = codec.encode_array(&[0.1f32, 0.2, 0.3]).unwrap();
+    assert_eq!(x[2], BigInt::from(300));
+    let res: Result<[BigInt; 2], _> = codec.encode_array(&[0.1f32, 0.2, 0.3]);
+    assert_eq!(res.err(), Some(FixedPointError::LengthMismatch { expected: 2, actual: 3 }));
+
+    assert!(matches!(codec.encode(f64::NAN), Err(FixedPointError::NotFinite(_))));
+    assert_eq!(codec.encode(1e80f64).err(), Some(FixedPointError::Overflow));
+
+    // Inner products compose to scale^2, quadratic forms to scale^3
+    let s = codec.scale();
+    let ip = s.compose(&s);
+    assert_eq!(ip.product(), BigInt::from(1_000_000));
+    let quad = ip.compose(&Scale::new(&BigInt::from(10)));
+    assert_eq!(quad.product(), BigInt::from(10_000_000));
+    assert_close(decode(&BigInt::from(25_000_000), &quad), 2.5, 1e-12);
+}
+
+#[test]
+fn test_fixed_point_sip() {
+    const L: usize = 4;
+    let sip = Sip::<L>::new();
+    let codec = FixedPoint::new(100);
+
+    let x: [f64; L] = [0.5, -1.25, 2.0, 0.07];
+    let y: [f32; L] = [1.5, 0.25, -0.75, 3.0];
+    let truth: f64 = x.iter().zip(y.iter()).map(|(a, &b)| a * b as f64).sum();
+
+    let cipher = sip.encrypt_fixed(&x, 2.0, &codec).unwrap();
+    let dk = sip.derive_fe_key_fixed(&y, &codec).unwrap();
+    let result = sip.decrypt_fixed(&cipher, &dk).unwrap();
+    assert_close(result, truth, 1e-3);
+
+    let res = sip.encrypt_fixed(&x, 1.0, &codec);
+    assert_eq!(res.err(), Some(FixedPointError::OutOfBound { bound: BigInt::from(100) }));
+}
+
+#[test]
+fn test_fixed_point_sgp() {
+    const L: usize = 2;
+    let sgp = Sgp::<L>::new();
+    let codec = FixedPoint::new(10);
+
+    let x = [0.5f64, -1.2];
+    let y = [1.1f64, 0.3];
+    let f = [1.0f64, 0.5, -0.5, 2.0];
+    let truth = x[0] * (f[0] * y[0] + f[1] * y[1]) + x[1] * (f[2] * y[0] + f[3] * y[1]);
+
+    let cipher = sgp.encrypt_fixed(&x, &codec, &y, &codec, 2.0).unwrap();
+    let dk = sgp.derive_fe_key_fixed(&f, 2, 2, &codec).unwrap();
+    assert_eq!(cipher.scale.compose(&dk.scale).product(), BigInt::from(1000));
+    let result = sgp.decrypt_fixed(&cipher, &dk).unwrap();
+    assert_close(result, truth, 1e-9);
+}
+
+#[test]
+fn test_fixed_point_dmcfe() {
+    const L: usize = 3;
+    let mut clients: Vec<Dmcfe<L>> = (0..L).map(Dmcfe::<L>::new_single).collect();
+    let pub_keys: Vec<G1> = clients.iter().map(|c| c.client_pub_key.clone()).collect();
+    for client in clients.iter_mut() {
+        client.set_share(&pub_keys);
+    }
+
+    let codec = FixedPoint::new(100);
+    let label = "dmcfe-fixed";
+    let x = [0.31f32, -0.42, 1.5];
+    let y = [1.0f32, 2.0, -0.5];
+    let truth: f32 = x.iter().zip(y.iter()).map(|(a, b)| a * b).sum();
+
+    let ciphers: Vec<Fixed<DmcfeCipher>> = (0..L).map(|i| clients[i].encrypt_single_fixed(x[i], 2.0, label, &codec).unwrap()).collect();
+    let shares: Vec<_> = clients.iter().map(|c| c.derive_fe_key_share_fixed(&y, &codec).unwrap()).collect();
+    let dk = clients[0].key_comb_fixed(&shares, &y, &codec).unwrap();
+    let result = clients[0].decrypt_with_label_fixed(&ciphers, &dk, label).unwrap();
+    assert_close(result, truth as f64, 1e-4);
+
+    let other = clients[1].encrypt_single_fixed(x[1], 2.0, label, &FixedPoint::new(10)).unwrap();
+    let mixed = vec![ciphers[0].clone(), other, ciphers[2].clone()];
+    let res = clients[0].decrypt_with_label_fixed(&mixed, &dk, label);
+    assert_eq!(res.err(), Some(FixedPointError::ScaleMismatch));
+
+    let client = Dmcfe::<L>::new();
+    let cipher = client.encrypt_fixed(&x, 2.0, label, &codec).unwrap();
+    let dk = client.derive_fe_key_fixed(&y, &codec).unwrap();
+    let result = client.decrypt_fixed(&cipher, &dk, label).unwrap();
+    assert_close(result, truth as f64, 1e-4);
+}
@@ -0,0 +1,98 @@
+use num_bigint::{BigInt};
+
+use ruby::math::matrix::{BigIntMatrix};
+use ruby::utils::{inner_product_result, quadratic_result};
+use ruby::simple_ip::Sip;
+use ruby::quadratic_sgp::{Sgp, SgpPlain};
+use ruby::key_issuance::{KeyIssuer, IssuancePolicy, IssuanceError};
+use ruby::traits::FunctionalEncryption;
+
+fn ints<const L: usize>(a: [i64; L]) -> [BigInt; L] {
+    array_init::array_init(|i| BigInt::from(a[i]))
+}
+
+#[test]
+fn test_key_issuance_sip() {
+    const L: usize = 3;
+    let mut issuer = KeyIssuer::new(Sip::<L>::new(), IssuancePolicy::new(2));
+
+    let y1 = ints([1, 0, 1]);
+    let y2 = ints([0, 1, 1]);
+    let y3 = ints([1, 1, 2]);
+    let y4 = ints([1, 1, 0]);
+    for y in [&y1, &y2, &y3, &y4].iter() {
+        issuer.approve(y).unwrap();
+    }
+
+    let x = ints([3, -4, 5]);
+    let cipher = issuer.scheme().encrypt(&x);
+
+    let dk = issuer.issue("alice", &y1).unwrap();
+    let result = issuer.scheme().decrypt(&cipher, &dk);
+    assert_eq!(result.unwrap(), inner_product_result(&x, &y1));
+    issuer.issue("alice", &y2).unwrap();
+    assert_eq!(issuer.rank("alice"), 2);
+
+    // y3 = y1 + y2 lies in the issued span and does not consume budget
+    issuer.issue("alice", &y3).unwrap();
+    assert_eq!(issuer.rank("alice"), 2);
+    assert_eq!(issuer.remaining_budget("alice"), 0);
+
+    // y4 would reveal x entirely
+    let res = issuer.issue("alice", &y4);
+    assert_eq!(res.err(), Some(IssuanceError::RequesterBudgetExceeded { requester: "alice".to_string(), rank: 3, budget: 2 }));
+
+    // Other requesters have their own budget
+    issuer.issue("bob", &y4).unwrap();
+    assert_eq!(issuer.rank("bob"), 1);
+    assert_eq!(issuer.total_rank(), 3);
+
+    let res = issuer.issue("alice", &ints([1, 2, 3]));
+    assert_eq!(res.err(), Some(IssuanceError::NotAllowListed));
+
+    assert_eq!(issuer.issued().len(), 4);
+    assert_eq!(issuer.issued_to("alice").count(), 3);
+}
+
+#[test]
+fn test_key_issuance_total_budget() {
+    const L: usize = 2;
+    let policy = IssuancePolicy {
+        max_rank_per_requester: 1,
+        max_rank_total: Some(1),
+    };
+    let mut issuer = KeyIssuer::new(Sip::<L>::new(), policy);
+    let y1 = ints([1, 2]);
+    let y2 = ints([2, 1]);
+    issuer.approve(&y1).unwrap();
+    issuer.approve(&y2).unwrap();
+
+    issuer.issue("alice", &y1).unwrap();
+    // Colluding requesters together would learn x
+    let res = issuer.issue("bob", &y2);
+    assert_eq!(res.err(), Some(IssuanceError::TotalBudgetExceeded { rank: 2, budget: 1 }));
+    // A scalar multiple reveals nothing new, also modulo the group order
+    let y3 = ints([-1, -2]);
+    issuer.approve(&y3).unwrap();
+    issuer.issue("bob", &y3).unwrap();
+    assert_eq!(issuer.total_rank(), 1);
+}
+
+#[test]
+fn test_key_issuance_sgp() {
+    const L: usize = 2;
+    let mut issuer = KeyIssuer::new(Sgp::<L>::new(), IssuancePolicy::new(3));
+
+    let f = BigIntMatrix::new_ints(&[1, 0, 0, 1], 2, 2);
+    issuer.approve(&f).unwrap();
+    let res = issuer.approve(&BigIntMatrix::new_ints(&[1, 0, 0], 1, 3));
+    assert_eq!(res.err(), Some(IssuanceError::DimensionMismatch { expected: 4, actual: 3 }));
+
+    let x = ints([2, -3]);
+    let plain = SgpPlain { x: x.clone(), y: x.clone() };
+    let cipher = issuer.scheme().encrypt(&plain);
+    let dk = issuer.issue("alice", &f).unwrap();
+    let result = issuer.scheme().decrypt(&cipher, &dk);
+    assert_eq!(result.unwrap(), quadratic_result(&x, &x, &f));
+    assert_eq!(issuer.rank("alice"), 1);
+}
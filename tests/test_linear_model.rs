@@ -0,0 +1,79 @@
+use std::fs;
+
+use ruby::ml::linear_model::{LinearModel, LinearModelService, LinearOutput, ModelError, Score};
+
+fn assert_scores(result: &[Score], truth: &[Score], eps: f64) {
+    assert_eq!(result.len(), truth.len());
+    for (r, t) in result.iter().zip(truth.iter()) {
+        assert_eq!(r.name, t.name);
+        assert!((r.value - t.value).abs() < eps, "{}: {} vs {}", r.name, r.value, t.value);
+    }
+}
+
+#[test]
+fn test_linear_model_json() {
+    let json = r#"{
+        "features": ["age", "bmi", "glucose"],
+        "scales": [10, 100, 100],
+        "weight_scale": 100,
+        "outputs": [
+            {"name": "risk", "weights": [0.02, 0.3, -1.25], "bias": -1.5},
+            {"name": "score", "weights": [-0.5, 0.0, 0.75]}
+        ]
+    }"#;
+    let model = LinearModel::from_json(json).unwrap();
+    assert_eq!(model.outputs[1].bias, 0.0);
+    assert_eq!(LinearModel::from_json(&model.to_json()).unwrap(), model);
+
+    // the service supports up to 4 features, the model has 3
+    let service = LinearModelService::<4>::new(model.clone(), "linear-json").unwrap();
+    let x = [42.5f64, 0.27, 1.13];
+    let cipher = service.encrypt(&x).unwrap();
+    let result = service.compute(&cipher).unwrap();
+    assert_scores(&result, &model.evaluate(&x).unwrap(), 1e-2);
+
+    assert_eq!(service.encrypt(&[1.0f64, 2.0]).err(), Some(ModelError::FeatureMismatch { expected: 3, actual: 2 }));
+    assert_eq!(
+        LinearModelService::<2>::new(model, "linear-json").err(),
+        Some(ModelError::TooManyFeatures { max: 2, actual: 3 })
+    );
+}
+
+#[test]
+fn test_linear_model_csv() {
+    let csv = "name,bias,a,b\nscale,10,100,10\nfirst,0.5,1.5,-2.0\n\nsecond,-1.0,0.25,3.5\n";
+    let path = std::env::temp_dir().join(format!("ruby_linear_model_{}.csv", std::process::id()));
+    fs::write(&path, csv).unwrap();
+    let model = LinearModel::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(model.features, vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(model.scales, vec![100, 10]);
+    assert_eq!(model.weight_scale, 10);
+    assert_eq!(model.outputs[1], LinearOutput { name: "second".to_string(), weights: vec![0.25, 3.5], bias: -1.0 });
+
+    let service = LinearModelService::<2>::new(model.clone(), "linear-csv").unwrap();
+    let x = [-0.37f32, 2.4];
+    let cipher = service.encrypt(&x).unwrap();
+    let result = service.compute(&cipher).unwrap();
+    // the weight of `a` only has scale 10
+    assert_scores(&result, &model.evaluate(&x).unwrap(), 5e-2);
+}
+
+#[test]
+fn test_linear_model_errors() {
+    let res = LinearModel::from_csv("name,bias,a,b\nfirst,0.5,1.5,x\n");
+    assert_eq!(res.err(), Some(ModelError::Csv { line: 2, message: "invalid number 'x'".to_string() }));
+
+    let res = LinearModel::from_csv("name,bias,a,b\nfirst,0.5,1.5\n");
+    assert!(matches!(res, Err(ModelError::Csv { line: 2, .. })));
+
+    let res = LinearModel::from_json(r#"{"features": ["a", "b"], "outputs": [{"name": "o", "weights": [1.0]}]}"#);
+    assert_eq!(res.err(), Some(ModelError::FeatureMismatch { expected: 2, actual: 1 }));
+
+    let res = LinearModel::from_json(r#"{"features": ["a", "b"], "scales": [100, 30], "outputs": [{"name": "o", "weights": [1.0, 2.0]}]}"#);
+    assert_eq!(res.err(), Some(ModelError::InvalidScale(30)));
+
+    assert!(matches!(LinearModel::from_json("{"), Err(ModelError::Json(_))));
+    assert!(matches!(LinearModel::load("/nonexistent/model.json"), Err(ModelError::Io(_))));
+}
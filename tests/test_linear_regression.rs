@@ -0,0 +1,83 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use ruby::define::{G2Vector};
+use ruby::ml::ModelError;
+use ruby::ml::federated::{FederatedError};
+use ruby::ml::linear_regression::{LinearRegression, RegressionAggregator, RegressionClient, SufficientStatistics};
+
+const L: usize = 3;
+
+/// `y = 1.5 x_0 - 0.7 x_1 + 2 + noise`, split across `L` holders.
+fn datasets(seed: u64) -> Vec<(Vec<Vec<f64>>, Vec<f64>)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..L)
+        .map(|h| {
+            let rows = 15 + 5 * h;
+            let x: Vec<Vec<f64>> = (0..rows).map(|_| vec![rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0)]).collect();
+            let y: Vec<f64> = x.iter().map(|r| 1.5 * r[0] - 0.7 * r[1] + 2.0 + rng.gen_range(-0.1..0.1)).collect();
+            (x, y)
+        })
+        .collect()
+}
+
+fn encrypted_fit(data: &[(Vec<Vec<f64>>, Vec<f64>)], ridge: f64) -> LinearRegression {
+    let holders = RegressionClient::<L>::setup("regression", 2, true, 1000, 1000.0);
+    let mut aggregator = RegressionAggregator::<L>::new("regression", 2, true, ridge);
+    for h in holders.iter() {
+        let (x, y) = &data[h.index()];
+        aggregator.submit(h.encrypt_statistics(aggregator.round(), x, y).unwrap()).unwrap();
+    }
+    let weights = aggregator.weights().unwrap();
+    let shares: Vec<G2Vector> = holders.iter().map(|h| h.key_share(&weights).unwrap()).collect();
+    aggregator.fit(&shares).unwrap()
+}
+
+fn plain_fit(data: &[(Vec<Vec<f64>>, Vec<f64>)], ridge: f64) -> LinearRegression {
+    let x: Vec<Vec<f64>> = data.iter().flat_map(|(x, _)| x.iter().cloned()).collect();
+    let y: Vec<f64> = data.iter().flat_map(|(_, y)| y.iter().cloned()).collect();
+    SufficientStatistics::from_data(&x, &y, true).unwrap().solve(ridge, true).unwrap()
+}
+
+fn assert_close(a: &LinearRegression, b: &LinearRegression, eps: f64) {
+    for (wa, wb) in a.weights.iter().zip(b.weights.iter()) {
+        assert!((wa - wb).abs() < eps, "{:?} vs {:?}", a, b);
+    }
+    assert!((a.intercept - b.intercept).abs() < eps, "{:?} vs {:?}", a, b);
+}
+
+#[test]
+fn test_linear_regression_encrypted() {
+    let data = datasets(1);
+    let plain = plain_fit(&data, 0.0);
+    assert_close(&plain, &LinearRegression { weights: vec![1.5, -0.7], intercept: 2.0 }, 0.05);
+    assert_close(&encrypted_fit(&data, 0.0), &plain, 1e-3);
+
+    // the ridge penalty shrinks the weights but not the intercept
+    let ridge = plain_fit(&data, 50.0);
+    assert!(ridge.weights[0].abs() < plain.weights[0].abs());
+    assert_close(&encrypted_fit(&data, 50.0), &ridge, 1e-3);
+}
+
+#[test]
+fn test_linear_regression_statistics() {
+    let x = vec![vec![1.0, 2.0], vec![3.0, 6.0], vec![-1.0, -2.0]];
+    let y = vec![1.0, 2.0, 3.0];
+    let stats = SufficientStatistics::from_data(&x, &y, false).unwrap();
+    assert_eq!(stats.to_vec(), vec![11.0, 22.0, 44.0, 4.0, 8.0]);
+    assert_eq!(SufficientStatistics::from_vec(2, &stats.to_vec()).unwrap(), stats);
+
+    // the second feature is a multiple of the first one, only the ridge penalty makes the solution unique
+    assert_eq!(stats.solve(0.0, false).err(), Some(ModelError::Singular));
+    assert!(stats.solve(1.0, false).is_ok());
+
+    let holders = RegressionClient::<L>::setup("regression-errors", 2, false, 100, 20.0);
+    assert_eq!(
+        holders[0].encrypt_statistics(0, &x, &y).err(),
+        Some(ModelError::Federated(FederatedError::UpdateOutOfBound { coordinate: 1, bound: 20.0 }))
+    );
+    assert_eq!(
+        holders[0].encrypt_statistics(0, &[vec![1.0]], &[1.0]).err(),
+        Some(ModelError::FeatureMismatch { expected: 2, actual: 1 })
+    );
+}
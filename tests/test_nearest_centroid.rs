@@ -0,0 +1,40 @@
+use num_bigint::{BigInt};
+
+use ruby::ml::ModelError;
+use ruby::ml::nearest_centroid::{NearestCentroid, squared_distances};
+use ruby::quadratic_sgp::{SgpCipher};
+
+fn argmin(v: &[f64]) -> usize {
+    (0..v.len()).fold(0, |best, i| if v[i] < v[best] { i } else { best })
+}
+
+#[test]
+fn test_nearest_centroid() {
+    const L: usize = 3;
+    let centroids = vec![vec![0.0, 0.0], vec![2.5, 1.0], vec![-3.0, 4.2]];
+    let service = NearestCentroid::<L>::new(centroids.clone(), 10, 5.0).unwrap();
+
+    let points = [[0.4, -0.3], [2.0, 0.5], [-1.7, 3.1], [1.5, 0.6]];
+    let ciphers: Vec<SgpCipher<L>> = points.iter().map(|x| service.encrypt(x).unwrap()).collect();
+    let assignment = service.assign_all(&ciphers).unwrap();
+    for (x, &a) in points.iter().zip(assignment.iter()) {
+        assert_eq!(a, service.assign_plain(x).unwrap());
+        assert_eq!(a, argmin(&squared_distances(x, &centroids)));
+    }
+    assert_eq!(assignment, vec![0, 1, 2, 1]);
+}
+
+#[test]
+fn test_nearest_centroid_errors() {
+    const L: usize = 3;
+    let service = NearestCentroid::<L>::new(vec![vec![1.0, 1.0]], 10, 2.0).unwrap();
+    assert_eq!(service.encode(&[0.5, -1.0]).unwrap(), [BigInt::from(5), BigInt::from(-10), BigInt::from(1)]);
+    assert_eq!(service.encrypt(&[0.5]).err(), Some(ModelError::FeatureMismatch { expected: 2, actual: 1 }));
+    assert_eq!(service.encrypt(&[2.5, 0.0]).err(), Some(ModelError::InputOutOfBound { bound: BigInt::from(20) }));
+
+    assert_eq!(
+        NearestCentroid::<L>::new(vec![vec![1.0, 1.0], vec![1.0]], 10, 2.0).err(),
+        Some(ModelError::FeatureMismatch { expected: 2, actual: 1 })
+    );
+    assert!(matches!(NearestCentroid::<L>::new(Vec::new(), 10, 2.0), Err(ModelError::Empty)));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "plonk")]
+
+use fawkes_crypto::{
+    backend::bellman_groth16::engines::Bls12_381,
+    core::sizedvec::SizedVec,
+    native::ecc::*,
+    rand::{thread_rng, Rng},
+    ff_uint::Num,
+};
+
+use num_bigint::BigInt;
+use ruby::math::matrix::BigIntMatrix;
+use ruby::zk::backend::{Backend, Groth16, Snark};
+use ruby::zk::dlog::ZkDlog;
+use ruby::zk::plonk::{Plonk, PlonkSrs};
+use ruby::zk::qp::ZkQp;
+use ruby::zk::sip::ZkSip;
+use ruby::zk::{ParamsError, VerifyError, Zk};
+use std::env;
+
+pub type Bn12381Fr = fawkes_crypto::engines::bls12_381::Fr;
+
+/// Degree of the SRS of the tests, that of the qp circuit of dimension 2, the largest one.
+const DEGREE: usize = 1 << 16;
+
+fn check_circuits_on<B: Backend<Fr = Bn12381Fr>>(srs: &B::Srs) {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = Bls12_381::jubjub_params();
+
+    let g = EdwardsPoint::<Bn12381Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let x: Num<Bn12381Fr> = rng.gen();
+    let y = g.mul(x.to_other_reduced(), &jubjub_params);
+    let dlog_params = ZkDlog::<Bls12_381>::setup_on::<B>(srs).unwrap();
+    let dlog = ZkDlog::<Bls12_381>::generate_on(&dlog_params, &g, &x).unwrap();
+    assert_eq!(ZkDlog::<Bls12_381>::verify_on::<B>(&dlog_params.vk(), &g, &y, &dlog), Ok(()));
+    assert_eq!(
+        ZkDlog::<Bls12_381>::verify_on::<B>(&dlog_params.vk(), &g, &g, &dlog),
+        Err(VerifyError::InputMismatch { input: "y".to_string() })
+    );
+    // the proof of one statement with the public inputs of another
+    let other: Num<Bn12381Fr> = rng.gen();
+    let other_y = g.mul(other.to_other_reduced(), &jubjub_params);
+    let forged = Snark::<B> {
+        inputs: ZkDlog::<Bls12_381>::generate_on(&dlog_params, &g, &other).unwrap().inputs,
+        proof: dlog.proof,
+    };
+    assert_eq!(ZkDlog::<Bls12_381>::verify_on::<B>(&dlog_params.vk(), &g, &other_y, &forged), Err(VerifyError::InvalidProof));
+
+    let sk: Num<Bn12381Fr> = rng.gen();
+    let h = g.mul(sk.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Bn12381Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let t: SizedVec<Num<Bn12381Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let sip_params = ZkSip::<N, Bls12_381>::setup_on::<B>(srs).unwrap();
+    let (sip_public, sip) = ZkSip::<N, Bls12_381>::prove_on(&sip_params, &g, &h, &s, &t).unwrap();
+    assert_eq!(ZkSip::<N, Bls12_381>::verify_on::<B>(&sip_params.vk(), &sip_public, &sip), Ok(()));
+
+    let bigint_f = BigIntMatrix::new_random(N, N, &BigInt::from(-64), &BigInt::from(64));
+    let qp_params = ZkQp::<N, Bls12_381>::setup_on::<B>(srs).unwrap();
+    let (qp_public, qp) = ZkQp::<N, Bls12_381>::prove_on(&qp_params, &g, &h, &s, &t, &bigint_f).unwrap();
+    assert_eq!(ZkQp::<N, Bls12_381>::verify_on::<B>(&qp_params.vk(), &qp_public, &qp), Ok(()));
+    let mut tampered = qp_public.clone();
+    tampered.c2 = tampered.c1;
+    assert_eq!(
+        ZkQp::<N, Bls12_381>::verify_on::<B>(&qp_params.vk(), &tampered, &qp),
+        Err(VerifyError::InputMismatch { input: "c2".to_string() })
+    );
+}
+
+#[test]
+fn test_plonk_circuits_groth16() {
+    check_circuits_on::<Groth16<Bls12_381>>(&());
+}
+
+#[test]
+fn test_plonk_circuits_plonk() {
+    let srs = PlonkSrs::generate(DEGREE);
+    let path = env::temp_dir().join(format!("ruby-plonk-{}.srs", std::process::id()));
+    srs.save(&path).unwrap();
+    let loaded = PlonkSrs::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.max_degree(), DEGREE);
+    assert_eq!(loaded.to_bytes(), srs.to_bytes());
+
+    check_circuits_on::<Plonk>(&loaded);
+}
+
+#[test]
+fn test_plonk_srs() {
+    let srs = PlonkSrs::generate(1 << 10);
+    match ZkDlog::<Bls12_381>::setup_on::<Plonk>(&srs) {
+        Err(ParamsError::SrsTooSmall { degree, max_degree }) => {
+            assert_eq!(max_degree, 1 << 10);
+            assert!(degree > max_degree && degree <= DEGREE);
+        }
+        _ => panic!("the setup must fail"),
+    }
+
+    assert!(matches!(PlonkSrs::from_bytes(&srs.to_bytes()[..32]), Err(ParamsError::Io(_))));
+}
@@ -0,0 +1,121 @@
+use num_bigint::{BigInt};
+use std::fs;
+use std::path::PathBuf;
+
+use ruby::ml::ModelError;
+use ruby::ml::neural_network::NeuralNetwork;
+use ruby::ml::npy::{NpyArray};
+use ruby::ml::quadratic_network::{QuadraticWeights, QuantizedNetwork};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ruby_quadratic_{}_{}", std::process::id(), name))
+}
+
+fn argmax(v: &[f64]) -> usize {
+    (0..v.len()).fold(0, |best, i| if v[i] > v[best] { i } else { best })
+}
+
+fn weights() -> QuadraticWeights {
+    let json = r#"{
+        "classes": ["low", "high", "mixed"],
+        "p": [[0.21, -0.1, 0.05], [0.0, 0.17, -0.12], [-0.08, 0.03, 0.2], [0.11, 0.14, 0.0]],
+        "q": [
+            [[0.3, -0.1, 0.0], [-0.1, -0.2, 0.05], [0.0, 0.05, 0.1]],
+            [[-0.25, 0.0, 0.12], [0.0, 0.28, -0.04], [0.12, -0.04, 0.0]],
+            [[0.02, 0.21, -0.3], [0.21, 0.0, 0.0], [-0.3, 0.0, 0.19]]
+        ]
+    }"#;
+    QuadraticWeights::from_json(json).unwrap()
+}
+
+#[test]
+fn test_npy() {
+    let array = NpyArray::new(vec![2, 3], vec![1.5, -2.0, 0.25, 3.0, 0.0, -1.0]);
+    let bytes = array.to_bytes();
+    assert_eq!(bytes.len() % 64, (6 * 8) % 64);
+    assert_eq!(NpyArray::from_bytes(&bytes).unwrap(), array);
+
+    // a Fortran-ordered float32 array, as written by numpy
+    let header = "{'descr': '<f4', 'fortran_order': True, 'shape': (2, 3), }";
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for v in [1.5f32, 3.0, -2.0, 0.0, 0.25, -1.0].iter() {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    assert_eq!(NpyArray::from_bytes(&bytes).unwrap(), array);
+
+    let header = "{'descr': '<c16', 'fortran_order': False, 'shape': (1,), }";
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    assert_eq!(NpyArray::from_bytes(&bytes).err(), Some(ModelError::Npy("unsupported dtype '<c16'".to_string())));
+    assert!(matches!(NpyArray::from_bytes(b"not a numpy file"), Err(ModelError::Npy(_))));
+}
+
+#[test]
+fn test_quadratic_network_import() {
+    const L: usize = 4;
+    let weights = weights();
+    let (network, report) = weights.quantize(10, 10).unwrap();
+    assert!(report.max_abs() <= 0.05 + 1e-12);
+    assert!(report.p.rms <= report.p.max_abs);
+    assert_eq!(report.q.len(), 3);
+
+    // the integer model survives serialization
+    let path = temp_path("network.json");
+    network.save(&path).unwrap();
+    let loaded = QuantizedNetwork::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, network);
+
+    // inputs with scale 10
+    let x = [2.3f64, -1.1, 0.7, 3.4];
+    let x_int: [BigInt; L] = [BigInt::from(23), BigInt::from(-11), BigInt::from(7), BigInt::from(34)];
+
+    let service = NeuralNetwork::<L>::from_quantized(&loaded, &BigInt::from(100)).unwrap();
+    assert_eq!(service.bound, BigInt::from(100));
+    assert_eq!(NeuralNetwork::<3>::from_quantized(&loaded, &BigInt::from(100)).err(), Some(ModelError::FeatureMismatch { expected: 3, actual: L }));
+    let result = service.compute(&service.encrypt(&x_int));
+
+    let truth = weights.evaluate(&x).unwrap();
+    let scale = loaded.result_scale(10);
+    let scores: Vec<f64> = result
+        .iter()
+        .map(|r| r.to_string().parse::<f64>().unwrap() / scale.to_string().parse::<f64>().unwrap())
+        .collect();
+    for (s, t) in scores.iter().zip(truth.iter()) {
+        // the error of the weights is at most 0.05, amplified by the inputs
+        assert!((s - t).abs() < 0.5, "{} vs {}", s, t);
+    }
+    assert_eq!(argmax(&scores), argmax(&truth));
+}
+
+#[test]
+fn test_quadratic_network_npy() {
+    let weights = weights();
+    let (n, d) = (weights.n(), weights.d());
+    let p = NpyArray::new(vec![n, d], weights.p.concat());
+    let q = NpyArray::new(vec![3, d, d], weights.q.iter().map(|qi| qi.concat()).collect::<Vec<_>>().concat());
+
+    let (p_path, q_path) = (temp_path("p.npy"), temp_path("q.npy"));
+    p.save(&p_path).unwrap();
+    q.save(&q_path).unwrap();
+    let loaded = QuadraticWeights::load_npy(&p_path, &q_path).unwrap();
+    fs::remove_file(&p_path).unwrap();
+    fs::remove_file(&q_path).unwrap();
+
+    assert_eq!(loaded.p, weights.p);
+    assert_eq!(loaded.q, weights.q);
+    assert_eq!(loaded.classes, vec!["0".to_string(), "1".to_string(), "2".to_string()]);
+
+    let q_bad = NpyArray::new(vec![2, d, d + 1], vec![0.0; 2 * d * (d + 1)]);
+    assert_eq!(
+        QuadraticWeights::from_arrays(&p, &q_bad).err(),
+        Some(ModelError::Shape { expected: vec![2, d, d], actual: vec![2, d, d + 1] })
+    );
+    assert_eq!(weights.quantize(0, 10).err(), Some(ModelError::InvalidScale(0)));
+    let mut large = weights;
+    large.q[1][0][0] = 1e6;
+    assert_eq!(large.quantize(10, 1 << 50).err(), Some(ModelError::InvalidScale(1 << 50)));
+}
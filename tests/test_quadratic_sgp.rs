@@ -4,6 +4,7 @@ use ruby::math::matrix::{BigIntMatrix};
 use ruby::utils::{quadratic_result};
 use ruby::utils::rand_utils::{RandUtilsRng, Sample};
 use ruby::quadratic_sgp::{Sgp, SgpPlain};
+use ruby::bounds::DecryptError;
 use ruby::traits::FunctionalEncryption;
 
 #[test]
@@ -26,9 +27,9 @@ fn test_sgp_1() {
     let plain = SgpPlain {x, y};
     let cipher = sgp.encrypt(&plain);
     let dk = sgp.derive_fe_key(&f);
-    let result = sgp.decrypt(&cipher, &dk, &BigInt::from(100)); 
+    let result = sgp.decrypt(&cipher, &dk); 
 
-    assert!(result.is_some());
+    assert!(result.is_ok());
     assert_eq!(result.unwrap(), plain_result);
 }
 
@@ -42,7 +43,8 @@ fn test_sgp_2() {
     let low = (-bound).to_bigint().unwrap();
     let high = bound.to_bigint().unwrap();
 
-    let sgp = Sgp::<L>::new();
+    let mut sgp = Sgp::<L>::new();
+    sgp.set_bound(&high, &high);
 
     let x: [BigInt; L] = rng.sample_range_array::<L>(&low, &high); 
     let y: [BigInt; L] = rng.sample_range_array::<L>(&low, &high);
@@ -62,11 +64,45 @@ fn test_sgp_2() {
     println!("[Quadratic Derive]: {:.2?}", elapsed);
 
     let now = Instant::now();
-    let result = sgp.decrypt(&cipher, &dk, &BigInt::from(bound)); 
+    let result = sgp.decrypt(&cipher, &dk); 
     let elapsed = now.elapsed();
     println!("[Quadratic Decrypt]: {:.2?}", elapsed);
 
-    assert!(result.is_some());
+    assert!(result.is_ok());
     assert_eq!(result.unwrap(), plain_result);
 }
 
+
+#[test]
+fn test_sgp_bounds() {
+    const L: usize = 2;
+    let sgp = Sgp::<L>::new();
+
+    let x = [BigInt::from(40), BigInt::from(-3)];
+    let y = [BigInt::from(2), BigInt::from(1)];
+    let f = BigIntMatrix::new_ints(&[1, -1, 0, 2][..], 2, 2);
+    let plain_result = quadratic_result(&x, &y, &f);
+
+    let plain = SgpPlain {x: x.clone(), y: y.clone()};
+    let mut cipher = sgp.encrypt_with_bound(&plain, &BigInt::from(50), &BigInt::from(2));
+    let dk = sgp.derive_fe_key(&f);
+    assert_eq!(dk.bound, BigInt::from(2));
+    assert_eq!(sgp.decrypt(&cipher, &dk).unwrap(), plain_result);
+
+    // projecting with p scales the bounds by its largest absolute column sum
+    let p = BigIntMatrix::new_ints(&[1, 0, -1, 1][..], 2, 2);
+    let projected = sgp.project(&cipher, &p);
+    assert_eq!(projected.x_bound, BigInt::from(100));
+    assert_eq!(projected.y_bound, BigInt::from(4));
+    let dk = sgp.derive_fe_key_projected(&f, &p);
+    let px: Vec<BigInt> = (0..2).map(|j| &x[0] * p.get_element(0, j) + &x[1] * p.get_element(1, j)).collect();
+    let py: Vec<BigInt> = (0..2).map(|j| &y[0] * p.get_element(0, j) + &y[1] * p.get_element(1, j)).collect();
+    assert_eq!(sgp.decrypt(&projected, &dk).unwrap(), quadratic_result(&px, &py, &f));
+
+    cipher.x_bound = BigInt::from(1);
+    let dk = sgp.derive_fe_key(&f);
+    assert_eq!(sgp.decrypt(&cipher, &dk), Err(DecryptError::OutOfRange { range: BigInt::from(16) }));
+
+    let dk = Sgp::<3>::new().derive_fe_key(&BigIntMatrix::new_ints(&[1; 9][..], 3, 3));
+    assert_eq!(sgp.decrypt(&cipher, &dk), Err(DecryptError::DimensionMismatch { expected: 2, actual: 3 }));
+}
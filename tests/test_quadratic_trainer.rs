@@ -0,0 +1,98 @@
+use num_bigint::{BigInt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use ruby::bounds::{DecryptError};
+use ruby::ml::ModelError;
+use ruby::ml::neural_network::NeuralNetwork;
+use ruby::ml::quadratic_network::{QuantizedNetwork};
+use ruby::ml::quadratic_trainer::{Optimizer, QuadraticTrainer, accuracy, cross_entropy};
+
+const N: usize = 4;
+const CLASSES: usize = 3;
+
+/// Samples of class `c` are large in coordinate `c`, with a random sign. Quadratic scores are even functions of
+/// the input, so the classes must not be separated by the sign alone.
+fn synthetic(samples: usize, seed: u64) -> (Vec<Vec<f64>>, Vec<usize>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut x = Vec::with_capacity(samples);
+    let mut labels = Vec::with_capacity(samples);
+    for s in 0..samples {
+        let c = s % CLASSES;
+        let mut xs: Vec<f64> = (0..N).map(|_| rng.gen_range(-0.3..0.3)).collect();
+        let sign = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+        xs[c] += sign * rng.gen_range(1.5..2.5);
+        x.push(xs);
+        labels.push(c);
+    }
+    (x, labels)
+}
+
+/// Integer scores `(P^T x)^T Q_i (P^T x)` of the quantized network.
+fn integer_scores(network: &QuantizedNetwork, x: &[i64]) -> Vec<BigInt> {
+    let (n, d) = (network.n, network.d);
+    let z: Vec<i64> = (0..d).map(|j| (0..n).map(|i| x[i] * network.p[i * d + j]).sum()).collect();
+    network.q
+        .iter()
+        .map(|qi| BigInt::from((0..d).map(|j| (0..d).map(|k| z[j] * qi[j * d + k] * z[k]).sum::<i64>()).sum::<i64>()))
+        .collect()
+}
+
+#[test]
+fn test_quadratic_trainer() {
+    let (x, labels) = synthetic(150, 1);
+    let (x_test, labels_test) = synthetic(60, 2);
+
+    let mut trainer = QuadraticTrainer::new(3, Optimizer::adam(0.05));
+    trainer.epochs = 0;
+    let initial = trainer.train(&x, &labels, CLASSES).unwrap();
+    trainer.epochs = 40;
+    let adam = trainer.train(&x, &labels, CLASSES).unwrap();
+    assert!(cross_entropy(&adam, &x, &labels).unwrap() < cross_entropy(&initial, &x, &labels).unwrap() / 2.0);
+    assert!(accuracy(&adam, &x_test, &labels_test).unwrap() >= 0.9);
+
+    trainer.optimizer = Optimizer::Sgd { learning_rate: 0.1 };
+    let sgd = trainer.train(&x, &labels, CLASSES).unwrap();
+    assert!(cross_entropy(&sgd, &x, &labels).unwrap() < cross_entropy(&initial, &x, &labels).unwrap());
+    assert!(accuracy(&sgd, &x_test, &labels_test).unwrap() >= 0.8);
+
+    assert_eq!(trainer.train(&x, &vec![3; x.len()], CLASSES).err(), Some(ModelError::InvalidLabel(3)));
+    assert_eq!(trainer.train(&x, &labels, 1).err(), Some(ModelError::UnsupportedOutputs(1)));
+    assert_eq!(trainer.train(&x, &labels[1..], CLASSES).err(), Some(ModelError::FeatureMismatch { expected: 150, actual: 149 }));
+}
+
+#[test]
+fn test_quadratic_trainer_sgp() {
+    let (x, labels) = synthetic(150, 3);
+    let weights = QuadraticTrainer::new(3, Optimizer::adam(0.05)).train(&x, &labels, CLASSES).unwrap();
+
+    // inputs are encoded with scale 4, so they are bounded by 16
+    let x_bound = BigInt::from(16);
+    let max_range = BigInt::from(1u64 << 26);
+    let (network, report) = weights.quantize_bounded(&x_bound, &max_range).unwrap();
+    assert!(network.decryption_range(&x_bound) <= max_range);
+    assert!(network.p_scale > 1);
+    assert!(report.max_abs() <= 0.5 / network.p_scale as f64);
+
+    let service = NeuralNetwork::<N>::from_quantized(&network, &x_bound).unwrap();
+    let (x_test, labels_test) = synthetic(6, 4);
+    let mut correct = 0;
+    for (xs, &l) in x_test.iter().zip(labels_test.iter()) {
+        let x_int: Vec<i64> = xs.iter().map(|xi| (xi * 4.0).round() as i64).collect();
+        let plain: [BigInt; N] = array_init::array_init(|i| BigInt::from(x_int[i]));
+        let result = service.compute(&service.encrypt(&plain));
+        let truth = integer_scores(&network, &x_int);
+        assert_eq!(result, truth);
+
+        let class = (0..CLASSES).fold(0, |best, i| if result[i] > result[best] { i } else { best });
+        if class == l {
+            correct += 1;
+        }
+    }
+    assert!(correct >= 5, "{} of 6 correct", correct);
+
+    assert!(matches!(
+        weights.quantize_bounded(&x_bound, &BigInt::from(10)),
+        Err(ModelError::Decrypt(DecryptError::RangeTooLarge { .. }))
+    ));
+}
@@ -0,0 +1,114 @@
+use num_bigint::{BigInt};
+
+use ruby::define::{G1, G2, MODULUS};
+use ruby::dmcfe_ip::Dmcfe;
+use ruby::simple_ip::Sip;
+use ruby::traits::FunctionalEncryption;
+use ruby::utils::rand_utils::{RandUtilsRng, Sample};
+use ruby::zk::VerifyError;
+use ruby::zk::sigma::{LinearRelation, SigmaGroup, SigmaProof, prove_dlog, prove_dlog_equality, prove_dmcfe_pub_key,
+                      prove_sip_mpk, verify_dlog, verify_dlog_equality, verify_dmcfe_pub_key, verify_sip_mpk};
+
+#[test]
+fn test_sigma_dlog() {
+    let mut rng = RandUtilsRng::new();
+    let x: BigInt = rng.sample(&MODULUS);
+
+    let g1 = G1::generator();
+    let y1 = g1.mul_scalar(&x);
+    let proof = prove_dlog(&g1, &x, "test");
+    assert_eq!(verify_dlog(&g1, &y1, &proof, "test"), Ok(()));
+    assert_eq!(verify_dlog(&g1, &y1, &proof, "other"), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_dlog(&g1, &g1, &proof, "test"), Err(VerifyError::InvalidProof));
+
+    let g2 = G2::generator();
+    let y2 = g2.mul_scalar(&x);
+    let proof = prove_dlog(&g2, &x, "test");
+    assert_eq!(verify_dlog(&g2, &y2, &proof, "test"), Ok(()));
+    let mut tampered = proof.clone();
+    tampered.z[0] += 1;
+    assert_eq!(verify_dlog(&g2, &y2, &tampered, "test"), Err(VerifyError::InvalidProof));
+}
+
+#[test]
+fn test_sigma_dlog_equality() {
+    let mut rng = RandUtilsRng::new();
+    let x: BigInt = rng.sample(&MODULUS);
+    let g = G1::generator();
+    let h = g.mul_scalar(&rng.sample(&MODULUS));
+
+    let proof = prove_dlog_equality(&g, &h, &x, "test");
+    assert_eq!(verify_dlog_equality(&g, &g.mul_scalar(&x), &h, &h.mul_scalar(&x), &proof, "test"), Ok(()));
+    let other = &x + 1;
+    assert_eq!(
+        verify_dlog_equality(&g, &g.mul_scalar(&x), &h, &h.mul_scalar(&other), &proof, "test"),
+        Err(VerifyError::InvalidProof)
+    );
+}
+
+#[test]
+fn test_sigma_linear_relation() {
+    let mut rng = RandUtilsRng::new();
+    let (x, r): (BigInt, BigInt) = (rng.sample(&MODULUS), rng.sample(&MODULUS));
+    let g = G1::generator();
+    let h = g.mul_scalar(&rng.sample(&MODULUS));
+
+    // a Pedersen commitment to x and a public key for the same x
+    let c = g.mul_scalar(&x).add_point(&h.mul_scalar(&r));
+    let relation = LinearRelation::new(2)
+        .equation(c, vec![(0, g.clone()), (1, h.clone())])
+        .equation(g.mul_scalar(&x), vec![(0, g.clone())]);
+    let proof = relation.prove(&[x.clone(), r.clone()], "test");
+    assert_eq!(relation.verify(&proof, "test"), Ok(()));
+
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), 3 * 32);
+    assert_eq!(SigmaProof::from_bytes(&bytes), Some(proof.clone()));
+    assert_eq!(SigmaProof::from_bytes(&bytes[1..]), None);
+
+    let short = SigmaProof { e: proof.e.clone(), z: vec![proof.z[0].clone()] };
+    assert_eq!(relation.verify(&short, "test"), Err(VerifyError::InputLength { expected: 2, actual: 1 }));
+}
+
+#[test]
+#[should_panic(expected = "Malformed input")]
+fn test_sigma_wrong_witness() {
+    let g = G1::generator();
+    let x = BigInt::from(5);
+    LinearRelation::new(1).equation(g.mul_scalar(&x), vec![(0, g)]).prove(&[BigInt::from(6)], "test");
+}
+
+#[test]
+fn test_sigma_sip_mpk() {
+    const L: usize = 3;
+    let sip = Sip::<L>::new();
+    let proof = prove_sip_mpk(&sip);
+    assert_eq!(proof.z.len(), L);
+    assert_eq!(verify_sip_mpk(sip.mpk(), &proof), Ok(()));
+    assert_eq!(verify_sip_mpk(Sip::<L>::new().mpk(), &proof), Err(VerifyError::InvalidProof));
+}
+
+#[test]
+fn test_sigma_sip_dk() {
+    const L: usize = 3;
+    let sip = Sip::<L>::new();
+    let y = [BigInt::from(3), BigInt::from(-7), BigInt::from(12)];
+    let dk = sip.derive_fe_key(&y);
+
+    // a key checked against a well-formed master public key is bound to its master secret key
+    assert_eq!(verify_sip_mpk(sip.mpk(), &prove_sip_mpk(&sip)), Ok(()));
+    assert!(sip.mpk().verify_dk(&dk));
+    assert!(!Sip::<L>::new().mpk().verify_dk(&dk));
+}
+
+#[test]
+fn test_sigma_dmcfe_pub_key() {
+    const L: usize = 2;
+    let clients: Vec<Dmcfe<L>> = (0..L).map(Dmcfe::<L>::new_single).collect();
+    let proof = prove_dmcfe_pub_key(&clients[0]);
+    assert_eq!(verify_dmcfe_pub_key(0, &clients[0].client_pub_key, &proof), Ok(()));
+    // a proof is bound to the index and to the key of the client
+    assert_eq!(verify_dmcfe_pub_key(1, &clients[0].client_pub_key, &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_dmcfe_pub_key(0, &clients[1].client_pub_key, &proof), Err(VerifyError::InvalidProof));
+    assert_eq!(verify_dmcfe_pub_key(1, &clients[1].client_pub_key, &prove_dmcfe_pub_key(&clients[1])), Ok(()));
+}
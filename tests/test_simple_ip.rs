@@ -3,6 +3,7 @@ use num_bigint::{BigInt, ToBigInt};
 use ruby::utils::{inner_product_result};
 use ruby::utils::rand_utils::{RandUtilsRng, Sample};
 use ruby::simple_ip::Sip;
+use ruby::bounds::DecryptError;
 use ruby::traits::FunctionalEncryption;
 
 
@@ -16,7 +17,8 @@ fn test_sip() {
     let low = (-bound).to_bigint().unwrap();
     let high = bound.to_bigint().unwrap();
 
-    let sip = Sip::<L>::new();
+    let mut sip = Sip::<L>::new();
+    sip.set_bound(&high);
 
     let x: [BigInt; L] = rng.sample_range_array::<L>(&low, &high); 
     let y: [BigInt; L] = rng.sample_range_array::<L>(&low, &high);
@@ -34,11 +36,40 @@ fn test_sip() {
     println!("[SIP Derive FE Key]: {:.2?}", elapsed);
 
     let now = Instant::now();
-    let result = sip.decrypt(&cipher, &dk, &BigInt::from(bound)); 
+    let result = sip.decrypt(&cipher, &dk); 
     let elapsed = now.elapsed();
     println!("[SIP Decrypt]: {:.2?}", elapsed);
 
-    assert!(result.is_some());
+    assert!(result.is_ok());
     assert_eq!(result.unwrap(), plain_result);
 }
 
+
+#[test]
+fn test_sip_bounds() {
+    const L: usize = 3;
+    let sip = Sip::<L>::new();
+
+    // x and y have very different ranges: the result range is 1000 * 2 * 3 instead of 1000^2 * 3
+    let x = [BigInt::from(-900), BigInt::from(1000), BigInt::from(35)];
+    let y = [BigInt::from(1), BigInt::from(-2), BigInt::from(0)];
+    let mut cipher = sip.encrypt_with_bound(&x, &BigInt::from(1000));
+    let dk = sip.derive_fe_key(&y);
+    assert_eq!(cipher.bound, BigInt::from(1000));
+    assert_eq!(dk.bound, BigInt::from(2));
+    assert_eq!(sip.decrypt(&cipher, &dk).unwrap(), inner_product_result(&x, &y));
+
+    cipher.bound = BigInt::from(10);
+    assert_eq!(sip.decrypt(&cipher, &dk), Err(DecryptError::OutOfRange { range: BigInt::from(60) }));
+
+    // encrypt carries the declared bound of the scheme, not the largest entry of x
+    let mut sip = sip;
+    sip.set_bound(&BigInt::from(2000));
+    let cipher = sip.encrypt(&x);
+    assert_eq!(cipher.bound, BigInt::from(2000));
+    assert_eq!(sip.decrypt(&cipher, &dk).unwrap(), inner_product_result(&x, &y));
+
+    // an entry above the bound is an error of try_encrypt_with_bound rather than a panic
+    assert!(sip.try_encrypt_with_bound(&x, &BigInt::from(1000)).is_ok());
+    assert_eq!(sip.try_encrypt_with_bound(&x, &BigInt::from(999)).err(), Some(DecryptError::OutOfRange { range: BigInt::from(999) }));
+}
@@ -0,0 +1,48 @@
+use num_bigint::{BigInt};
+
+use ruby::simple_ip::Sip;
+use ruby::traits::FunctionalEncryption;
+use ruby::zk::VerifyError;
+use ruby::zk::sip_range::{SipEncryptionProof};
+
+#[test]
+fn test_sip_encryption_proof() {
+    const L: usize = 4;
+    let sip = Sip::<L>::new();
+    let bound = BigInt::from(100);
+    let x = [BigInt::from(100), BigInt::from(-100), BigInt::from(0), BigInt::from(37)];
+    let y = [BigInt::from(2), BigInt::from(1), BigInt::from(-5), BigInt::from(3)];
+
+    let (mut cipher, proof) = SipEncryptionProof::prove(sip.mpk(), &x, &bound);
+    assert_eq!(proof.verify(sip.mpk(), &cipher), Ok(()));
+    assert_eq!(sip.decrypt(&cipher, &sip.derive_fe_key(&y)).unwrap(), BigInt::from(211));
+
+    // the proof is bound to the ciphertext, its bound and the master public key
+    let (other, _) = SipEncryptionProof::prove(sip.mpk(), &x, &bound);
+    assert_eq!(proof.verify(sip.mpk(), &other), Err(VerifyError::InvalidProof));
+    assert_eq!(proof.verify(Sip::<L>::new().mpk(), &cipher), Err(VerifyError::InvalidProof));
+    cipher.bound = BigInt::from(127);
+    assert_eq!(proof.verify(sip.mpk(), &cipher), Err(VerifyError::InvalidProof));
+    cipher.bound = BigInt::from(1000);
+    assert_eq!(proof.verify(sip.mpk(), &cipher), Err(VerifyError::InputLength { expected: 11, actual: 8 }));
+}
+
+#[test]
+fn test_sip_encryption_proof_bounds() {
+    const L: usize = 2;
+    let sip = Sip::<L>::new();
+    for b in [0, 1, 2, 3, 4, 7, 8, 255].iter() {
+        let bound = BigInt::from(*b);
+        for x in [[-bound.clone(), bound.clone()], [BigInt::from(0), -bound.clone()]].iter() {
+            let (cipher, proof) = SipEncryptionProof::prove(sip.mpk(), x, &bound);
+            assert_eq!(proof.verify(sip.mpk(), &cipher), Ok(()), "x = {:?}, bound = {}", x, bound);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "Malformed input")]
+fn test_sip_encryption_proof_out_of_bound() {
+    let sip = Sip::<2>::new();
+    SipEncryptionProof::prove(sip.mpk(), &[BigInt::from(5), BigInt::from(6)], &BigInt::from(5));
+}
@@ -0,0 +1,81 @@
+use num_bigint::{BigInt};
+
+use ruby::ml::ModelError;
+use ruby::ml::statistics::{StatisticsClient, StatisticsService};
+
+fn assert_close(a: &[f64], b: &[f64], eps: f64) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b.iter()) {
+        assert!((x - y).abs() < eps, "{:?} vs {:?}", a, b);
+    }
+}
+
+fn plain_covariance(rows: &[Vec<f64>]) -> (Vec<f64>, Vec<f64>) {
+    let n = rows.len() as f64;
+    let d = rows[0].len();
+    let mean: Vec<f64> = (0..d).map(|j| rows.iter().map(|r| r[j]).sum::<f64>() / n).collect();
+    let mut covariance = vec![0.0; d * d];
+    for j in 0..d {
+        for k in 0..d {
+            covariance[j * d + k] = rows.iter().map(|r| (r[j] - mean[j]) * (r[k] - mean[k])).sum::<f64>() / (n - 1.0);
+        }
+    }
+    (mean, covariance)
+}
+
+#[test]
+fn test_statistics() {
+    const L: usize = 3;
+    let rows = vec![
+        vec![1.5, -2.0, 3.1],
+        vec![0.4, 1.2, -0.7],
+        vec![-3.3, 0.8, 2.2],
+        vec![2.0, -1.1, 0.0],
+    ];
+    let service = StatisticsService::<L>::new(10, 5.0);
+    // the data owner only holds the master public keys
+    let client = StatisticsClient::<L>::new(service.sip_mpk(), service.sgp_pk(), 10, 5.0);
+    let data = client.encrypt(&rows).unwrap();
+    assert_eq!((data.rows(), data.columns()), (4, 3));
+
+    let (mean, covariance) = plain_covariance(&rows);
+    assert_close(&service.sum(&data).unwrap(), &[0.6, -1.1, 4.6], 1e-9);
+    assert_close(&service.mean(&data).unwrap(), &mean, 1e-9);
+    assert_close(&service.covariance(&data).unwrap(), &covariance, 1e-9);
+
+    let description = service.describe(&data).unwrap();
+    assert_eq!(description.count, 4);
+    assert_close(&description.mean, &mean, 1e-9);
+    assert_close(&description.variance, &[covariance[0], covariance[4], covariance[8]], 1e-9);
+    assert_close(&service.variance(&data).unwrap(), &description.variance, 1e-9);
+    assert!((service.second_moment(&data, 0, 2).unwrap() - (4.65 - 0.28 - 7.26)).abs() < 1e-9);
+
+    // the number of rows is not bounded by the dimension of the keys
+    let many: Vec<Vec<f64>> = (0..20).map(|i| vec![(i % 5) as f64 - 2.0, 0.5, (i % 3) as f64]).collect();
+    let data = service.client().encrypt(&many).unwrap();
+    assert_eq!(data.rows(), 20);
+    let (mean, covariance) = plain_covariance(&many);
+    assert_close(&service.mean(&data).unwrap(), &mean, 1e-9);
+    assert_close(&service.covariance(&data).unwrap(), &covariance, 1e-9);
+}
+
+#[test]
+fn test_statistics_errors() {
+    const L: usize = 2;
+    let service = StatisticsService::<L>::new(10, 2.0);
+    let client = service.client();
+    assert!(matches!(client.encrypt(&[]), Err(ModelError::Empty)));
+    assert_eq!(
+        client.encrypt(&[vec![1.0, 1.0], vec![1.0]]).err(),
+        Some(ModelError::FeatureMismatch { expected: 2, actual: 1 })
+    );
+    assert_eq!(
+        client.encrypt(&[vec![1.0, 2.5], vec![0.0, 0.0]]).err(),
+        Some(ModelError::InputOutOfBound { bound: BigInt::from(20) })
+    );
+
+    let data = client.encrypt(&[vec![1.0, -1.0]]).unwrap();
+    assert_close(&service.mean(&data).unwrap(), &[1.0, -1.0], 1e-9);
+    assert!(matches!(service.covariance(&data), Err(ModelError::Empty)));
+    assert!(matches!(service.second_moment(&data, 0, 2), Err(ModelError::FeatureMismatch { expected: 2, actual: 3 })));
+}
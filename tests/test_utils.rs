@@ -4,7 +4,7 @@ use num_traits::Num;
 use miracl_core::bls12381::pair;
 use ruby::define::{BigNum, G1, G2};
 use ruby::utils::{baby_step_giant_step, baby_step_giant_step_g1};
-use ruby::utils::rand_utils::{RandUtilsRAND, RandUtilsRng, Sample};
+use ruby::utils::rand_utils::{RandUtilsRand, RandUtilsRng, Sample};
 use ruby::define::{CURVE_ORDER};
 
 #[cfg(test)]
@@ -75,7 +75,7 @@ mod tests {
 
     #[test]
     fn test_rand_utils_rand () {
-        let mut rand_utils = RandUtilsRAND::new();
+        let mut rand_utils = RandUtilsRand::new();
         println!("next big int mod: {:?}", rand_utils.sample(&BigNum::new_int(1000)));
         println!("next big int range: {:?}", rand_utils.sample_range(&BigNum::new_int(50), &BigNum::new_int(100)));
         println!("next big int mod vec: {:?}", rand_utils.sample_vec(5, &BigNum::new_int(1000)));
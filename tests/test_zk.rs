@@ -25,11 +25,13 @@ use num_bigint::ToBigInt;
 use ruby::math::matrix::{BigIntMatrix};
 use ruby::utils::{quadratic_result, reduce};
 use ruby::zk::dlog::{ZkDlog};
-use ruby::zk::qp::{ZkQp};
-use ruby::zk::sip::{ZkSip};
+use ruby::zk::commitment::FunctionOpening;
+use ruby::zk::qp::{ZkQp, ZkQpFunction};
+use ruby::zk::sip::{ZkSip, ZkSipFunction};
 use std::time::Instant;
-use ruby::zk::qp::{QPProofSecret, QPProofPublic, CQPProofSecret, CQPProofPublic};
-use ruby::zk::ToEncoding;
+use ruby::zk::qp::{QpProofSecret, QpProofPublic, CqpProofSecret, CqpProofPublic};
+use ruby::zk::backend::Groth16;
+use ruby::zk::{CircuitCost, ParamsError, ProveError, SnarkInfo, ToEncoding, VerifyError, Zk, ZkParams};
 
 pub type Bn256Fr = fawkes_crypto::engines::bn256::Fr;
 pub type Bn12381Fr = fawkes_crypto::engines::bls12_381::Fr;
@@ -157,7 +159,7 @@ fn test_circuit_qp () {
 
     const L: usize = 1; 
 
-    fn circuit<C:CS<Fr = Fr>>(public: CQPProofPublic<C, L>, secret: CQPProofSecret<C, L>) {
+    fn circuit<C:CS<Fr = Fr>>(public: CqpProofPublic<C, L>, secret: CqpProofSecret<C, L>) {
         let jubjub_params = JJParams::new();
         let cs = secret.get_cs();
 
@@ -222,7 +224,7 @@ fn test_circuit_qp () {
         .map(|ti| g1.mul(ti.to_other_reduced(), &jubjub_params))
         .collect::<SizedVec<_, L>>();
 
-    let qp_proof_public = QPProofPublic {
+    let qp_proof_public = QpProofPublic {
         g1,
         h1,
         c1,
@@ -230,7 +232,7 @@ fn test_circuit_qp () {
         c3,
         c4
     };
-    let qp_proof_secret = QPProofSecret {
+    let qp_proof_secret = QpProofSecret {
         r,
         f_st,
         s,
@@ -253,7 +255,7 @@ fn test_zk_discrete_log() {
             .mul(Num::from(8), &jubjub_params);
     let x: Num<Bn256Fr> = rng.gen();
 
-    let snark = ZkDlog::generate(&g, &x);
+    let snark = ZkDlog::<Bn256>::generate(&g, &x);
 
     let res = verifier::verify(&snark.vk, &snark.proof, &snark.inputs);
     assert!(res, "Verifier result should be true");
@@ -338,3 +340,305 @@ fn test_zk_simple_inner_product_zk() {
 }
 
 
+
+#[test]
+fn test_zk_verify() {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+
+    let g = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let x: Num<Bn256Fr> = rng.gen();
+    let y = g.mul(x.to_other_reduced(), &jubjub_params);
+
+    let dlog_params = ZkDlog::<Bn256>::setup();
+    let dlog = ZkDlog::<Bn256>::generate_with(&dlog_params, &g, &x).unwrap();
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &dlog), Ok(()));
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &g, &dlog), Err(VerifyError::InputMismatch { input: "y".to_string() }));
+    // the key of another setup, e.g. one chosen by the prover, rejects the proof
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&ZkDlog::<Bn256>::setup().vk(), &g, &y, &dlog), Err(VerifyError::InvalidProof));
+
+    let sk: Num<Bn256Fr> = rng.gen();
+    let h = g.mul(sk.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let t: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+
+    let sip_params = ZkSip::<N>::setup();
+    let (sip_public, sip) = ZkSip::<N>::prove_with(&sip_params, &g, &h, &s, &t).unwrap();
+    assert_eq!(ZkSip::<N>::verify_with(&sip_params.vk(), &sip_public, &sip), Ok(()));
+    let mut tampered = sip_public.clone();
+    tampered.v[1] = h;
+    assert_eq!(ZkSip::<N>::verify_with(&sip_params.vk(), &tampered, &sip), Err(VerifyError::InputMismatch { input: "v[1]".to_string() }));
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &sip), Err(VerifyError::InputLength { expected: 4, actual: 12 }));
+
+    let bigint_f = BigIntMatrix::new_random(N, N, &BigInt::from(-64), &BigInt::from(64));
+    let qp_params = ZkQp::<N>::setup();
+    let (qp_public, qp) = ZkQp::<N>::prove_with(&qp_params, &g, &h, &s, &t, &bigint_f).unwrap();
+    assert_eq!(ZkQp::<N>::verify_with(&qp_params.vk(), &qp_public, &qp), Ok(()));
+    let mut tampered = qp_public.clone();
+    tampered.c1 = tampered.c2;
+    assert_eq!(ZkQp::<N>::verify_with(&qp_params.vk(), &tampered, &qp), Err(VerifyError::InputMismatch { input: "c1".to_string() }));
+
+    // matching public inputs with the proof of another statement
+    let other = ZkDlog::<Bn256>::generate_with(&dlog_params, &g, &sk).unwrap();
+    let forged = SnarkInfo { inputs: dlog.inputs.clone(), proof: other.proof, vk: dlog.vk };
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &forged), Err(VerifyError::InvalidProof));
+}
+
+#[test]
+fn test_zk_params() {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+
+    let g = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let sk: Num<Bn256Fr> = rng.gen();
+    let h = g.mul(sk.to_other_reduced(), &jubjub_params);
+
+    let path = std::env::temp_dir().join(format!("ruby_zk_params_{}", std::process::id()));
+    ZkSip::<N>::setup().save(&path).unwrap();
+    let params = ZkParams::<Bn256>::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(params.circuit, "sip/2");
+    let vk = params.vk();
+
+    // many proofs against the same pinned verifying key
+    for _ in 0..2 {
+        let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+        let y: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+        let (public, snark) = ZkSip::<N>::prove_with(&params, &g, &h, &s, &y).unwrap();
+        assert_eq!(snark.vk.encode(), vk.encode());
+        assert_eq!(ZkSip::<N>::verify_with(&vk, &public, &snark), Ok(()));
+    }
+
+    let bigint_f = BigIntMatrix::new_random(N, N, &BigInt::from(-64), &BigInt::from(64));
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    assert_eq!(
+        ZkQp::<N>::prove_with(&params, &g, &h, &s, &s, &bigint_f).err(),
+        Some(ParamsError::WrongCircuit { expected: "qp/2".to_string(), actual: "sip/2".to_string() })
+    );
+    assert!(matches!(ZkParams::<Bn256>::from_bytes(&params.to_bytes()[..100]), Err(ParamsError::Io(_))));
+
+    // a proof from another setup does not verify against the pinned key
+    let dlog_params = ZkDlog::<Bn256>::setup();
+    let y = g.mul(sk.to_other_reduced(), &jubjub_params);
+    let snark = ZkDlog::<Bn256>::generate(&g, &sk);
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&snark.vk, &g, &y, &snark), Ok(()));
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &snark), Err(VerifyError::InvalidProof));
+    let snark = ZkDlog::<Bn256>::generate_with(&dlog_params, &g, &sk).unwrap();
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &snark), Ok(()));
+}
+
+/// Prove and verify the dlog, Sip and Qp circuits on the engine `Z`.
+fn check_circuits_on<Z: Zk>() {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = Z::jubjub_params();
+
+    let g = EdwardsPoint::<Z::Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let x: Num<Z::Fr> = rng.gen();
+    let y = g.mul(x.to_other_reduced(), &jubjub_params);
+    let dlog_params = ZkDlog::<Z>::setup();
+    let dlog = ZkDlog::<Z>::generate_with(&dlog_params, &g, &x).unwrap();
+    assert_eq!(ZkDlog::<Z>::verify_with(&dlog_params.vk(), &g, &y, &dlog), Ok(()));
+    assert_eq!(ZkDlog::<Z>::verify_with(&dlog_params.vk(), &g, &g, &dlog), Err(VerifyError::InputMismatch { input: "y".to_string() }));
+
+    let sk: Num<Z::Fr> = rng.gen();
+    let h = g.mul(sk.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Z::Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let t: SizedVec<Num<Z::Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let sip_params = ZkSip::<N, Z>::setup();
+    let (sip_public, sip) = ZkSip::<N, Z>::prove_with(&sip_params, &g, &h, &s, &t).unwrap();
+    assert_eq!(ZkSip::<N, Z>::verify_with(&sip_params.vk(), &sip_public, &sip), Ok(()));
+
+    let bigint_f = BigIntMatrix::new_random(N, N, &BigInt::from(-64), &BigInt::from(64));
+    let qp_params = ZkQp::<N, Z>::setup();
+    let (qp_public, qp) = ZkQp::<N, Z>::prove_with(&qp_params, &g, &h, &s, &t, &bigint_f).unwrap();
+    assert_eq!(ZkQp::<N, Z>::verify_with(&qp_params.vk(), &qp_public, &qp), Ok(()));
+    let mut tampered = qp_public.clone();
+    tampered.c2 = tampered.c1;
+    assert_eq!(ZkQp::<N, Z>::verify_with(&qp_params.vk(), &tampered, &qp), Err(VerifyError::InputMismatch { input: "c2".to_string() }));
+}
+
+#[test]
+fn test_zk_circuits_bn256() {
+    check_circuits_on::<Bn256>();
+}
+
+#[test]
+fn test_zk_circuits_bls12381() {
+    check_circuits_on::<Bls12_381>();
+}
+
+#[test]
+fn test_zk_backend() {
+    const N: usize = 1;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+    let g = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let x: Num<Bn256Fr> = rng.gen();
+    let y = g.mul(x.to_other_reduced(), &jubjub_params);
+
+    let dlog_params = ZkDlog::<Bn256>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+    let dlog = ZkDlog::<Bn256>::generate_on(&dlog_params, &g, &x).unwrap();
+    assert_eq!(ZkDlog::<Bn256>::verify_on::<Groth16<Bn256>>(&dlog_params.vk(), &g, &y, &dlog), Ok(()));
+    assert_eq!(
+        ZkDlog::<Bn256>::verify_on::<Groth16<Bn256>>(&dlog_params.vk(), &g, &g, &dlog),
+        Err(VerifyError::InputMismatch { input: "y".to_string() })
+    );
+    // the Groth16 backend is the one of `ZkParams` and `SnarkInfo`
+    let snark = dlog.with_vk(dlog_params.vk());
+    assert_eq!(ZkDlog::<Bn256>::verify_with(&dlog_params.vk(), &g, &y, &snark), Ok(()));
+
+    let h = g.mul(x.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let t: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let sip_params = ZkSip::<N>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+    let (sip_public, sip) = ZkSip::<N>::prove_on(&sip_params, &g, &h, &s, &t).unwrap();
+    assert_eq!(ZkSip::<N>::verify_on::<Groth16<Bn256>>(&sip_params.vk(), &sip_public, &sip), Ok(()));
+    assert_eq!(
+        ZkSip::<N>::verify_on::<Groth16<Bn256>>(&ZkSip::<N>::setup_on::<Groth16<Bn256>>(&()).unwrap().vk(), &sip_public, &sip),
+        Err(VerifyError::InvalidProof)
+    );
+    assert_eq!(
+        ZkDlog::<Bn256>::generate_on(&sip_params, &g, &x).err(),
+        Some(ParamsError::WrongCircuit { expected: "dlog".to_string(), actual: "sip/1".to_string() })
+    );
+
+    let bigint_f = BigIntMatrix::new_ints(&[5][..], N, N);
+    let qp_params = ZkQp::<N>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+    let (qp_public, qp) = ZkQp::<N>::prove_on(&qp_params, &g, &h, &s, &t, &bigint_f).unwrap();
+    assert_eq!(ZkQp::<N>::verify_on::<Groth16<Bn256>>(&qp_params.vk(), &qp_public, &qp), Ok(()));
+
+    let sip_function_params = ZkSipFunction::<N>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+    let (public, snark) = ZkSipFunction::<N>::prove_on(&sip_function_params, &g, &h, &s, &FunctionOpening::vector(&t[..])).unwrap();
+    assert_eq!(ZkSipFunction::<N>::verify_on::<Groth16<Bn256>>(&sip_function_params.vk(), &public, &snark), Ok(()));
+    let snark = snark.with_vk(sip_function_params.vk());
+    assert_eq!(ZkSipFunction::<N>::verify_with(&sip_function_params.vk(), &public, &snark), Ok(()));
+
+    let qp_function_params = ZkQpFunction::<N>::setup_on::<Groth16<Bn256>>(&()).unwrap();
+    let (public, snark) = ZkQpFunction::<N>::prove_on(&qp_function_params, &g, &h, &s, &t, &FunctionOpening::matrix(&bigint_f)).unwrap();
+    assert_eq!(ZkQpFunction::<N>::verify_on::<Groth16<Bn256>>(&qp_function_params.vk(), &public, &snark), Ok(()));
+    assert_eq!(
+        ZkQpFunction::<N>::prove_on(&qp_function_params, &g, &h, &s, &t, &FunctionOpening::vector(&[t[0], t[0]])).err(),
+        Some(ProveError::FunctionLength { expected: 1, actual: 2 })
+    );
+}
+
+#[test]
+fn test_zk_sip_function() {
+    const N: usize = 3;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+    let g = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let sk: Num<Bn256Fr> = rng.gen();
+    let h = g.mul(sk.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let y: Vec<Num<Bn256Fr>> = (0..N).map(|_| rng.gen()).collect();
+
+    // the data owner publishes the commitment of the approved function
+    let opening = FunctionOpening::vector(&y);
+    let commitment = opening.commitment();
+    let params = ZkSipFunction::<N>::setup();
+    let (public, snark) = ZkSipFunction::<N>::prove_with(&params, &g, &h, &s, &opening).unwrap();
+    assert_eq!(public.commitment, commitment);
+    assert_eq!(ZkSipFunction::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+
+    // a key for another function does not match the commitment
+    let other = FunctionOpening::vector(&y[..].iter().rev().cloned().collect::<Vec<_>>());
+    let (other_public, other_snark) = ZkSipFunction::<N>::prove_with(&params, &g, &h, &s, &other).unwrap();
+    assert_eq!(ZkSipFunction::<N>::verify_with(&params.vk(), &other_public, &other_snark), Ok(()));
+    assert_ne!(other_public.commitment, commitment);
+    let mut claimed = other_public.clone();
+    claimed.commitment = commitment;
+    assert_eq!(
+        ZkSipFunction::<N>::verify_with(&params.vk(), &claimed, &other_snark),
+        Err(VerifyError::InputMismatch { input: "commitment".to_string() })
+    );
+    assert_eq!(
+        ZkSipFunction::<N>::prove_with(&ZkSip::<N>::setup(), &g, &h, &s, &opening).err(),
+        Some(ProveError::Params(ParamsError::WrongCircuit { expected: "sip-function/3".to_string(), actual: "sip/3".to_string() }))
+    );
+    assert_eq!(
+        ZkSipFunction::<N>::prove_with(&params, &g, &h, &s, &FunctionOpening::vector(&y[..2])).err(),
+        Some(ProveError::FunctionLength { expected: 3, actual: 2 })
+    );
+}
+
+#[test]
+fn test_zk_qp_function() {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+    let g1 = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let sk: Num<Bn256Fr> = rng.gen();
+    let h1 = g1.mul(sk.to_other_reduced(), &jubjub_params);
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let t: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let bigint_f = BigIntMatrix::new_ints(&[3, -1, 0, -7][..], N, N);
+
+    let opening = FunctionOpening::matrix(&bigint_f);
+    let params = ZkQpFunction::<N>::setup();
+    let (public, snark) = ZkQpFunction::<N>::prove_with(&params, &g1, &h1, &s, &t, &opening).unwrap();
+    assert_eq!(public.commitment, opening.commitment());
+    assert_eq!(ZkQpFunction::<N>::verify_with(&params.vk(), &public, &snark), Ok(()));
+    // the statement is the one of `ZkQp` for the same key
+    for i in 0..N {
+        assert_eq!(public.statement.c3[i], g1.mul(s[i].to_other_reduced(), &jubjub_params));
+    }
+
+    let mut claimed = public.clone();
+    claimed.commitment = FunctionOpening::matrix(&BigIntMatrix::new_ints(&[3, -1, 0, 7][..], N, N)).commitment();
+    assert_eq!(
+        ZkQpFunction::<N>::verify_with(&params.vk(), &claimed, &snark),
+        Err(VerifyError::InputMismatch { input: "commitment".to_string() })
+    );
+    assert_eq!(
+        ZkQp::<N>::verify_with(&params.vk(), &public.statement, &snark),
+        Err(VerifyError::InputLength { expected: 16, actual: 17 })
+    );
+}
+
+#[test]
+fn test_zk_qp_function_wrong_dimension() {
+    const N: usize = 2;
+    let mut rng = thread_rng();
+    let jubjub_params = JubJubBN256::new();
+    let g1 = EdwardsPoint::<Bn256Fr>::rand(&mut rng, &jubjub_params)
+        .mul(Num::from(8), &jubjub_params);
+    let s: SizedVec<Num<Bn256Fr>, N> = (0..N).map(|_| rng.gen()).collect();
+    let opening = FunctionOpening::matrix(&BigIntMatrix::new_ints(&[1, 2, 3][..], 1, 3));
+    assert_eq!(
+        ZkQpFunction::<N>::prove_with(&ZkQpFunction::<N>::setup(), &g1, &g1, &s, &s, &opening).err(),
+        Some(ProveError::FunctionLength { expected: 4, actual: 3 })
+    );
+}
+
+#[test]
+fn test_zk_circuit_cost() {
+    let cost = |constraints, public_inputs, witness| CircuitCost { constraints, public_inputs, witness };
+
+    assert_eq!(ZkDlog::<Bn256>::cost(), cost(2812, 4, 2808));
+    assert_eq!(ZkDlog::<Bls12_381>::cost(), cost(2823, 4, 2819));
+
+    // one scalar multiplication, i.e. 2811 constraints, per entry of the vectors
+    assert_eq!(ZkSip::<1>::cost(), cost(10737, 10, 10725));
+    assert_eq!(ZkSip::<2>::cost(), cost(13548, 12, 13533));
+    assert_eq!(ZkSip::<3>::cost(), cost(16359, 14, 16341));
+    assert_eq!(ZkSip::<3, Bls12_381>::cost(), cost(16423, 14, 16405));
+    assert_eq!(ZkQp::<1>::cost(), cost(13546, 12, 13530));
+    assert_eq!(ZkQp::<2>::cost(), cost(19166, 16, 19142));
+    assert_eq!(ZkQp::<3>::cost(), cost(24786, 20, 24754));
+
+    assert_eq!(ZkSipFunction::<3>::cost(), cost(17048, 15, 17030));
+    assert_eq!(ZkQpFunction::<2>::cost(), cost(19862, 17, 19841));
+
+    // the public inputs are the ones checked by `verify_with`
+    assert_eq!(ZkDlog::<Bn256>::setup().vk().ic.len(), ZkDlog::<Bn256>::cost().public_inputs + 1);
+}